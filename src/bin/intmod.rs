@@ -4,6 +4,8 @@ use clap::{Parser, Subcommand};
 use env_logger::Env;
 
 use intermodal_rs::cmd::image::{self, ImageCommands};
+use intermodal_rs::cmd::login::{self, LoginArgs, LogoutArgs};
+use intermodal_rs::cmd::registry::{self, RegistryCommands};
 use intermodal_rs::image::transports;
 
 #[derive(Debug, Parser)]
@@ -25,6 +27,19 @@ enum Commands {
         #[command(subcommand)]
         image_commands: ImageCommands,
     },
+
+    /// Login to a container registry.
+    Login(LoginArgs),
+
+    /// Logout from a container registry.
+    Logout(LogoutArgs),
+
+    /// Handle container registries.
+    #[command(arg_required_else_help = true)]
+    Registry {
+        #[command(subcommand)]
+        registry_commands: RegistryCommands,
+    },
 }
 
 #[tokio::main(flavor = "current_thread")]
@@ -45,5 +60,10 @@ async fn main() -> io::Result<()> {
 
     match cli.commands {
         Commands::Image { image_commands } => image::run_subcmd_image(image_commands).await,
+        Commands::Login(args) => login::run_cmd_login(args).await,
+        Commands::Logout(args) => login::run_cmd_logout(args),
+        Commands::Registry { registry_commands } => {
+            registry::run_subcmd_registry(registry_commands).await
+        }
     }
 }
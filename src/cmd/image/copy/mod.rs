@@ -0,0 +1,25 @@
+//! Handling of 'copy' subcommand of 'image' command
+
+use std::io;
+
+use crate::cmd::image::ImageCommands;
+use crate::image::api::{copy_image, CopyOptions};
+
+/// API to run 'copy' subcommand
+pub async fn run_subcmd_copy(subcmd: ImageCommands) -> io::Result<()> {
+    if let ImageCommands::Copy {
+        ref src,
+        ref dest,
+        all,
+    } = subcmd
+    {
+        let options = CopyOptions {
+            all,
+            ..Default::default()
+        };
+
+        copy_image(src, dest, &options).await
+    } else {
+        Ok(())
+    }
+}
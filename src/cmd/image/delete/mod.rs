@@ -0,0 +1,16 @@
+//! Handling of 'delete' subcommand of 'image' command
+
+use std::io;
+
+use crate::cmd::image::ImageCommands;
+use crate::image::api::delete_image;
+
+/// API to run 'delete' subcommand
+pub async fn run_subcmd_delete(subcmd: ImageCommands) -> io::Result<()> {
+    if let ImageCommands::Delete { ref name } = subcmd {
+        let digest = delete_image(name).await?;
+        println!("Deleted: {}", digest);
+    }
+
+    Ok(())
+}
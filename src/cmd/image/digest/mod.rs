@@ -0,0 +1,18 @@
+//! Handling of 'digest' subcommand of 'image' command
+
+use std::io;
+
+use crate::cmd::image::ImageCommands;
+use crate::image::api::resolve_digest;
+
+/// API to run 'digest' subcommand
+pub async fn run_subcmd_digest(subcmd: ImageCommands) -> io::Result<()> {
+    if let ImageCommands::Digest { ref name } = subcmd {
+        let (resolved_name, digest) = resolve_digest(name).await?;
+
+        // Print the fully resolved name (eg. 'docker.io/library/fedora@sha256:...').
+        println!("{}@{}", resolved_name, digest);
+    }
+
+    Ok(())
+}
@@ -96,9 +96,9 @@ pub async fn run_subcmd_inspect(cmd: ImageCommands) -> io::Result<()> {
 
                     let mut reference_name: Option<String> = None;
                     let mut reference_tag: Option<String> = None;
-                    if docker_ref.is_some() {
-                        reference_name = Some(docker_ref.as_ref().unwrap().name());
-                        reference_tag = Some(docker_ref.as_ref().unwrap().tag());
+                    if let Some(docker_ref) = &docker_ref {
+                        reference_name = Some(docker_ref.name());
+                        reference_tag = Some(docker_ref.tag());
                     }
 
                     let output = InspectOutput {
@@ -1,9 +1,13 @@
 use clap::Subcommand;
 
 pub mod cache;
+pub mod copy;
+pub mod delete;
+pub mod digest;
 pub mod inspect;
 //pub mod mount;
 pub mod pull;
+pub mod referrers;
 
 #[derive(Debug, Subcommand)]
 pub enum ImageCommands {
@@ -36,6 +40,58 @@ pub enum ImageCommands {
         clean_on_err: bool,
     },
 
+    /// Copy Container Image from the source to the destination.
+    #[command(arg_required_else_help = true)]
+    Copy {
+        #[arg(long, help = "Source Image Name (eg. 'docker://fedora:latest').")]
+        src: String,
+
+        #[arg(
+            long,
+            help = "Destination Image Name (eg. 'oci:/path/to/layout:latest')."
+        )]
+        dest: String,
+
+        #[arg(
+            long,
+            help = "Copy all the images in a manifest list, instead of only the image for current OS/Architecture."
+        )]
+        all: bool,
+    },
+
+    /// Delete Container Image (manifest and all it's tags) from the registry.
+    #[command(arg_required_else_help = true)]
+    Delete {
+        #[arg(
+            long,
+            help = "Image Name to delete (eg. 'docker://registry.example.com/app:ci-123')."
+        )]
+        name: String,
+    },
+
+    /// Print the pinned reference (with the manifest digest) of a Container Image.
+    #[command(arg_required_else_help = true)]
+    Digest {
+        #[arg(long, help = "Image Name to resolve (eg. 'docker://fedora:latest').")]
+        name: String,
+    },
+
+    /// List the artifacts (eg. SBOMs and signatures) attached to a Container Image.
+    #[command(arg_required_else_help = true)]
+    Referrers {
+        #[arg(
+            long,
+            help = "Image Name to list the artifacts for (eg. 'docker://registry.example.com/app:v1')."
+        )]
+        name: String,
+
+        #[arg(
+            long = "artifact-type",
+            help = "List the artifacts of this type only (eg. 'application/spdx+json')."
+        )]
+        artifact_type: Option<String>,
+    },
+
     /// Clear local cache of saved image blobs.
     #[command(name = "clear-blob-cache")]
     ClearCache,
@@ -45,6 +101,10 @@ pub async fn run_subcmd_image(cmd: ImageCommands) -> std::io::Result<()> {
     match cmd {
         ImageCommands::Inspect { .. } => inspect::run_subcmd_inspect(cmd).await,
         ImageCommands::Pull { .. } => pull::run_subcmd_pull(cmd).await,
+        ImageCommands::Copy { .. } => copy::run_subcmd_copy(cmd).await,
+        ImageCommands::Delete { .. } => delete::run_subcmd_delete(cmd).await,
+        ImageCommands::Digest { .. } => digest::run_subcmd_digest(cmd).await,
+        ImageCommands::Referrers { .. } => referrers::run_subcmd_referrers(cmd).await,
         ImageCommands::ClearCache => cache::run_subcmd_clear_cache(),
     }
 }
@@ -0,0 +1,29 @@
+//! Handling of 'referrers' subcommand of 'image' command
+
+use std::io;
+
+use crate::cmd::image::ImageCommands;
+use crate::image::api::list_referrers;
+
+/// API to run 'referrers' subcommand
+pub async fn run_subcmd_referrers(subcmd: ImageCommands) -> io::Result<()> {
+    if let ImageCommands::Referrers {
+        ref name,
+        ref artifact_type,
+    } = subcmd
+    {
+        let referrers = list_referrers(name, artifact_type.as_deref()).await?;
+
+        // The artifact type (or the media type if not an artifact) followed by the digest.
+        for referrer in referrers {
+            let kind = referrer
+                .artifact_type
+                .as_deref()
+                .or(referrer.mediatype.as_deref())
+                .unwrap_or("-");
+            println!("{}\t{}", kind, referrer.digest);
+        }
+    }
+
+    Ok(())
+}
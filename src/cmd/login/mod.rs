@@ -0,0 +1,85 @@
+//! Handling of 'login' and 'logout' commands
+
+use std::io::{self, BufRead};
+use std::path::PathBuf;
+
+use clap::Args;
+
+use crate::image::api::{registry_login, registry_logout};
+
+/// Arguments for the 'login' command.
+#[derive(Debug, Args)]
+pub struct LoginArgs {
+    #[arg(
+        help = "Registry to login to (eg. 'quay.io').",
+        default_value = "docker.io"
+    )]
+    registry: String,
+
+    #[arg(long, short, help = "Username for the Registry.")]
+    username: String,
+
+    #[arg(
+        long,
+        short,
+        help = "Password for the Registry.",
+        conflicts_with = "password_stdin"
+    )]
+    password: Option<String>,
+
+    #[arg(long = "password-stdin", help = "Read the password from stdin.")]
+    password_stdin: bool,
+
+    #[arg(
+        long = "authfile",
+        help = "Path of the auth file (default: '$REGISTRY_AUTH_FILE' or '~/.docker/config.json')."
+    )]
+    auth_file: Option<PathBuf>,
+}
+
+/// Arguments for the 'logout' command.
+#[derive(Debug, Args)]
+pub struct LogoutArgs {
+    #[arg(
+        help = "Registry to logout from (eg. 'quay.io').",
+        default_value = "docker.io"
+    )]
+    registry: String,
+
+    #[arg(
+        long = "authfile",
+        help = "Path of the auth file (default: '$REGISTRY_AUTH_FILE' or '~/.docker/config.json')."
+    )]
+    auth_file: Option<PathBuf>,
+}
+
+/// API to run 'login' command
+pub async fn run_cmd_login(args: LoginArgs) -> io::Result<()> {
+    let password = match args.password {
+        Some(password) => password,
+        None if args.password_stdin => {
+            let mut password = String::new();
+            io::stdin().lock().read_line(&mut password)?;
+            password.trim_end_matches(&['\r', '\n'][..]).to_string()
+        }
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "One of '--password' or '--password-stdin' is required.",
+            ))
+        }
+    };
+
+    registry_login(
+        &args.registry,
+        &args.username,
+        &password,
+        args.auth_file.as_deref(),
+    )
+    .await
+}
+
+/// API to run 'logout' command
+pub fn run_cmd_logout(args: LogoutArgs) -> io::Result<()> {
+    registry_logout(&args.registry, args.auth_file.as_deref())
+}
@@ -2,3 +2,5 @@
 
 pub(crate) mod errors;
 pub mod image;
+pub mod login;
+pub mod registry;
@@ -0,0 +1,48 @@
+//! Handling of 'registry' command
+
+use std::io;
+
+use clap::Subcommand;
+
+use crate::image::api::registry_catalog;
+
+#[derive(Debug, Subcommand)]
+pub enum RegistryCommands {
+    /// List the repositories in the Registry (using the '/v2/_catalog' API).
+    #[command(name = "ls", arg_required_else_help = true)]
+    List {
+        #[arg(help = "Registry to list the repositories of (eg. 'registry.example.com:5000').")]
+        registry: String,
+    },
+}
+
+pub async fn run_subcmd_registry(cmd: RegistryCommands) -> io::Result<()> {
+    match cmd {
+        RegistryCommands::List { registry } => {
+            for repository in registry_catalog(&registry).await? {
+                println!("{}", repository);
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_list_invalid_registry() {
+        let cmd = RegistryCommands::List {
+            registry: "bad host".to_string(),
+        };
+
+        let result = run_subcmd_registry(cmd).await;
+        assert!(result.is_err());
+        assert!(
+            result.unwrap_err().to_string().contains("Invalid Registry"),
+            "Expected an Invalid Registry error."
+        );
+    }
+}
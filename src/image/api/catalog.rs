@@ -0,0 +1,90 @@
+//! Registry 'catalog' related APIs
+
+use std::io;
+
+use crate::image::docker::catalog;
+
+/// Returns the names of all the repositories in the Registry.
+///
+/// The Registry should support the `/v2/_catalog` API. If credentials for the Registry are found
+/// in the auth files, they are used.
+///
+/// # Example:
+///
+/// ```rust,no_run
+/// # use intermodal_rs::image::api::registry_catalog;
+///
+/// #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let repositories = registry_catalog("registry.example.com:5000").await.unwrap();
+///
+/// for repository in repositories {
+///     println!("{}", repository);
+/// }
+/// # }
+/// ```
+pub async fn registry_catalog(registry: &str) -> io::Result<Vec<String>> {
+    Ok(catalog::get_catalog(registry).await?)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_registry_catalog() {
+        let server = MockServer::start().await;
+
+        let challenge = format!(
+            "Bearer realm=\"{}/token\",service=\"test-registry\"",
+            server.uri()
+        );
+        Mock::given(method("GET"))
+            .and(path("/v2/"))
+            .respond_with(
+                ResponseTemplate::new(401).insert_header("WWW-Authenticate", challenge.as_str()),
+            )
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .and(query_param("scope", "registry:catalog:*"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"token": "catalog"}"#))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/v2/_catalog"))
+            .and(header("Authorization", "Bearer catalog"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Link", r#"</v2/_catalog?last=library/fedora>; rel="next""#)
+                    .set_body_string(r#"{"repositories": ["library/alpine", "library/fedora"]}"#),
+            )
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/v2/_catalog"))
+            .and(query_param("last", "library/fedora"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_string(r#"{"repositories": ["team/app"]}"#),
+            )
+            .with_priority(1)
+            .mount(&server)
+            .await;
+
+        let repositories = registry_catalog(&server.address().to_string()).await;
+        assert!(repositories.is_ok(), "{:?}", repositories);
+        assert_eq!(
+            repositories.unwrap(),
+            vec!["library/alpine", "library/fedora", "team/app"]
+        );
+    }
+}
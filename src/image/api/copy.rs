@@ -0,0 +1,245 @@
+//! Image 'copy' related APIs and internal functions
+
+use std::convert::TryFrom;
+use std::io;
+
+use futures_util::{stream, TryStreamExt};
+
+use crate::image::{
+    docker::{MEDIA_TYPE_DOCKER_V2_LIST, MEDIA_TYPE_DOCKER_V2_SCHEMA2_MANIFEST},
+    oci::spec_v1::{
+        Descriptor, Index, Manifest, MEDIA_TYPE_IMAGE_INDEX, MEDIA_TYPE_IMAGE_MANIFEST,
+    },
+    transports,
+    types::{ImageDestination, ImageManifest, ImageSource},
+};
+
+/// Options for Copying the Images.
+#[derive(Debug, Clone)]
+pub struct CopyOptions {
+    /// Copy all the images in a manifest 'list' (or 'index'). By default only the image matching
+    /// current OS/Architecture is copied.
+    pub all: bool,
+
+    /// Maximum number of blobs copied in parallel.
+    pub max_parallel_copies: usize,
+}
+
+impl Default for CopyOptions {
+    fn default() -> Self {
+        CopyOptions {
+            all: false,
+            max_parallel_copies: 3,
+        }
+    }
+}
+
+/// Copies a container image from the source reference to the destination reference.
+///
+/// The references are the image names including transport (eg. `docker://fedora:latest` or
+/// `oci:/path/to/layout:latest`). The config and layer blobs followed by the manifest(s) are
+/// copied from the source to the destination. The blobs that are already present at the
+/// destination are not copied.
+///
+/// # Example:
+///
+/// ```rust,no_run
+/// # use intermodal_rs::image::api::{copy_image, CopyOptions};
+///
+/// #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// # intermodal_rs::image::transports::init_transports();
+/// let result = copy_image(
+///     "docker://busybox:latest",
+///     "oci:/tmp/busybox:latest",
+///     &CopyOptions::default(),
+/// )
+/// .await;
+///
+/// assert!(result.is_ok())
+/// # }
+/// ```
+pub async fn copy_image(
+    src_reference: &str,
+    dst_reference: &str,
+    options: &CopyOptions,
+) -> io::Result<()> {
+    log::info!("Copying the image: {} to {}", src_reference, dst_reference);
+
+    let src_ref = transports::parse_image_name(src_reference)?;
+    let dst_ref = transports::parse_image_name(dst_reference)?;
+
+    let mut dest = dst_ref.new_image_destination()?;
+
+    let mut image = src_ref.new_image()?;
+    let manifest = if options.all {
+        image.manifest().await?
+    } else {
+        image.resolved_manifest().await?
+    };
+
+    match manifest.mime_type.as_str() {
+        MEDIA_TYPE_IMAGE_MANIFEST | MEDIA_TYPE_DOCKER_V2_SCHEMA2_MANIFEST => {
+            copy_single_image(image.source_ref(), dest.as_ref(), &manifest, options).await?;
+        }
+        MEDIA_TYPE_IMAGE_INDEX | MEDIA_TYPE_DOCKER_V2_LIST => {
+            if !dest.supports_digests() {
+                let errstr = format!(
+                    "Destination '{}' does not support copying all the images in a list.",
+                    dst_reference
+                );
+                log::error!("{}", errstr);
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, errstr));
+            }
+
+            let index: Index = serde_json::from_slice(&manifest.manifest)?;
+            for m in index.manifests {
+                log::debug!("Copying the image for Instance: {}", m.digest);
+                let instance = image.source_mut().get_manifest(Some(&m.digest)).await?;
+
+                copy_single_image(image.source_ref(), dest.as_ref(), &instance, options).await?;
+                dest.put_manifest(&instance, Some(&m.digest)).await?;
+            }
+        }
+        mime_type => {
+            let errstr = format!(
+                "Media Type: {} found. Can't Copy Image with this Media Type.",
+                mime_type
+            );
+            log::error!("{}", errstr);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, errstr));
+        }
+    }
+
+    log::debug!("Putting the Manifest.");
+    dest.put_manifest(&manifest, None).await?;
+
+    log::debug!("Committing the Image.");
+    dest.commit().await?;
+
+    log::info!("Image copied successfully!");
+    Ok(())
+}
+
+// Copies the config and layer blobs for the `manifest` of a single image.
+async fn copy_single_image(
+    source: &(dyn ImageSource + Send + Sync),
+    dest: &(dyn ImageDestination + Send + Sync),
+    manifest: &ImageManifest,
+    options: &CopyOptions,
+) -> io::Result<()> {
+    let manifest: Manifest = serde_json::from_slice(&manifest.manifest)?;
+
+    let blobs = std::iter::once(manifest.config).chain(manifest.layers);
+
+    stream::iter(blobs.map(Ok))
+        .try_for_each_concurrent(options.max_parallel_copies.max(1), |blob| {
+            copy_blob(source, dest, blob)
+        })
+        .await
+}
+
+async fn copy_blob(
+    source: &(dyn ImageSource + Send + Sync),
+    dest: &(dyn ImageDestination + Send + Sync),
+    blob: Descriptor,
+) -> io::Result<()> {
+    if dest.has_blob(&blob.digest).await? {
+        log::info!("Blob {} exists at the destination, skipping.", blob.digest);
+        return Ok(());
+    }
+
+    log::info!("Copying Blob: {}", blob.digest);
+    let reader = source.get_blob(&blob.digest).await?;
+    let size = u64::try_from(blob.size).ok();
+    dest.put_blob(reader, &blob.digest, size).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::image::oci::{
+        layout::OCIImageLayout,
+        testdata::{create_test_image_layout, TEST_LAYOUT_TAG},
+    };
+
+    async fn inspect_layers(reference: &str) -> Vec<String> {
+        let mut image = transports::parse_image_name(reference)
+            .unwrap()
+            .new_image()
+            .unwrap();
+
+        image.inspect().await.unwrap().layers
+    }
+
+    #[tokio::test]
+    async fn test_copy_oci_to_oci() {
+        transports::init_transports();
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let layout_path = create_test_image_layout(tempdir.path()).await;
+        let src_reference = format!("oci:{}:{}", layout_path.display(), TEST_LAYOUT_TAG);
+
+        let dst_path = tempdir.path().join("copied");
+        let dst_reference = format!("oci:{}:v1", dst_path.display());
+
+        let result = copy_image(&src_reference, &dst_reference, &CopyOptions::default()).await;
+        assert!(result.is_ok(), "{:?}", result);
+
+        // Copying again to a different tag, should add the tag to the same layout.
+        let dst_reference2 = format!("oci:{}:v2", dst_path.display());
+        let result = copy_image(&src_reference, &dst_reference2, &CopyOptions::default()).await;
+        assert!(result.is_ok(), "{:?}", result);
+
+        let layout = OCIImageLayout::open(&dst_path, None).unwrap();
+        let mut tags = layout.tags();
+        tags.sort();
+        assert_eq!(tags, vec!["v1", "v2"]);
+
+        assert_eq!(
+            inspect_layers(&src_reference).await,
+            inspect_layers(&dst_reference).await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_copy_oci_to_oci_archive() {
+        transports::init_transports();
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let layout_path = create_test_image_layout(tempdir.path()).await;
+        let src_reference = format!("oci:{}", layout_path.display());
+
+        let archive_path = tempdir.path().join("image.tar");
+        let dst_reference = format!("oci-archive:{}:copied", archive_path.display());
+
+        let result = copy_image(&src_reference, &dst_reference, &CopyOptions::default()).await;
+        assert!(result.is_ok(), "{:?}", result);
+
+        assert_eq!(
+            inspect_layers(&src_reference).await,
+            inspect_layers(&dst_reference).await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_copy_to_unsupported_destination() {
+        transports::init_transports();
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let layout_path = create_test_image_layout(tempdir.path()).await;
+        let src_reference = format!("oci:{}", layout_path.display());
+
+        let dst_reference = format!(
+            "docker-archive:{}",
+            tempdir.path().join("image.tar").display()
+        );
+
+        let result = copy_image(&src_reference, &dst_reference, &CopyOptions::default()).await;
+        assert!(result.is_err());
+    }
+}
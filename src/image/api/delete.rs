@@ -0,0 +1,33 @@
+//! Image 'delete' related APIs
+
+use std::io;
+
+use crate::image::{docker::registries::RegistriesConfig, oci::digest::Digest};
+
+use super::digest::docker_reference;
+
+/// Deletes the image for a 'docker' reference (eg. `docker://registry.example.com/app:ci-123`)
+/// from the Registry.
+///
+/// The manifest is deleted by it's digest (a tag is resolved to the digest first), so all the
+/// tags pointing to the same manifest are deleted too. Returns the digest of the deleted manifest.
+/// Fails if deleting is disabled on the Registry.
+///
+/// # Example:
+///
+/// ```rust,no_run
+/// # use intermodal_rs::image::api::delete_image;
+///
+/// #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let digest = delete_image("docker://registry.example.com/app:ci-123").await.unwrap();
+///
+/// println!("Deleted: {}", digest);
+/// # }
+/// ```
+pub async fn delete_image(reference: &str) -> io::Result<Digest> {
+    let docker_ref = docker_reference(reference)?;
+    let destination = docker_ref.new_docker_destination(&RegistriesConfig::load())?;
+
+    Ok(destination.delete_manifest().await?)
+}
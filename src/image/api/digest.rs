@@ -0,0 +1,52 @@
+//! Image 'digest' related APIs
+
+use std::io;
+
+use crate::image::{
+    docker::{
+        reference::{api::parse, types::DockerReference},
+        registries::RegistriesConfig,
+    },
+    oci::digest::Digest,
+    types::errors::ImageError,
+};
+
+/// Resolves a 'docker' reference (eg. `docker://fedora:latest`) to the digest of it's manifest.
+///
+/// The manifest is not downloaded, if the Registry returns the digest in the response to a `HEAD`
+/// request (`Docker-Content-Digest` header). Returns the name of the repository the reference is
+/// resolved to (using the search registries and the short name aliases) along with the digest,
+/// which can be used to pin the image (eg. `docker.io/library/fedora@sha256:...`).
+///
+/// # Example:
+///
+/// ```rust,no_run
+/// # use intermodal_rs::image::api::resolve_digest;
+///
+/// #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let (name, digest) = resolve_digest("docker://fedora:latest").await.unwrap();
+///
+/// println!("{}@{}", name, digest);
+/// # }
+/// ```
+pub async fn resolve_digest(reference: &str) -> io::Result<(String, Digest)> {
+    let docker_ref = docker_reference(reference)?;
+    let source = docker_ref.new_docker_source(&RegistriesConfig::load())?;
+
+    Ok(source.resolve_digest().await?)
+}
+
+// Parses the `reference`, which should be a 'docker' reference (ie. 'docker://...').
+pub(super) fn docker_reference(reference: &str) -> io::Result<DockerReference> {
+    match reference.strip_prefix("docker://") {
+        Some(docker_ref) => Ok(parse(docker_ref).map_err(ImageError::from)?),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Invalid Reference '{}', only 'docker://' references are supported.",
+                reference
+            ),
+        )),
+    }
+}
@@ -0,0 +1,191 @@
+//! Registry 'login' and 'logout' related APIs
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::image::docker::auth::{self, Credentials};
+
+/// Logs in to the Registry with the given username and password.
+///
+/// The credentials are verified with the Registry before they are stored in the auth file. If
+/// `auth_file` is `None`, `$REGISTRY_AUTH_FILE` (if set) or `~/.docker/config.json` is used. If a
+/// credential helper is configured in the auth file for the Registry, the credentials are stored
+/// using the helper.
+///
+/// # Example:
+///
+/// ```rust,no_run
+/// # use intermodal_rs::image::api::registry_login;
+///
+/// #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let result = registry_login("quay.io", "user", "password", None).await;
+///
+/// assert!(result.is_ok())
+/// # }
+/// ```
+pub async fn registry_login(
+    registry: &str,
+    username: &str,
+    password: &str,
+    auth_file: Option<&Path>,
+) -> io::Result<()> {
+    let path = auth_file_path(auth_file)?;
+
+    let credentials = Credentials {
+        username: username.to_string(),
+        password: password.to_string(),
+        identity_token: None,
+    };
+
+    Ok(auth::login(registry, &credentials, &path).await?)
+}
+
+/// Logs out of the Registry, by removing the credentials for it from the auth file.
+///
+/// It is an error if there are no credentials for the Registry in the auth file.
+pub fn registry_logout(registry: &str, auth_file: Option<&Path>) -> io::Result<()> {
+    let path = auth_file_path(auth_file)?;
+
+    auth::logout(registry, &path)
+}
+
+fn auth_file_path(auth_file: Option<&Path>) -> io::Result<PathBuf> {
+    match auth_file {
+        Some(path) => Ok(path.to_path_buf()),
+        None => auth::default_auth_file_path().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "Unable to determine the path of the auth file.",
+            )
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use wiremock::matchers::{body_string_contains, header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    // A Registry with a token realm, accepting only `user:pass`.
+    async fn setup_mock_registry() -> MockServer {
+        let server = MockServer::start().await;
+
+        let challenge = format!(
+            "Bearer realm=\"{}/token\",service=\"test-registry\"",
+            server.uri()
+        );
+        Mock::given(method("GET"))
+            .and(path("/v2/"))
+            .respond_with(
+                ResponseTemplate::new(401).insert_header("WWW-Authenticate", challenge.as_str()),
+            )
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .and(header("Authorization", "Basic dXNlcjpwYXNz"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"token": "abcd"}"#))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(401))
+            .with_priority(10)
+            .mount(&server)
+            .await;
+
+        server
+    }
+
+    #[tokio::test]
+    async fn test_login_stores_refresh_token() {
+        let server = setup_mock_registry().await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(body_string_contains("grant_type=password"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"access_token": "abcd", "refresh_token": "efgh"}"#),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        let registry = server.uri();
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let auth_file = tempdir.path().join("auth.json");
+
+        let result = registry_login(&registry, "user", "pass", Some(&auth_file)).await;
+        assert!(result.is_ok(), "{:?}", result);
+
+        let contents: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&auth_file).unwrap()).unwrap();
+        let host = registry.trim_start_matches("http://");
+        assert_eq!(contents["auths"][host]["auth"], "dXNlcjpwYXNz");
+        assert_eq!(contents["auths"][host]["identitytoken"], "efgh");
+    }
+
+    #[tokio::test]
+    async fn test_login_logout() {
+        let server = setup_mock_registry().await;
+        let registry = server.uri();
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let auth_file = tempdir.path().join("auth.json");
+
+        let result = registry_login(&registry, "user", "wrong", Some(&auth_file)).await;
+        assert!(result.is_err());
+        assert!(!auth_file.exists());
+
+        let result = registry_login(&registry, "user", "pass", Some(&auth_file)).await;
+        assert!(result.is_ok(), "{:?}", result);
+
+        let contents: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&auth_file).unwrap()).unwrap();
+        let host = registry.trim_start_matches("http://");
+        assert_eq!(contents["auths"][host]["auth"], "dXNlcjpwYXNz");
+
+        let result = registry_logout(&registry, Some(&auth_file));
+        assert!(result.is_ok(), "{:?}", result);
+
+        // Not logged in any longer.
+        let result = registry_logout(&registry, Some(&auth_file));
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_login_encodes_account() {
+        let server = setup_mock_registry().await;
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .and(query_param("account", "a b&c+d"))
+            .and(query_param("service", "test-registry"))
+            .and(header("Authorization", "Basic YSBiJmMrZDpwYXNz"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"token": "abcd"}"#))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let auth_file = tempdir.path().join("auth.json");
+
+        let result = registry_login(&server.uri(), "a b&c+d", "pass", Some(&auth_file)).await;
+        assert!(result.is_ok(), "{:?}", result);
+    }
+
+    #[tokio::test]
+    async fn test_login_invalid_registry() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let auth_file = tempdir.path().join("auth.json");
+
+        let result = registry_login("bad host", "user", "pass", Some(&auth_file)).await;
+        assert!(result.is_err());
+        assert!(!auth_file.exists());
+    }
+}
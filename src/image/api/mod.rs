@@ -5,5 +5,28 @@
 mod pull;
 pub use pull::*;
 
+mod copy;
+pub use copy::*;
+
+mod catalog;
+pub use catalog::*;
+
+mod delete;
+pub use delete::*;
+
+mod digest;
+pub use digest::*;
+
+mod referrers;
+pub use referrers::*;
+
+pub use crate::image::docker::certs::{set_registry_certificates, RegistryCertificates};
+pub use crate::image::docker::retry::{
+    registry_rate_limit, set_retry_policy, RateLimit, RetryPolicy,
+};
+
+mod login;
+pub use login::*;
+
 mod mount;
 pub use mount::*;
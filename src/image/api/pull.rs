@@ -1,20 +1,23 @@
 //! Image 'pull' related APIs and internal functions
 
 use std::collections::HashMap;
-use std::io;
+use std::io::{self, Write};
 use std::path::Path;
 use std::sync::Arc;
 
+use flate2::write::GzDecoder;
+use tokio::{io::BufReader, sync::Semaphore};
+use tokio_util::io::InspectReader;
+
 use crate::image::{
     oci::{
-        digest::Digest,
+        digest::{Digest, DigestReader, DigestVerifier},
         layout::OCIImageLayout,
-        spec_v1::{Descriptor, Image as OCIImage, Index, Manifest},
+        spec_v1::{Descriptor, Image as OCIImage, Index, Manifest, ANNOTATION_REF_NAME},
     },
     transports,
     types::ImageSource,
 };
-use tokio::{io::BufReader, sync::Semaphore};
 
 /// Pulls a container image to a given Path.
 ///
@@ -116,7 +119,7 @@ async fn perform_image_pull(
     let mut annotations = HashMap::new();
     // FIXME : Not sure, Also, right now we 'know' tag is `Some`.
     let _ = annotations.insert(
-        "org.opencontainers.image.ref.name".to_string(),
+        ANNOTATION_REF_NAME.to_string(),
         img_layout.tag().as_ref().unwrap().clone(),
     );
 
@@ -128,6 +131,7 @@ async fn perform_image_pull(
         urls: None,
         platform: None,
         annotations: Some(annotations),
+        artifact_type: None,
     };
 
     log::trace!("Updating Image Layout 'Index', with new manifest.");
@@ -154,6 +158,10 @@ async fn perform_image_pull(
 
     let image_obj: OCIImage = serde_json::from_slice(&config)?;
 
+    // The layers are downloaded using the source of the image, so that they are downloaded from
+    // where the manifest was found (eg. a mirror).
+    let img = Arc::new(img);
+
     log::debug!("Getting Image Layers!");
     let max_parallel_dloads = 3;
     let mut layer_handles = vec![];
@@ -161,13 +169,21 @@ async fn perform_image_pull(
 
     for (layer, unzipped_digest) in manifest_obj.layers.iter().zip(image_obj.rootfs.diff_ids) {
         let layer_digest = layer.digest.clone();
+        let layer_media_type = layer.mediatype.clone().unwrap_or_default();
         let img_layout = img_layout.clone();
-        let img_source = image_ref.new_image_source()?;
+        let img = img.clone();
 
         let permit = semaphore.clone().acquire_owned().await;
 
         let handle = tokio::spawn(async move {
-            do_download_image_layer(layer_digest, unzipped_digest, img_layout, img_source).await?;
+            do_download_image_layer(
+                layer_digest,
+                &layer_media_type,
+                unzipped_digest,
+                img_layout,
+                img.source_ref(),
+            )
+            .await?;
             drop(permit);
             Ok::<(), std::io::Error>(())
         });
@@ -175,7 +191,7 @@ async fn perform_image_pull(
     }
 
     for h in layer_handles {
-        let _ = h.await?;
+        h.await??;
     }
 
     // We now have everything - Write this to disk layout.
@@ -189,36 +205,143 @@ async fn perform_image_pull(
     Ok(())
 }
 
-async fn do_download_image_layer<'a>(
+// Downloads the layer and saves it to the Image Layout.
+//
+// The layer is read only once and during that, the digest of the layer is verified, the layer is
+// uncompressed (if required) and the digest of the uncompressed layer is verified against the
+// `diff_id`. If either of the digests does not match, the saved layer is removed and an error is
+// returned.
+async fn do_download_image_layer(
     layer_digest: Digest,
+    layer_media_type: &str,
     unzipped_digest: Digest,
     img_layout: OCIImageLayout,
-    img_source: Box<dyn ImageSource + Send + Sync>,
+    img_source: &(dyn ImageSource + Send + Sync),
 ) -> io::Result<()> {
     log::info!("Getting Image Layer: {}", layer_digest);
 
-    // let img_source = img.source_ref();
     let layer_reader = img_source.get_blob(&layer_digest).await?;
+    let layer_reader = DigestReader::new(layer_reader, &layer_digest).map_err(invalid_data)?;
 
-    log::trace!("Layer downloaded, Verifying the RootFS Layer.");
-    let reader = BufReader::new(layer_reader);
-    // FIXME: Use the proper decoder based on Media type
-    let mut gzip_decoder = async_compression::tokio::bufread::GzipDecoder::new(reader);
-    let unzipped_verify = unzipped_digest.verify(&mut gzip_decoder).await;
-
-    if unzipped_verify {
-        log::trace!("Image Layer {} verified. Saving Image Layer.", layer_digest);
-        // FIXME: This unnecessarily verifies the image that we just verified above.
-        let layer_reader = img_source.get_blob(&layer_digest).await?;
-        let mut reader = BufReader::new(layer_reader);
-        img_layout
-            .write_blob_file(&layer_digest, &mut reader)
-            .await?;
+    let unzipped_verifier = DigestVerifier::new(&unzipped_digest).map_err(invalid_data)?;
+    let mut unzipped_writer = if layer_media_type.ends_with("gzip") {
+        UnzippedWriter::Gzip(GzDecoder::new(unzipped_verifier))
     } else {
-        log::error!(
-            "Checksum does not match for: {} after uncompressing.",
-            &layer_digest
+        // Uncompressed layers (eg. from a 'docker-archive'), the digest is the same as 'diff_id'.
+        log::trace!(
+            "Layer Media Type: '{}', not uncompressing.",
+            layer_media_type
         );
+        UnzippedWriter::Plain(unzipped_verifier)
+    };
+
+    // Everything read from the layer, is also uncompressed for verifying the 'diff_id'.
+    let mut unzip_error = None;
+    let mut reader = BufReader::new(InspectReader::new(layer_reader, |data: &[u8]| {
+        if unzip_error.is_none() {
+            if let Err(e) = unzipped_writer.write(data) {
+                unzip_error = Some(e);
+            }
+        }
+    }));
+
+    log::trace!("Saving and Verifying Image Layer {}.", layer_digest);
+    let mut result = img_layout.write_blob_file(&layer_digest, &mut reader).await;
+    drop(reader);
+
+    if result.is_ok() {
+        result = match unzip_error {
+            Some(e) => Err(e),
+            None => unzipped_writer.verify(),
+        };
+    }
+
+    if let Err(e) = result {
+        log::error!("Saving Image Layer {} failed: {}", &layer_digest, e);
+        let _ = tokio::fs::remove_file(img_layout.blob_path(&layer_digest)).await;
+        return Err(e);
     }
+
+    log::trace!("Image Layer {} verified and saved.", layer_digest);
     Ok(())
 }
+
+// Uncompresses (if required) the layer written to it and verifies the digest of the uncompressed
+// layer.
+enum UnzippedWriter {
+    Gzip(GzDecoder<DigestVerifier>),
+    Plain(DigestVerifier),
+}
+
+impl UnzippedWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        match self {
+            UnzippedWriter::Gzip(decoder) => decoder.write_all(data),
+            UnzippedWriter::Plain(verifier) => verifier.write_all(data),
+        }
+    }
+
+    fn verify(self) -> io::Result<()> {
+        match self {
+            UnzippedWriter::Gzip(decoder) => decoder.finish()?.verify(),
+            UnzippedWriter::Plain(verifier) => verifier.verify(),
+        }
+    }
+}
+
+fn invalid_data<E>(e: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::image::oci::testdata::{create_test_image_layout, test_layer_blobs};
+
+    #[tokio::test]
+    async fn test_download_image_layer() {
+        transports::init_transports();
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let layout_path = create_test_image_layout(tempdir.path()).await;
+        let reference = format!("oci:{}", layout_path.display());
+        let image_ref = transports::parse_image_name(&reference).unwrap();
+
+        let (tarred, gzipped) = test_layer_blobs();
+        let layer_digest = Digest::from_bytes(&gzipped);
+        let media_type = "application/vnd.oci.image.layer.v1.tar+gzip";
+
+        let img_layout = OCIImageLayout::new("pulled", None, tempdir.path().join("pulled"));
+        let result = do_download_image_layer(
+            layer_digest.clone(),
+            media_type,
+            Digest::from_bytes(&tarred),
+            img_layout.clone(),
+            &*image_ref.new_image_source().unwrap(),
+        )
+        .await;
+        assert!(result.is_ok(), "{:?}", result);
+        assert_eq!(
+            std::fs::read(img_layout.blob_path(&layer_digest)).unwrap(),
+            gzipped
+        );
+
+        // The uncompressed layer does not match the 'diff_id'.
+        let img_layout = OCIImageLayout::new("mismatch", None, tempdir.path().join("mismatch"));
+        let result = do_download_image_layer(
+            layer_digest.clone(),
+            media_type,
+            Digest::from_bytes(&gzipped),
+            img_layout.clone(),
+            &*image_ref.new_image_source().unwrap(),
+        )
+        .await;
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert!(!img_layout.blob_path(&layer_digest).exists());
+    }
+}
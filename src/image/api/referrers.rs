@@ -0,0 +1,40 @@
+//! Image 'referrers' related APIs
+
+use std::io;
+
+use crate::image::{docker::registries::RegistriesConfig, oci::spec_v1::Descriptor};
+
+use super::digest::docker_reference;
+
+/// Lists the artifacts (eg. SBOMs and signatures) attached to the image for a 'docker' reference
+/// (eg. `docker://registry.example.com/app:v1`), ie. the manifests with the image as their
+/// `subject`.
+///
+/// Only the artifacts of the `artifact_type` are listed, if specified. The Referrers API is used
+/// if the Registry supports it, else the Referrers Tag Schema (`sha256-<hex>`) is used.
+///
+/// # Example:
+///
+/// ```rust,no_run
+/// # use intermodal_rs::image::api::list_referrers;
+///
+/// #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let referrers = list_referrers("docker://registry.example.com/app:v1", None)
+///     .await
+///     .unwrap();
+///
+/// for referrer in referrers {
+///     println!("{:?} {}", referrer.artifact_type, referrer.digest);
+/// }
+/// # }
+/// ```
+pub async fn list_referrers(
+    reference: &str,
+    artifact_type: Option<&str>,
+) -> io::Result<Vec<Descriptor>> {
+    let docker_ref = docker_reference(reference)?;
+    let source = docker_ref.new_docker_source(&RegistriesConfig::load())?;
+
+    Ok(source.get_referrers(artifact_type).await?)
+}
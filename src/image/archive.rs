@@ -0,0 +1,217 @@
+//! Reading files from (uncompressed) tar archives without unpacking them.
+//!
+//! Image archives (like those created by `docker save` or an OCI Image Layout in a tar file) are
+//! often large. Instead of unpacking such archives to a temporary directory, the archive is
+//! scanned once to record the offset and size of every file in it. Later, the individual files are
+//! read directly from the archive using the recorded offsets.
+
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncSeekExt, Take},
+};
+
+// Maximum number of symbolic links followed when looking up an entry.
+const MAX_LINKS_FOLLOWED: usize = 16;
+
+#[derive(Debug, Clone, Copy)]
+struct EntryPosition {
+    offset: u64,
+    size: u64,
+}
+
+/// An index of all the files in a tar archive.
+#[derive(Debug, Clone)]
+pub(crate) struct TarArchive {
+    path: PathBuf,
+    files: HashMap<String, EntryPosition>,
+    links: HashMap<String, String>,
+}
+
+impl TarArchive {
+    /// Scans the archive at the `path` and records all the files (and links) in it.
+    ///
+    /// Note: This is a blocking call.
+    pub(crate) fn open<P>(path: P) -> std::io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = PathBuf::from(path.as_ref());
+        log::debug!("Indexing Archive: {:?}", path);
+
+        let mut archive = tar::Archive::new(std::fs::File::open(&path)?);
+
+        let mut files = HashMap::new();
+        let mut links = HashMap::new();
+        for entry in archive.entries_with_seek()? {
+            let entry = entry?;
+            let name = normalize(&entry.path()?);
+            let entry_type = entry.header().entry_type();
+
+            if entry_type.is_file() {
+                log::trace!("Archive File: {}", name);
+                files.insert(
+                    name,
+                    EntryPosition {
+                        offset: entry.raw_file_position(),
+                        size: entry.size(),
+                    },
+                );
+            } else if entry_type.is_symlink() || entry_type.is_hard_link() {
+                if let Some(target) = entry.link_name()? {
+                    // Symbolic links are relative to the directory of the link, hard links are
+                    // relative to the root of the archive.
+                    let target = if entry_type.is_symlink() {
+                        let parent = Path::new(&name).parent().unwrap_or_else(|| Path::new(""));
+                        normalize(&parent.join(target))
+                    } else {
+                        normalize(&target)
+                    };
+                    log::trace!("Archive Link: {} -> {}", name, target);
+                    links.insert(name, target);
+                }
+            }
+        }
+
+        Ok(TarArchive { path, files, links })
+    }
+
+    /// Returns the path of the archive on the FS.
+    pub(crate) fn archive_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the size of the file with the `name` in the archive.
+    pub(crate) fn entry_size(&self, name: &str) -> std::io::Result<u64> {
+        Ok(self.position(name)?.size)
+    }
+
+    /// Returns a reader for the file with the `name` in the archive.
+    pub(crate) async fn entry_reader(&self, name: &str) -> std::io::Result<Take<File>> {
+        let position = self.position(name)?;
+
+        let mut f = File::open(&self.path).await?;
+        f.seek(SeekFrom::Start(position.offset)).await?;
+
+        Ok(f.take(position.size))
+    }
+
+    /// Reads the whole file with the `name` in the archive.
+    pub(crate) async fn read_entry(&self, name: &str) -> std::io::Result<Vec<u8>> {
+        let mut contents = Vec::new();
+        self.entry_reader(name)
+            .await?
+            .read_to_end(&mut contents)
+            .await?;
+
+        Ok(contents)
+    }
+
+    /// Returns a blocking reader for the file with the `name` in the archive.
+    pub(crate) fn entry_reader_blocking(
+        &self,
+        name: &str,
+    ) -> std::io::Result<std::io::Take<std::fs::File>> {
+        let position = self.position(name)?;
+
+        let mut f = std::fs::File::open(&self.path)?;
+        f.seek(SeekFrom::Start(position.offset))?;
+
+        Ok(f.take(position.size))
+    }
+
+    /// Reads the whole file with the `name` in the archive.
+    ///
+    /// Note: This is a blocking call, useful only for reading small files (like `index.json`).
+    pub(crate) fn read_entry_blocking(&self, name: &str) -> std::io::Result<Vec<u8>> {
+        let mut contents = Vec::new();
+        self.entry_reader_blocking(name)?
+            .read_to_end(&mut contents)?;
+
+        Ok(contents)
+    }
+
+    fn position(&self, name: &str) -> std::io::Result<EntryPosition> {
+        let mut name = normalize(Path::new(name));
+
+        for _ in 0..MAX_LINKS_FOLLOWED {
+            if let Some(position) = self.files.get(&name) {
+                return Ok(*position);
+            }
+            match self.links.get(&name) {
+                Some(target) => name = target.clone(),
+                None => break,
+            }
+        }
+
+        Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("File '{}' not found in the archive {:?}.", name, self.path),
+        ))
+    }
+}
+
+// Normalizes a path inside the archive, so that `./foo/../bar` and `bar` are treated the same.
+fn normalize(path: &Path) -> String {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(c) => normalized.push(c),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            _ => {}
+        }
+    }
+
+    normalized.to_string_lossy().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn create_test_archive(path: &Path) {
+        let mut builder = tar::Builder::new(std::fs::File::create(path).unwrap());
+
+        for (name, contents) in [("./index.json", &b"{}"[..]), ("dir/file", &b"hello"[..])] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, contents).unwrap();
+        }
+
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        header.set_mode(0o777);
+        builder
+            .append_link(&mut header, "other/link", "../dir/file")
+            .unwrap();
+
+        builder.finish().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_entries() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let archive_path = tempdir.path().join("test.tar");
+        create_test_archive(&archive_path);
+
+        let archive = TarArchive::open(&archive_path).unwrap();
+
+        assert_eq!(archive.entry_size("dir/file").unwrap(), 5);
+        assert_eq!(archive.read_entry("index.json").await.unwrap(), b"{}");
+        assert_eq!(archive.read_entry("./dir/file").await.unwrap(), b"hello");
+        assert_eq!(archive.read_entry("dir/file").await.unwrap(), b"hello");
+        assert_eq!(archive.read_entry("other/link").await.unwrap(), b"hello");
+        assert_eq!(archive.read_entry_blocking("dir/file").unwrap(), b"hello");
+        assert!(archive.read_entry("missing").await.is_err());
+        assert!(archive.read_entry("dir").await.is_err());
+    }
+}
@@ -0,0 +1,20 @@
+//! Implementation of 'docker-archive' Transport
+//!
+//! A 'docker-archive' is a tar file as created by `docker save`, containing a `manifest.json`,
+//! the image configs and the layers (as tar files) of one or more images. The reference for the
+//! transport looks like `docker-archive:<path>[:<docker-reference>]`, where the optional
+//! `docker-reference` (eg. `busybox:latest`) selects one of the images in the archive by it's
+//! `RepoTags`.
+//!
+//! Since a 'docker save' archive does not contain a registry manifest, a Schema2 manifest is
+//! synthesized for the image from the `manifest.json`.
+//!
+//! References:
+//! [Docker Archive Implementation](https://github.com/containers/image/tree/master/docker/archive)
+
+pub(crate) mod reference;
+pub(crate) mod source;
+pub mod transport;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,94 @@
+//! Implementation of Docker Archive Image Reference
+
+use std::path::PathBuf;
+
+use crate::image::{
+    docker::reference::{
+        api::parse,
+        types::{DockerImageReference, DockerReference},
+    },
+    oci::image::OCIImage,
+    types::{errors::ImageError, Image, ImageReference, ImageResult, ImageSource, ImageTransport},
+};
+
+use super::{source::DockerArchiveSource, transport::DockerArchiveTransport};
+
+/// A structure implementing a Reference to an image inside a `docker save` archive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct DockerArchiveReference {
+    pub(crate) path: PathBuf,
+    pub(crate) reference: Option<DockerReference>,
+}
+
+impl DockerArchiveReference {
+    /// Parses the reference of the form `<path>[:<docker-reference>]`.
+    ///
+    /// Note: Unlike the 'oci' references, the path cannot contain a ':'.
+    pub(crate) fn parse(reference: &str) -> ImageResult<Self> {
+        let (path, docker_ref) = match reference.split_once(':') {
+            Some((path, docker_ref)) => (path, Some(docker_ref)),
+            None => (reference, None),
+        };
+
+        if path.is_empty() {
+            log::error!("Path in the reference '{}' is empty.", reference);
+            return Err(ImageError::new());
+        }
+
+        let docker_ref = match docker_ref {
+            Some(r) => Some(parse(r)?),
+            None => None,
+        };
+
+        Ok(DockerArchiveReference {
+            path: PathBuf::from(path),
+            reference: docker_ref,
+        })
+    }
+}
+
+impl ImageReference for DockerArchiveReference {
+    fn transport(&self) -> Box<dyn ImageTransport + Send + Sync> {
+        Box::new(DockerArchiveTransport::new())
+    }
+
+    fn string_within_transport(&self) -> String {
+        match &self.reference {
+            Some(r) => format!("{}:{}:{}", self.path.display(), r.name(), r.tag()),
+            None => format!("{}", self.path.display()),
+        }
+    }
+
+    /// Returns an object implementing trait 'ImageSource' (in our case 'DockerArchiveSource').
+    fn new_image_source(&self) -> ImageResult<Box<dyn ImageSource + Send + Sync>> {
+        Ok(Box::new(DockerArchiveSource::open(self)?))
+    }
+
+    /// Returns an object implementing trait 'Image' in our case 'OCIImage'
+    fn new_image(&self) -> ImageResult<Box<dyn Image + Send + Sync>> {
+        let source = self.new_image_source()?;
+
+        Ok(Box::new(OCIImage {
+            source,
+            cfgblob: None,
+        }))
+    }
+
+    /// Returns the DockerReference for the image.
+    ///
+    /// If the reference was not specified by the user, the first of the `RepoTags` for the image
+    /// in the archive is used. This reads the archive (a blocking call).
+    fn docker_reference(&self) -> Option<Box<dyn DockerImageReference>> {
+        if let Some(r) = &self.reference {
+            return Some(Box::new(r.clone()));
+        }
+
+        let source = DockerArchiveSource::open(self).ok()?;
+        let repo_tag = source.repo_tags().first()?.clone();
+
+        match parse(&repo_tag) {
+            Ok(r) => Some(Box::new(r)),
+            Err(_) => None,
+        }
+    }
+}
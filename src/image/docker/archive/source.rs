@@ -0,0 +1,238 @@
+//! Implementation of Docker Archive specific ImageSource
+
+use std::collections::HashMap;
+use std::io::Read;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use tokio::io::AsyncRead;
+
+use crate::image::{
+    archive::TarArchive,
+    docker::{
+        errors::DockerImageError,
+        manifest::schema2::{Schema2, Schema2Descriptor},
+        reference::{api::parse, types::DockerImageReference},
+        MEDIA_TYPE_DOCKER_V2_SCHEMA2_CONFIG, MEDIA_TYPE_DOCKER_V2_SCHEMA2_LAYER,
+        MEDIA_TYPE_DOCKER_V2_SCHEMA2_LAYER_UNCOMPRESSED, MEDIA_TYPE_DOCKER_V2_SCHEMA2_MANIFEST,
+    },
+    oci::{
+        digest::{Digest, DigestReader},
+        spec_v1::RootFS,
+    },
+    types::{
+        errors::{ImageError, ImageResult},
+        ImageManifest, ImageReference, ImageSource,
+    },
+};
+
+use super::reference::DockerArchiveReference;
+
+const MANIFEST_JSON_FILENAME: &str = "manifest.json";
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// An entry for an Image in the `manifest.json` of the archive.
+#[derive(Debug, Clone, Deserialize)]
+struct ArchiveManifestItem {
+    #[serde(rename = "Config")]
+    config: String,
+
+    #[serde(default, rename = "RepoTags")]
+    repo_tags: Option<Vec<String>>,
+
+    #[serde(rename = "Layers")]
+    layers: Vec<String>,
+}
+
+// Only the `rootfs` of the Image config is required to synthesize the manifest.
+#[derive(Debug, Deserialize)]
+struct ConfigRootFSOnly {
+    rootfs: RootFS,
+}
+
+/// DockerArchiveSource structure. This structure implements `ImageSource` trait for an image in
+/// a `docker save` archive.
+#[derive(Debug)]
+pub(crate) struct DockerArchiveSource {
+    reference: DockerArchiveReference,
+    archive: TarArchive,
+    repo_tags: Vec<String>,
+    manifest: ImageManifest,
+
+    // Digest -> File Name in the Archive for the Config and Layers.
+    blobs: HashMap<String, String>,
+}
+
+impl DockerArchiveSource {
+    /// Opens the archive and synthesizes a Schema2 manifest for the referred image.
+    ///
+    /// Layers in the archive are usually uncompressed, in which case the digest of the layer is
+    /// same as the corresponding `diff_id` in the config. Only compressed layers (if any) are
+    /// read to determine their digest.
+    ///
+    /// Note: This is a blocking call.
+    pub(crate) fn open(reference: &DockerArchiveReference) -> ImageResult<Self> {
+        let archive = TarArchive::open(&reference.path)?;
+
+        let items: Vec<ArchiveManifestItem> =
+            serde_json::from_slice(&archive.read_entry_blocking(MANIFEST_JSON_FILENAME)?)?;
+        let item = Self::select_item(reference, items)?;
+
+        let config = archive.read_entry_blocking(&item.config)?;
+        let config_digest = Digest::from_bytes(&config);
+        let rootfs: ConfigRootFSOnly = serde_json::from_slice(&config)?;
+
+        if rootfs.rootfs.diff_ids.len() != item.layers.len() {
+            return Err(source_error(format!(
+                "Image Config has {} 'diff_ids' but there are {} layers.",
+                rootfs.rootfs.diff_ids.len(),
+                item.layers.len()
+            )));
+        }
+
+        let mut blobs = HashMap::new();
+        blobs.insert(config_digest.to_string(), item.config.clone());
+
+        let mut layers = vec![];
+        for (layer, diff_id) in item.layers.iter().zip(rootfs.rootfs.diff_ids) {
+            let mut magic = [0u8; 2];
+            let is_gzip = archive
+                .entry_reader_blocking(layer)?
+                .read_exact(&mut magic)
+                .is_ok()
+                && magic == GZIP_MAGIC;
+
+            let (media_type, digest) = if is_gzip {
+                log::trace!("Layer '{}' is compressed, computing it's digest.", layer);
+                let digest = Digest::from_reader(&mut archive.entry_reader_blocking(layer)?)?;
+                (MEDIA_TYPE_DOCKER_V2_SCHEMA2_LAYER, digest)
+            } else {
+                (MEDIA_TYPE_DOCKER_V2_SCHEMA2_LAYER_UNCOMPRESSED, diff_id)
+            };
+
+            blobs.insert(digest.to_string(), layer.clone());
+            layers.push(Schema2Descriptor {
+                media_type: media_type.to_string(),
+                size: archive.entry_size(layer)? as i64,
+                digest,
+                urls: None,
+            });
+        }
+
+        let schema2 = Schema2 {
+            schema_version: 2,
+            media_type: MEDIA_TYPE_DOCKER_V2_SCHEMA2_MANIFEST.to_string(),
+            config: Schema2Descriptor {
+                media_type: MEDIA_TYPE_DOCKER_V2_SCHEMA2_CONFIG.to_string(),
+                size: config.len() as i64,
+                digest: config_digest,
+                urls: None,
+            },
+            layers,
+        };
+        log::trace!("Synthesized Manifest: {:?}", schema2);
+
+        Ok(DockerArchiveSource {
+            reference: reference.clone(),
+            archive,
+            repo_tags: item.repo_tags.unwrap_or_default(),
+            manifest: ImageManifest {
+                manifest: serde_json::to_vec(&schema2)?,
+                mime_type: MEDIA_TYPE_DOCKER_V2_SCHEMA2_MANIFEST.to_string(),
+            },
+            blobs,
+        })
+    }
+
+    // Selects the image matching the reference (or the only image) from the `manifest.json`.
+    fn select_item(
+        reference: &DockerArchiveReference,
+        items: Vec<ArchiveManifestItem>,
+    ) -> ImageResult<ArchiveManifestItem> {
+        match &reference.reference {
+            Some(docker_ref) => {
+                for item in items {
+                    let matches = item.repo_tags.iter().flatten().any(|t| match parse(t) {
+                        Ok(r) => r.name() == docker_ref.name() && r.tag() == docker_ref.tag(),
+                        Err(_) => false,
+                    });
+                    if matches {
+                        return Ok(item);
+                    }
+                }
+                Err(source_error(format!(
+                    "No Image matching '{}:{}' found in the archive.",
+                    docker_ref.name(),
+                    docker_ref.tag()
+                )))
+            }
+            None => {
+                if items.len() == 1 {
+                    Ok(items.into_iter().next().unwrap())
+                } else {
+                    Err(source_error(format!(
+                        "Archive contains {} images, a reference is required.",
+                        items.len()
+                    )))
+                }
+            }
+        }
+    }
+
+    /// Returns the `RepoTags` of the image in the archive.
+    pub(crate) fn repo_tags(&self) -> &Vec<String> {
+        &self.repo_tags
+    }
+}
+
+fn source_error(errstr: String) -> ImageError {
+    log::error!("{}", errstr);
+    ImageError::new().with(DockerImageError::SourceError(errstr))
+}
+
+#[async_trait]
+impl ImageSource for DockerArchiveSource {
+    fn reference(&self) -> Box<dyn ImageReference> {
+        Box::new(self.reference.clone())
+    }
+
+    async fn get_manifest(&mut self, digest: Option<&Digest>) -> ImageResult<ImageManifest> {
+        if let Some(digest) = digest {
+            if *digest != Digest::from_bytes(&self.manifest.manifest) {
+                return Err(source_error(format!(
+                    "Manifest for Digest '{}' not found in the archive.",
+                    digest
+                )));
+            }
+        }
+
+        Ok(self.manifest.clone())
+    }
+
+    async fn get_blob(
+        &self,
+        digest: &Digest,
+    ) -> ImageResult<Box<dyn AsyncRead + Unpin + Send + Sync>> {
+        log::debug!("Reading Blob: {}", digest);
+        let entry_name = match self.blobs.get(&digest.to_string()) {
+            Some(entry_name) => entry_name,
+            None => {
+                return Err(source_error(format!(
+                    "Blob for Digest '{}' not found in the archive.",
+                    digest
+                )))
+            }
+        };
+
+        // Verified as it is read, a corrupted Blob fails the read at the end.
+        let reader = self.archive.entry_reader(entry_name).await?;
+        let reader = DigestReader::new(reader, digest).map_err(|e| ImageError::new().with(e))?;
+
+        Ok(Box::new(reader))
+    }
+
+    async fn get_repo_tags(&self) -> ImageResult<Vec<String>> {
+        Ok(self.repo_tags.clone())
+    }
+}
@@ -0,0 +1,134 @@
+use std::path::{Path, PathBuf};
+
+use tokio::io::AsyncReadExt;
+
+use crate::image::{
+    api::pull_container_image,
+    docker::{
+        manifest::schema2::Schema2, MEDIA_TYPE_DOCKER_V2_SCHEMA2_LAYER_UNCOMPRESSED,
+        MEDIA_TYPE_DOCKER_V2_SCHEMA2_MANIFEST,
+    },
+    oci::{
+        digest::Digest,
+        testdata::{test_config_blob, test_layer_blobs},
+    },
+    transports,
+};
+
+fn append_file(builder: &mut tar::Builder<std::fs::File>, name: &str, contents: &[u8]) {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, name, contents).unwrap();
+}
+
+// Creates an archive similar to the one created by `docker save busybox:latest`
+async fn create_test_archive(path: &Path) -> PathBuf {
+    let (tarred, _) = test_layer_blobs();
+    let config = test_config_blob(&Digest::from_bytes(&tarred));
+    let config_name = format!("{}.json", Digest::from_bytes(&config).hex_digest());
+
+    let manifest = serde_json::json!([{
+        "Config": config_name,
+        "RepoTags": ["busybox:latest"],
+        "Layers": ["0123456789abcdef/layer.tar"]
+    }]);
+
+    let archive_path = path.join("busybox.tar");
+    let mut builder = tar::Builder::new(std::fs::File::create(&archive_path).unwrap());
+    append_file(&mut builder, "0123456789abcdef/layer.tar", &tarred);
+    append_file(&mut builder, "0123456789abcdef/VERSION", b"1.0");
+    append_file(&mut builder, &config_name, &config);
+    append_file(
+        &mut builder,
+        "manifest.json",
+        &serde_json::to_vec(&manifest).unwrap(),
+    );
+    append_file(
+        &mut builder,
+        "repositories",
+        br#"{"busybox":{"latest":"0123456789abcdef"}}"#,
+    );
+    builder.finish().unwrap();
+
+    archive_path
+}
+
+#[tokio::test]
+async fn test_synthesized_manifest() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let archive_path = create_test_archive(tempdir.path()).await;
+
+    transports::init_transports();
+    let image_name = format!("docker-archive:{}", archive_path.display());
+    let image_ref = transports::parse_image_name(&image_name);
+    assert!(image_ref.is_ok(), "{:?}", image_ref.err());
+    let image_ref = image_ref.unwrap();
+
+    let docker_ref = image_ref.docker_reference().unwrap();
+    assert_eq!(docker_ref.name(), "docker.io/library/busybox");
+    assert_eq!(docker_ref.tag(), "latest");
+
+    let mut image = image_ref.new_image().unwrap();
+    let manifest = image.manifest().await.unwrap();
+    assert_eq!(manifest.mime_type, MEDIA_TYPE_DOCKER_V2_SCHEMA2_MANIFEST);
+
+    let schema2: Schema2 = serde_json::from_slice(&manifest.manifest).unwrap();
+    let (tarred, _) = test_layer_blobs();
+    assert_eq!(schema2.layers.len(), 1);
+    assert_eq!(schema2.layers[0].digest, Digest::from_bytes(&tarred));
+    assert_eq!(schema2.layers[0].size, tarred.len() as i64);
+    assert_eq!(
+        schema2.layers[0].media_type,
+        MEDIA_TYPE_DOCKER_V2_SCHEMA2_LAYER_UNCOMPRESSED
+    );
+
+    let mut layer = image
+        .source_ref()
+        .get_blob(&schema2.layers[0].digest)
+        .await
+        .unwrap();
+    let mut blob = Vec::new();
+    layer.read_to_end(&mut blob).await.unwrap();
+    assert_eq!(blob, tarred);
+
+    let inspect = image.inspect().await.unwrap();
+    assert_eq!(inspect.architecture, "amd64");
+
+    let tags = image.source_ref().get_repo_tags().await.unwrap();
+    assert_eq!(tags, vec!["busybox:latest".to_string()]);
+}
+
+#[tokio::test]
+async fn test_select_image_by_reference() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let archive_path = create_test_archive(tempdir.path()).await;
+
+    transports::init_transports();
+    let image_name = format!("docker-archive:{}:busybox:latest", archive_path.display());
+    let image_ref = transports::parse_image_name(&image_name).unwrap();
+    assert!(image_ref.new_image_source().is_ok());
+
+    let image_name = format!("docker-archive:{}:busybox:1.32", archive_path.display());
+    let image_ref = transports::parse_image_name(&image_name).unwrap();
+    assert!(image_ref.new_image_source().is_err());
+}
+
+#[tokio::test]
+async fn test_pull_from_archive() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let archive_path = create_test_archive(tempdir.path()).await;
+
+    transports::init_transports();
+    let image_name = format!("docker-archive:{}", archive_path.display());
+    let images_path = tempdir.path().join("images");
+
+    let result = pull_container_image(&image_name, &images_path, false, true).await;
+    assert!(result.is_ok(), "{:?}", result.err());
+
+    let layout = result.unwrap();
+    let (tarred, _) = test_layer_blobs();
+    let layer = std::fs::read(layout.blob_path(&Digest::from_bytes(&tarred))).unwrap();
+    assert_eq!(layer, tarred);
+}
@@ -0,0 +1,97 @@
+//! Implementation of Docker Archive Transport
+
+use std::boxed::Box;
+use std::string::String;
+
+use crate::image::types::{ImageReference, ImageResult, ImageTransport};
+
+use super::reference::DockerArchiveReference;
+
+pub(crate) static DOCKER_ARCHIVE_TRANSPORT_NAME: &str = "docker-archive";
+
+pub(in crate::image) fn get_docker_archive_transport(
+) -> (String, Box<dyn ImageTransport + Send + Sync>) {
+    (
+        String::from(DOCKER_ARCHIVE_TRANSPORT_NAME),
+        Box::new(DockerArchiveTransport::new()),
+    )
+}
+
+/// A Structure implementing Docker Archive Transport.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) struct DockerArchiveTransport {}
+
+impl DockerArchiveTransport {
+    pub(crate) fn new() -> Self {
+        DockerArchiveTransport {}
+    }
+}
+
+impl ImageTransport for DockerArchiveTransport {
+    fn name(&self) -> String {
+        String::from(DOCKER_ARCHIVE_TRANSPORT_NAME)
+    }
+
+    fn parse_reference(&self, reference: &str) -> ImageResult<Box<dyn ImageReference>> {
+        log::debug!("Parsing Reference '{}'", reference);
+        Ok(Box::new(DockerArchiveReference::parse(reference)?))
+    }
+
+    fn cloned(&self) -> Box<dyn ImageTransport + Send + Sync> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_parse_reference() {
+        struct ParseRefTC<'a> {
+            input: &'a str,
+            result: bool,
+            within_transport: &'a str,
+        }
+
+        let transport = DockerArchiveTransport::new();
+        let test_cases = vec![
+            ParseRefTC {
+                input: "/tmp/busybox.tar",
+                result: true,
+                within_transport: "/tmp/busybox.tar",
+            },
+            ParseRefTC {
+                input: "/tmp/busybox.tar:busybox",
+                result: true,
+                within_transport: "/tmp/busybox.tar:docker.io/library/busybox:latest",
+            },
+            ParseRefTC {
+                input: "/tmp/busybox.tar:localhost:5000/busybox:1.32",
+                result: true,
+                within_transport: "/tmp/busybox.tar:localhost:5000/busybox:1.32",
+            },
+            ParseRefTC {
+                input: "",
+                result: false,
+                within_transport: "",
+            },
+            ParseRefTC {
+                input: "/tmp/busybox.tar:busybox/",
+                result: false,
+                within_transport: "",
+            },
+        ];
+
+        for tc in test_cases {
+            let result = transport.parse_reference(tc.input);
+            assert_eq!(result.is_ok(), tc.result, "{}", tc.input);
+
+            if let Ok(r) = result {
+                assert_eq!(r.transport().name(), "docker-archive");
+                assert_eq!(r.string_within_transport(), tc.within_transport);
+            }
+        }
+    }
+}
@@ -0,0 +1,927 @@
+//! Credentials for accessing the Docker Registries.
+//!
+//! The credentials are read from the auth files used by the `docker` and `containers` tools
+//! (`podman`, `skopeo` etc.). The auth files are looked up in the following order and the first
+//! file having the credentials for a registry is used -
+//!
+//! 1. `$REGISTRY_AUTH_FILE`
+//! 2. `${XDG_RUNTIME_DIR}/containers/auth.json`
+//! 3. `~/.docker/config.json`
+//!
+//! The format of these files is described in [containers-auth.json][auth_json].
+//!
+//! If the auth file specifies a credential helper for the registry (`credHelpers`) or a default
+//! one (`credsStore`), the credentials are obtained by running `docker-credential-<name> get` as
+//! per the [credential helper protocol][helpers]. The credentials from the helper take precedence
+//! over those from the `auths` in the same file.
+//!
+//! When logging in to a registry, the credentials are stored in `$REGISTRY_AUTH_FILE` (if set) or
+//! `~/.docker/config.json` (or by the credential helper configured in that file).
+//!
+//! Registries supporting the OAuth2 token flow may return a refresh token, which is stored as the
+//! `identitytoken` for the registry in the auth file and used instead of the password afterwards.
+//!
+//! [auth_json]: https://github.com/containers/image/blob/main/docs/containers-auth.json.5.md
+//! [helpers]: https://github.com/docker/docker-credential-helpers
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use directories::BaseDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::image::{
+    docker::{
+        client::DockerClient, reference::api::DEFAULT_DOCKER_DOMAIN, registries::RegistriesConfig,
+    },
+    types::errors::ImageResult,
+};
+
+// Docker Hub is known by a few different names in the auth files.
+const DOCKER_HUB_ALIASES: [&str; 2] = ["index.docker.io", "registry-1.docker.io"];
+
+// Server URL used by the credential helpers for Docker Hub.
+const DOCKER_HUB_SERVER_URL: &str = "https://index.docker.io/v1/";
+
+const CREDENTIAL_HELPER_PREFIX: &str = "docker-credential-";
+
+// Username used by the credential helpers for an identity token (instead of a password).
+const IDENTITY_TOKEN_USERNAME: &str = "<token>";
+
+/// Credentials (username and password) for a Registry.
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct Credentials {
+    pub(crate) username: String,
+    pub(crate) password: String,
+
+    /// OAuth2 refresh token for the Registry (`identitytoken` in the auth files), if any.
+    pub(crate) identity_token: Option<String>,
+}
+
+// Never log the password or the token.
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .field(
+                "identity_token",
+                &self.identity_token.as_ref().map(|_| "<redacted>"),
+            )
+            .finish()
+    }
+}
+
+impl Credentials {
+    /// Returns whether there is a password (and not just an identity token) in the credentials.
+    pub(crate) fn has_password(&self) -> bool {
+        self.username != IDENTITY_TOKEN_USERNAME
+    }
+
+    /// Returns the value of `Authorization` header for `Basic` authentication.
+    pub(crate) fn basic_auth_header(&self) -> String {
+        format!("Basic {}", self.auth_string())
+    }
+
+    // Returns the base64 encoded `username:password` string stored in the auth files.
+    fn auth_string(&self) -> String {
+        BASE64.encode(format!("{}:{}", self.username, self.password))
+    }
+
+    // Decodes the credentials from the base64 encoded `username:password` string.
+    fn from_auth_string(auth: &str) -> Option<Self> {
+        let decoded = BASE64.decode(auth.trim()).ok()?;
+        let decoded = String::from_utf8(decoded).ok()?;
+        let (username, password) = decoded.split_once(':')?;
+
+        Some(Credentials {
+            username: username.to_string(),
+            password: password.to_string(),
+            identity_token: None,
+        })
+    }
+
+    // Returns the credentials for just the `identity_token`.
+    fn from_identity_token(identity_token: &str) -> Self {
+        Credentials {
+            username: IDENTITY_TOKEN_USERNAME.to_string(),
+            password: String::new(),
+            identity_token: Some(identity_token.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AuthFile {
+    #[serde(default)]
+    auths: HashMap<String, AuthEntry>,
+
+    #[serde(default, rename = "credsStore")]
+    creds_store: Option<String>,
+
+    #[serde(default, rename = "credHelpers")]
+    cred_helpers: HashMap<String, String>,
+}
+
+impl AuthFile {
+    // Returns the name of the credential helper to be used for the `registry` (if any).
+    fn credential_helper(&self, registry: &str) -> Option<&str> {
+        self.cred_helpers
+            .iter()
+            .find(|(key, _)| normalize_registry(key) == registry)
+            .map(|(_, helper)| helper.as_str())
+            .or(self.creds_store.as_deref())
+            .filter(|helper| !helper.is_empty())
+    }
+}
+
+// Output of the `get` command (and input of the `store` command) of a credential helper.
+#[derive(Debug, Deserialize, Serialize)]
+struct HelperCredentials {
+    #[serde(rename = "ServerURL", default)]
+    server_url: String,
+
+    #[serde(rename = "Username")]
+    username: String,
+
+    #[serde(rename = "Secret")]
+    secret: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AuthEntry {
+    #[serde(default)]
+    auth: Option<String>,
+
+    #[serde(default)]
+    identitytoken: Option<String>,
+}
+
+/// Returns the paths of the auth files in the order in which they are looked up.
+pub(crate) fn auth_file_paths() -> Vec<PathBuf> {
+    let home_dir = BaseDirs::new().map(|base_dirs| base_dirs.home_dir().to_path_buf());
+
+    auth_file_paths_from(|name| std::env::var_os(name), home_dir)
+}
+
+// Returns the paths of the auth files given by the environment variables (`var` returns the value
+// of a variable) and the `home_dir`, in the order in which they are looked up.
+fn auth_file_paths_from<F>(var: F, home_dir: Option<PathBuf>) -> Vec<PathBuf>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    let mut paths = vec![];
+
+    if let Some(path) = var("REGISTRY_AUTH_FILE") {
+        paths.push(PathBuf::from(path));
+    }
+
+    if let Some(runtime_dir) = var("XDG_RUNTIME_DIR") {
+        let mut path = PathBuf::from(runtime_dir);
+        path.push("containers");
+        path.push("auth.json");
+        paths.push(path);
+    }
+
+    if let Some(mut path) = home_dir {
+        path.push(".docker");
+        path.push("config.json");
+        paths.push(path);
+    }
+
+    paths
+}
+
+/// Returns the path of the auth file used for storing the credentials during login.
+pub(crate) fn default_auth_file_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("REGISTRY_AUTH_FILE") {
+        return Some(PathBuf::from(path));
+    }
+
+    BaseDirs::new().map(|base_dirs| {
+        let mut path = base_dirs.home_dir().to_path_buf();
+        path.push(".docker");
+        path.push("config.json");
+        path
+    })
+}
+
+/// Verifies the `credentials` against the `registry` and stores them in the auth file at `path`.
+pub(crate) async fn login(
+    registry: &str,
+    credentials: &Credentials,
+    path: &Path,
+) -> ImageResult<()> {
+    let registry = normalize_registry(registry);
+    log::debug!(
+        "Logging in to '{}' as '{}'.",
+        registry,
+        credentials.username
+    );
+
+    let insecure = RegistriesConfig::load().registry_insecure(&registry);
+    let mut client = DockerClient::new(&registry, insecure)?;
+    client.set_credentials(Some(credentials.clone()));
+    client.do_verify_credentials().await?;
+
+    // The refresh token (if the Registry returned one) is stored along with the credentials.
+    let credentials = Credentials {
+        identity_token: client.refresh_token(),
+        ..credentials.clone()
+    };
+    store_credentials(path, &registry, &credentials)?;
+    log::info!("Login Succeeded for '{}'.", registry);
+
+    Ok(())
+}
+
+/// Removes the credentials for the `registry` from the auth file at `path`.
+pub(crate) fn logout(registry: &str, path: &Path) -> std::io::Result<()> {
+    let registry = normalize_registry(registry);
+    log::debug!("Logging out of '{}'.", registry);
+
+    if !remove_credentials(path, &registry)? {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("Not logged in to '{}'.", registry),
+        ));
+    }
+
+    log::info!("Removed Credentials for '{}'.", registry);
+    Ok(())
+}
+
+/// Returns the credentials for the `registry` from the auth files (if any).
+pub(crate) fn get_credentials(registry: &str) -> Option<Credentials> {
+    credentials_from_files(&auth_file_paths(), registry)
+}
+
+// Returns the credentials for the `registry` from the first of the `paths` having them.
+fn credentials_from_files(paths: &[PathBuf], registry: &str) -> Option<Credentials> {
+    for path in paths {
+        match credentials_from_file(path, registry) {
+            Ok(Some(credentials)) => {
+                log::debug!("Using Credentials for '{}' from {:?}.", registry, path);
+                return Some(credentials);
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!("Error '{}' in reading auth file {:?}.", e, path),
+        }
+    }
+
+    log::trace!("No Credentials found for '{}'.", registry);
+    None
+}
+
+// Returns the credentials for the `registry` from the auth file at the `path`.
+fn credentials_from_file(path: &Path, registry: &str) -> std::io::Result<Option<Credentials>> {
+    credentials_from_file_in(path, registry, &helper_search_dirs())
+}
+
+// Returns the credentials for the `registry` from the auth file at the `path`, with the credential
+// helpers looked up in the `search_dirs`.
+fn credentials_from_file_in(
+    path: &Path,
+    registry: &str,
+    search_dirs: &[PathBuf],
+) -> std::io::Result<Option<Credentials>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let auth_file: AuthFile = serde_json::from_slice(&std::fs::read(path)?)?;
+
+    let registry = normalize_registry(registry);
+    if let Some(helper) = auth_file.credential_helper(&registry) {
+        let program = helper_program(helper, search_dirs);
+        match run_credential_helper(&program, &registry) {
+            Ok(Some(credentials)) => return Ok(Some(credentials)),
+            Ok(None) => {}
+            Err(e) => log::warn!(
+                "Error '{}' in running Credential Helper '{}'.",
+                e,
+                program.display()
+            ),
+        }
+    }
+
+    for (key, entry) in auth_file.auths {
+        if normalize_registry(&key) != registry {
+            continue;
+        }
+
+        let identity_token = entry.identitytoken.filter(|t| !t.is_empty());
+        if let Some(auth) = &entry.auth {
+            match Credentials::from_auth_string(auth) {
+                Some(credentials) => {
+                    return Ok(Some(Credentials {
+                        identity_token,
+                        ..credentials
+                    }))
+                }
+                None => log::warn!("Invalid 'auth' for '{}' in {:?}.", key, path),
+            }
+        }
+        if let Some(identity_token) = identity_token {
+            return Ok(Some(Credentials::from_identity_token(&identity_token)));
+        }
+    }
+
+    Ok(None)
+}
+
+// Stores the `credentials` for the `registry` in the auth file at `path`.
+//
+// Other contents of the auth file are preserved.
+fn store_credentials(
+    path: &Path,
+    registry: &str,
+    credentials: &Credentials,
+) -> std::io::Result<()> {
+    let mut contents = read_auth_file_value(path)?;
+
+    let auth_file: AuthFile = serde_json::from_value(contents.clone())?;
+    if let Some(helper) = auth_file.credential_helper(registry) {
+        let program = helper_program(helper, &helper_search_dirs());
+        let input = serde_json::to_vec(&HelperCredentials {
+            server_url: helper_server_url(registry).to_string(),
+            username: credentials.username.clone(),
+            secret: credentials.password.clone(),
+        })?;
+
+        return check_helper_output(&program, run_helper_command(&program, "store", &input)?);
+    }
+
+    let auths = contents
+        .as_object_mut()
+        .unwrap()
+        .entry("auths")
+        .or_insert_with(|| serde_json::json!({}));
+    let auths = match auths.as_object_mut() {
+        Some(auths) => auths,
+        None => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Invalid 'auths' in the auth file {:?}.", path),
+            ))
+        }
+    };
+
+    let mut entry = serde_json::json!({ "auth": credentials.auth_string() });
+    if let Some(identity_token) = &credentials.identity_token {
+        entry["identitytoken"] = serde_json::json!(identity_token);
+    }
+
+    auths.retain(|key, _| normalize_registry(key) != registry);
+    auths.insert(auth_file_key(registry).to_string(), entry);
+
+    write_auth_file_value(path, &contents)
+}
+
+/// Stores the `identity_token` (OAuth2 refresh token) for the `registry` in the auth file that
+/// has the credentials for it.
+///
+/// Nothing is stored, if the credentials are from a credential helper or not found in any of the
+/// auth files.
+pub(crate) fn store_identity_token(registry: &str, identity_token: &str) -> std::io::Result<()> {
+    let registry = normalize_registry(registry);
+
+    for path in auth_file_paths() {
+        if credentials_from_file(&path, &registry)?.is_none() {
+            continue;
+        }
+
+        let mut contents = read_auth_file_value(&path)?;
+        let auth_file: AuthFile = serde_json::from_value(contents.clone())?;
+        if auth_file.credential_helper(&registry).is_some() {
+            log::debug!(
+                "Not storing the Identity Token for '{}' with the Credential Helper.",
+                registry
+            );
+            return Ok(());
+        }
+
+        let entry = contents
+            .get_mut("auths")
+            .and_then(|auths| auths.as_object_mut())
+            .and_then(|auths| {
+                auths
+                    .iter_mut()
+                    .find(|(key, _)| normalize_registry(key) == registry)
+            })
+            .and_then(|(_, entry)| entry.as_object_mut());
+        if let Some(entry) = entry {
+            log::debug!(
+                "Storing the Identity Token for '{}' in {:?}.",
+                registry,
+                path
+            );
+            entry.insert(
+                "identitytoken".to_string(),
+                serde_json::json!(identity_token),
+            );
+            return write_auth_file_value(&path, &contents);
+        }
+    }
+
+    Ok(())
+}
+
+// Removes the credentials for the `registry` from the auth file at `path`. Returns whether the
+// credentials were present.
+fn remove_credentials(path: &Path, registry: &str) -> std::io::Result<bool> {
+    if !path.exists() {
+        return Ok(false);
+    }
+    let mut contents = read_auth_file_value(path)?;
+
+    let auth_file: AuthFile = serde_json::from_value(contents.clone())?;
+    if let Some(helper) = auth_file.credential_helper(registry) {
+        let program = helper_program(helper, &helper_search_dirs());
+        let output = run_helper_command(&program, "erase", helper_server_url(registry).as_bytes())?;
+
+        return Ok(output.status.success());
+    }
+
+    let removed = match contents.get_mut("auths").and_then(|a| a.as_object_mut()) {
+        Some(auths) => {
+            let count = auths.len();
+            auths.retain(|key, _| normalize_registry(key) != registry);
+            auths.len() != count
+        }
+        None => false,
+    };
+
+    if removed {
+        write_auth_file_value(path, &contents)?;
+    }
+
+    Ok(removed)
+}
+
+// Reads the auth file as a JSON value, so that the unknown fields are preserved when written.
+fn read_auth_file_value(path: &Path) -> std::io::Result<serde_json::Value> {
+    if !path.exists() {
+        return Ok(serde_json::json!({}));
+    }
+
+    let contents: serde_json::Value = serde_json::from_slice(&std::fs::read(path)?)?;
+    if !contents.is_object() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Auth file {:?} is not a JSON object.", path),
+        ));
+    }
+
+    Ok(contents)
+}
+
+// Writes the auth file readable only by the user, since it contains secrets.
+//
+// The contents are written to a temporary file (created readable only by the user) in the same
+// directory, that then replaces the auth file. So the auth file is never partially written.
+fn write_auth_file_value(path: &Path, contents: &serde_json::Value) -> std::io::Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    std::fs::create_dir_all(parent)?;
+
+    let mut file = tempfile::NamedTempFile::new_in(parent)?;
+    file.write_all(&serde_json::to_vec_pretty(contents)?)?;
+    file.as_file().sync_all()?;
+    file.persist(path).map_err(|e| e.error)?;
+
+    Ok(())
+}
+
+// Docker uses the URL of the V1 API as the key for the Docker Hub.
+fn auth_file_key(registry: &str) -> &str {
+    helper_server_url(registry)
+}
+
+// Returns the server URL for the `registry` passed to the credential helpers.
+fn helper_server_url(registry: &str) -> &str {
+    if registry == DEFAULT_DOCKER_DOMAIN {
+        DOCKER_HUB_SERVER_URL
+    } else {
+        registry
+    }
+}
+
+// Returns the directories the credential helpers are looked up in (the `PATH`).
+fn helper_search_dirs() -> Vec<PathBuf> {
+    std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).collect())
+        .unwrap_or_default()
+}
+
+// Returns the program for the credential `helper`, the first one found in the `search_dirs`. If it
+// is not found, the program name is returned as it is (running it fails).
+fn helper_program(helper: &str, search_dirs: &[PathBuf]) -> PathBuf {
+    let name = format!("{}{}", CREDENTIAL_HELPER_PREFIX, helper);
+
+    search_dirs
+        .iter()
+        .map(|dir| dir.join(&name))
+        .find(|path| path.is_file())
+        .unwrap_or_else(|| PathBuf::from(name))
+}
+
+// Runs the credential helper `program` with the `command` and `input` written to it's `stdin`.
+fn run_helper_command(program: &Path, command: &str, input: &[u8]) -> std::io::Result<Output> {
+    log::debug!(
+        "Running Credential Helper '{} {}'.",
+        program.display(),
+        command
+    );
+
+    let mut child = Command::new(program)
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Closes the `stdin` once written, so that the helper gets an EOF.
+    child.stdin.take().unwrap().write_all(input)?;
+
+    child.wait_with_output()
+}
+
+// Returns an error if the credential helper `program` failed.
+fn check_helper_output(program: &Path, output: Output) -> std::io::Result<()> {
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!(
+            "Credential Helper '{}' failed ({}): {}",
+            program.display(),
+            output.status,
+            String::from_utf8_lossy(&output.stdout).trim()
+        )))
+    }
+}
+
+// Runs the credential helper `program` to get the credentials for the `registry`.
+//
+// The server URL is written to the `stdin` of `<program> get` and the credentials are read as JSON
+// from it's `stdout`. If the helper does not have the credentials, it exits with an error.
+fn run_credential_helper(program: &Path, registry: &str) -> std::io::Result<Option<Credentials>> {
+    let output = run_helper_command(program, "get", helper_server_url(registry).as_bytes())?;
+    if !output.status.success() {
+        log::debug!(
+            "Credential Helper '{}' failed ({}): {}",
+            program.display(),
+            output.status,
+            String::from_utf8_lossy(&output.stdout).trim()
+        );
+        return Ok(None);
+    }
+
+    let credentials: HelperCredentials = serde_json::from_slice(&output.stdout)?;
+    if credentials.username == IDENTITY_TOKEN_USERNAME {
+        return Ok(Some(Credentials::from_identity_token(&credentials.secret)));
+    }
+
+    Ok(Some(Credentials {
+        username: credentials.username,
+        password: credentials.secret,
+        identity_token: None,
+    }))
+}
+
+// Returns the registry host from the key in the auth file. The keys can be URLs like
+// `https://index.docker.io/v1/`.
+pub(crate) fn normalize_registry(key: &str) -> String {
+    let key = key
+        .trim_start_matches("https://")
+        .trim_start_matches("http://");
+    let host = key.split('/').next().unwrap_or_default();
+
+    if DOCKER_HUB_ALIASES.contains(&host) {
+        DEFAULT_DOCKER_DOMAIN.to_string()
+    } else {
+        host.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn write_auth_file(path: &Path, auths: &[(&str, &str)]) {
+        let auths: HashMap<&str, HashMap<&str, String>> = auths
+            .iter()
+            .map(|(registry, userpass)| {
+                let mut entry = HashMap::new();
+                entry.insert("auth", BASE64.encode(userpass));
+                (*registry, entry)
+            })
+            .collect();
+
+        let mut contents = HashMap::new();
+        contents.insert("auths", auths);
+        std::fs::write(path, serde_json::to_vec(&contents).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_credentials_from_file() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("config.json");
+        write_auth_file(
+            &path,
+            &[
+                ("https://index.docker.io/v1/", "hubuser:hubpass"),
+                ("localhost:5000", "user:pass:with:colons"),
+            ],
+        );
+
+        let credentials = credentials_from_file(&path, "docker.io").unwrap();
+        assert_eq!(
+            credentials,
+            Some(Credentials {
+                username: "hubuser".to_string(),
+                password: "hubpass".to_string(),
+                identity_token: None,
+            })
+        );
+
+        let credentials = credentials_from_file(&path, "localhost:5000").unwrap();
+        assert_eq!(credentials.unwrap().password, "pass:with:colons");
+
+        let credentials = credentials_from_file(&path, "quay.io").unwrap();
+        assert!(credentials.is_none());
+    }
+
+    #[test]
+    fn test_auth_file_paths() {
+        let home_dir = Some(PathBuf::from("/home/user"));
+
+        let paths = auth_file_paths_from(
+            |name| match name {
+                "REGISTRY_AUTH_FILE" => Some("/tmp/auth.json".into()),
+                "XDG_RUNTIME_DIR" => Some("/run/user/1000".into()),
+                _ => None,
+            },
+            home_dir.clone(),
+        );
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("/tmp/auth.json"),
+                PathBuf::from("/run/user/1000/containers/auth.json"),
+                PathBuf::from("/home/user/.docker/config.json"),
+            ]
+        );
+
+        let paths = auth_file_paths_from(|_| None, home_dir);
+        assert_eq!(paths, vec![PathBuf::from("/home/user/.docker/config.json")]);
+
+        assert!(auth_file_paths_from(|_| None, None).is_empty());
+    }
+
+    #[test]
+    fn test_credentials_lookup_order() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let first = tempdir.path().join("auth.json");
+        let second = tempdir.path().join("config.json");
+        let missing = tempdir.path().join("missing.json");
+        write_auth_file(&first, &[("quay.io", "first:pass")]);
+        write_auth_file(
+            &second,
+            &[("quay.io", "second:pass"), ("docker.io", "second:pass")],
+        );
+
+        let paths = vec![missing, first, second];
+        assert_eq!(
+            credentials_from_files(&paths, "quay.io").unwrap().username,
+            "first"
+        );
+        assert_eq!(
+            credentials_from_files(&paths, "docker.io")
+                .unwrap()
+                .username,
+            "second"
+        );
+        assert!(credentials_from_files(&paths, "ghcr.io").is_none());
+    }
+
+    #[test]
+    fn test_credential_helper_selection() {
+        let auth_file: AuthFile = serde_json::from_str(
+            r#"{
+                "credsStore": "desktop",
+                "credHelpers": {"https://quay.io": "quay", "gcr.io": ""}
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(auth_file.credential_helper("quay.io"), Some("quay"));
+        assert_eq!(auth_file.credential_helper("docker.io"), Some("desktop"));
+
+        let auth_file = AuthFile::default();
+        assert_eq!(auth_file.credential_helper("docker.io"), None);
+    }
+
+    // Writes a fake helper at `path`, that knows the credentials only for 'localhost:5000'.
+    #[cfg(unix)]
+    fn write_fake_credential_helper(path: &Path) {
+        use std::os::unix::fs::PermissionsExt;
+
+        std::fs::write(
+            path,
+            r#"#!/bin/sh
+[ "$1" = "get" ] || exit 1
+read server
+if [ "$server" = "localhost:5000" ]; then
+    echo '{"ServerURL": "localhost:5000", "Username": "helperuser", "Secret": "helpersecret"}'
+else
+    echo "credentials not found in native keychain"
+    exit 1
+fi
+"#,
+        )
+        .unwrap();
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_credential_helper() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let helper = tempdir.path().join("docker-credential-fake");
+        write_fake_credential_helper(&helper);
+
+        let credentials = run_credential_helper(&helper, "localhost:5000").unwrap();
+        assert_eq!(
+            credentials,
+            Some(Credentials {
+                username: "helperuser".to_string(),
+                password: "helpersecret".to_string(),
+                identity_token: None,
+            })
+        );
+
+        let credentials = run_credential_helper(&helper, "quay.io").unwrap();
+        assert!(credentials.is_none());
+
+        let missing = tempdir.path().join("docker-credential-missing");
+        assert!(run_credential_helper(&missing, "quay.io").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_credential_helper_from_path() {
+        // The helpers in the auth file are looked up in the search directories (the `PATH`).
+        let tempdir = tempfile::tempdir().unwrap();
+        let helper = tempdir.path().join("docker-credential-pathfake");
+        write_fake_credential_helper(&helper);
+
+        let search_dirs = vec![tempdir.path().join("missing"), tempdir.path().to_path_buf()];
+        assert_eq!(helper_program("pathfake", &search_dirs), helper);
+        assert_eq!(
+            helper_program("other", &search_dirs),
+            PathBuf::from("docker-credential-other")
+        );
+
+        let auth_path = tempdir.path().join("config.json");
+        std::fs::write(
+            &auth_path,
+            r#"{
+                "auths": {
+                    "localhost:5000": {"auth": "ZmlsZXVzZXI6ZmlsZXBhc3M="},
+                    "quay.io": {"auth": "ZmlsZXVzZXI6ZmlsZXBhc3M="}
+                },
+                "credHelpers": {"localhost:5000": "pathfake"},
+                "credsStore": "pathfake"
+            }"#,
+        )
+        .unwrap();
+
+        // From the registry specific helper.
+        let credentials =
+            credentials_from_file_in(&auth_path, "localhost:5000", &search_dirs).unwrap();
+
+        // From the default store, that does not have them, so from the `auths`.
+        let fallback = credentials_from_file_in(&auth_path, "quay.io", &search_dirs).unwrap();
+
+        assert_eq!(
+            credentials,
+            Some(Credentials {
+                username: "helperuser".to_string(),
+                password: "helpersecret".to_string(),
+                identity_token: None,
+            })
+        );
+        assert_eq!(fallback.unwrap().username, "fileuser");
+    }
+
+    #[test]
+    fn test_store_and_remove_credentials() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("docker").join("config.json");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(
+            &path,
+            r#"{"auths": {"quay.io": {"auth": "cXVheTpwYXNz"}}, "detachKeys": "ctrl-e"}"#,
+        )
+        .unwrap();
+
+        let credentials = Credentials {
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            identity_token: None,
+        };
+        store_credentials(&path, "docker.io", &credentials).unwrap();
+        store_credentials(&path, "localhost:5000", &credentials).unwrap();
+
+        let contents: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(contents["detachKeys"], "ctrl-e");
+        assert_eq!(
+            contents["auths"]["https://index.docker.io/v1/"]["auth"],
+            "dXNlcjpwYXNz"
+        );
+
+        assert_eq!(
+            credentials_from_file(&path, "docker.io").unwrap(),
+            Some(credentials.clone())
+        );
+        assert_eq!(
+            credentials_from_file(&path, "quay.io")
+                .unwrap()
+                .unwrap()
+                .username,
+            "quay"
+        );
+
+        assert!(remove_credentials(&path, "docker.io").unwrap());
+        assert!(!remove_credentials(&path, "docker.io").unwrap());
+        assert!(credentials_from_file(&path, "docker.io").unwrap().is_none());
+        assert_eq!(
+            credentials_from_file(&path, "localhost:5000").unwrap(),
+            Some(credentials)
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn test_identity_token() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("config.json");
+        std::fs::write(
+            &path,
+            r#"{"auths": {"quay.io": {"auth": "dXNlcjpwYXNz", "identitytoken": "refresh"}, "localhost:5000": {"identitytoken": "token-only"}}}"#,
+        )
+        .unwrap();
+
+        let credentials = credentials_from_file(&path, "quay.io").unwrap().unwrap();
+        assert_eq!(credentials.username, "user");
+        assert!(credentials.has_password());
+        assert_eq!(credentials.identity_token.as_deref(), Some("refresh"));
+
+        let credentials = credentials_from_file(&path, "localhost:5000")
+            .unwrap()
+            .unwrap();
+        assert!(!credentials.has_password());
+        assert_eq!(credentials.identity_token.as_deref(), Some("token-only"));
+
+        // Stored along with the credentials.
+        let credentials = Credentials {
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            identity_token: Some("new-refresh".to_string()),
+        };
+        store_credentials(&path, "quay.io", &credentials).unwrap();
+        assert_eq!(
+            credentials_from_file(&path, "quay.io").unwrap(),
+            Some(credentials.clone())
+        );
+        assert!(!format!("{:?}", credentials).contains("new-refresh"));
+    }
+
+    #[test]
+    fn test_basic_auth_header() {
+        let credentials = Credentials {
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            identity_token: None,
+        };
+
+        assert_eq!(credentials.basic_auth_header(), "Basic dXNlcjpwYXNz");
+        assert!(!format!("{:?}", credentials).contains("\"pass\""));
+    }
+}
@@ -0,0 +1,254 @@
+//! Locations of the blobs in the Registries
+//!
+//! The repositories a blob is known to be in (because it was pushed to or pulled from them) are
+//! saved per Registry. When the blob is pushed to another repository in the same Registry, it is
+//! mounted from one of these repositories (if the Registry allows it), instead of being uploaded
+//! again.
+//!
+//! The locations are only hints, a blob may have been deleted from the repository since. They are
+//! saved in a file in the cache directory (see `blob_locations_cache_path`), so that they are
+//! available for the pushes made later. Only the locations of the most recently recorded blobs
+//! are kept, so that the file does not grow without a limit.
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::image::oci::digest::Digest;
+
+lazy_static! {
+    static ref BLOB_LOCATIONS: Mutex<Option<(PathBuf, BlobLocations)>> = Mutex::new(None);
+}
+
+// Tests do not use (or change) the locations in the user's cache directory.
+#[cfg(test)]
+lazy_static! {
+    static ref TEST_CACHE_DIR: tempfile::TempDir = tempfile::tempdir().unwrap();
+}
+
+// Only the most recent locations of a blob in a Registry are kept.
+const MAX_LOCATIONS_PER_REGISTRY: usize = 5;
+
+// Only the locations of the most recently recorded blobs are kept.
+const MAX_BLOBS: usize = 1000;
+
+/// Repository paths of the blobs in the Registries.
+///
+/// The most recently recorded blobs (and the paths for a blob) are first.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct BlobLocations(Vec<BlobLocation>);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BlobLocation {
+    digest: String,
+    // Repository paths of the blob, keyed by the Registry.
+    registries: HashMap<String, Vec<String>>,
+}
+
+impl BlobLocations {
+    /// Loads the locations from the file at `path`. A missing or an invalid file has no locations.
+    pub(crate) fn load(path: &Path) -> Self {
+        match std::fs::read(path) {
+            Ok(contents) => serde_json::from_slice(&contents).unwrap_or_else(|e| {
+                log::warn!(
+                    "Ignoring Invalid Blob Locations '{}': {}",
+                    path.display(),
+                    e
+                );
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Records the blob with the `digest` to be in the repository at `path` in the `registry`.
+    /// Returns whether the locations changed.
+    pub(crate) fn record(&mut self, digest: &Digest, registry: &str, path: &str) -> bool {
+        let digest = digest.to_string();
+        let idx = self.0.iter().position(|location| location.digest == digest);
+
+        let unchanged = idx == Some(0)
+            && self.0[0]
+                .registries
+                .get(registry)
+                .and_then(|paths| paths.first())
+                .map(String::as_str)
+                == Some(path);
+        if unchanged {
+            return false;
+        }
+
+        let mut location = match idx {
+            Some(idx) => self.0.remove(idx),
+            None => BlobLocation {
+                digest,
+                registries: HashMap::new(),
+            },
+        };
+
+        let paths = location.registries.entry(registry.to_string()).or_default();
+        paths.retain(|p| p != path);
+        paths.insert(0, path.to_string());
+        paths.truncate(MAX_LOCATIONS_PER_REGISTRY);
+
+        self.0.insert(0, location);
+        self.0.truncate(MAX_BLOBS);
+
+        true
+    }
+
+    /// Returns the repository paths the blob with the `digest` is in, in the `registry`.
+    pub(crate) fn get(&self, digest: &Digest, registry: &str) -> Vec<String> {
+        let digest = digest.to_string();
+        self.0
+            .iter()
+            .find(|location| location.digest == digest)
+            .and_then(|location| location.registries.get(registry))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+// Replaces the file at `path` with the `contents` in one step, so that a concurrent `load` does
+// not see a partial file.
+fn write_file(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let parent = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut f = tempfile::NamedTempFile::new_in(parent)?;
+    f.write_all(contents)?;
+    f.persist(path).map_err(|e| e.error)?;
+
+    Ok(())
+}
+
+// Returns the path of the file the locations are saved in.
+fn cache_path() -> io::Result<PathBuf> {
+    #[cfg(test)]
+    return Ok(TEST_CACHE_DIR.path().join("blob-locations.json"));
+
+    #[cfg(not(test))]
+    crate::utils::blob_locations_cache_path()
+}
+
+// Runs `f` on the locations, loading them from the cache file if not loaded already.
+fn with_blob_locations<T>(f: impl FnOnce(&Path, &mut BlobLocations) -> T) -> io::Result<T> {
+    let mut blob_locations = BLOB_LOCATIONS.lock().unwrap();
+    if blob_locations.is_none() {
+        let path = cache_path()?;
+        let locations = BlobLocations::load(&path);
+        *blob_locations = Some((path, locations));
+    }
+
+    let (path, locations) = blob_locations.as_mut().unwrap();
+    Ok(f(path, locations))
+}
+
+/// Records the blob with the `digest` to be in the repository at `path` in the `registry`.
+///
+/// Failures are only logged, as the locations are just the hints for mounting the blobs.
+pub(crate) async fn record_blob_location(digest: &Digest, registry: &str, path: &str) {
+    let result = with_blob_locations(|cache_path, locations| {
+        if !locations.record(digest, registry, path) {
+            return Ok(None);
+        }
+
+        log::trace!("Blob {} Location: {}/{}", digest, registry, path);
+        Ok::<_, io::Error>(Some((
+            cache_path.to_path_buf(),
+            serde_json::to_vec(locations)?,
+        )))
+    });
+
+    // The file is written outside the lock (and off the async runtime).
+    let result = match result.and_then(|r| r) {
+        Ok(Some((cache_path, contents))) => {
+            tokio::task::spawn_blocking(move || write_file(&cache_path, &contents))
+                .await
+                .unwrap_or_else(|e| Err(io::Error::other(e)))
+        }
+        Ok(None) => Ok(()),
+        Err(e) => Err(e),
+    };
+
+    if let Err(e) = result {
+        log::warn!("Error in Saving the Blob Location for {}: {}", digest, e);
+    }
+}
+
+/// Returns the repository paths the blob with the `digest` is known to be in, in the `registry`.
+pub(crate) fn blob_locations(digest: &Digest, registry: &str) -> Vec<String> {
+    with_blob_locations(|_, locations| locations.get(digest, registry)).unwrap_or_else(|e| {
+        log::warn!("Error in Loading the Blob Locations: {}", e);
+        vec![]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_blob_locations() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let cache_path = tempdir.path().join("blob-locations.json");
+
+        let digest = Digest::from_bytes(b"blob");
+        let mut locations = BlobLocations::load(&cache_path);
+        assert!(locations.get(&digest, "registry.example.com").is_empty());
+
+        assert!(locations.record(&digest, "registry.example.com", "library/fedora"));
+        assert!(locations.record(&digest, "registry.example.com", "library/centos"));
+        assert!(!locations.record(&digest, "registry.example.com", "library/centos"));
+        assert!(locations.record(&digest, "other.example.com", "library/alpine"));
+        assert_eq!(
+            locations.get(&digest, "registry.example.com"),
+            vec!["library/centos", "library/fedora"]
+        );
+
+        // Recorded again, the location is the most recent.
+        assert!(locations.record(&digest, "registry.example.com", "library/fedora"));
+        for i in 0..MAX_LOCATIONS_PER_REGISTRY {
+            locations.record(&digest, "other.example.com", &format!("app/{}", i));
+        }
+        let contents = serde_json::to_vec(&locations).unwrap();
+        assert!(write_file(&cache_path, &contents).is_ok());
+
+        let locations = BlobLocations::load(&cache_path);
+        assert_eq!(
+            locations.get(&digest, "registry.example.com"),
+            vec!["library/fedora", "library/centos"]
+        );
+        let other = locations.get(&digest, "other.example.com");
+        assert_eq!(other.len(), MAX_LOCATIONS_PER_REGISTRY);
+        assert_eq!(other[0], format!("app/{}", MAX_LOCATIONS_PER_REGISTRY - 1));
+        assert!(!other.contains(&"library/alpine".to_string()));
+    }
+
+    #[test]
+    fn test_blob_locations_limit() {
+        let mut locations = BlobLocations::default();
+
+        let first = Digest::from_bytes(b"blob 0");
+        let second = Digest::from_bytes(b"blob 1");
+        for i in 0..MAX_BLOBS {
+            let digest = Digest::from_bytes(format!("blob {}", i).as_bytes());
+            locations.record(&digest, "registry.example.com", "library/fedora");
+        }
+
+        // The first blob is recorded again, so the second one is the least recent.
+        locations.record(&first, "registry.example.com", "library/centos");
+        let digest = Digest::from_bytes(b"one more blob");
+        locations.record(&digest, "registry.example.com", "library/fedora");
+
+        assert_eq!(locations.0.len(), MAX_BLOBS);
+        assert_eq!(
+            locations.get(&first, "registry.example.com"),
+            vec!["library/centos", "library/fedora"]
+        );
+        assert!(locations.get(&second, "registry.example.com").is_empty());
+    }
+}
@@ -0,0 +1,22 @@
+//! Listing the repositories in a Registry
+//!
+//! Uses the `/v2/_catalog` API of the Registry. Note: Not all Registries support the catalog
+//! (eg. `docker.io` does not), and those that do, may list only the repositories the user has
+//! access to.
+
+use crate::image::types::errors::ImageResult;
+
+use super::auth::normalize_registry;
+use super::client::DockerClient;
+use super::registries::RegistriesConfig;
+
+/// Returns all the repositories in the `registry` (`host[:port]`).
+pub(crate) async fn get_catalog(registry: &str) -> ImageResult<Vec<String>> {
+    let registry = normalize_registry(registry);
+    log::debug!("Getting the Catalog of '{}'.", registry);
+
+    let insecure = RegistriesConfig::load().registry_insecure(&registry);
+    let client = DockerClient::new(&registry, insecure)?;
+
+    Ok(client.do_get_catalog().await?)
+}
@@ -3,38 +3,73 @@
 //! [dockerio]: https://registry-1.docker.io
 //! [quayio]: https://quay.io/
 
-use core::convert::{Into, TryFrom};
 use std::boxed::Box;
+use std::collections::HashMap;
 use std::error::Error as StdError;
 use std::fmt;
 use std::sync::RwLock;
 
 use chrono::{DateTime, Duration, Utc};
 use futures_util::StreamExt;
+use hyper::client::ResponseFuture;
 use hyper::http::{
-    header::{ACCEPT, AUTHORIZATION, LOCATION},
-    Error as HttpError, HeaderMap, HeaderValue, Method as HttpMethod, StatusCode,
+    header::{
+        ACCEPT, AUTHORIZATION, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, LINK, LOCATION, RANGE,
+        WWW_AUTHENTICATE,
+    },
+    uri::InvalidUri,
+    HeaderMap, HeaderValue, Method as HttpMethod, StatusCode,
 };
 use hyper::{
     body::{to_bytes, Body},
     client::HttpConnector,
     Client as HyperClient, Error as HyperError, Request, Response, Uri,
 };
+use hyper_proxy::ProxyConnector;
 use hyper_tls::HttpsConnector;
 use serde::Deserialize;
 use tokio::{
     fs::File,
-    io::{AsyncRead, AsyncWriteExt},
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
 };
+use tokio_util::io::ReaderStream;
 
 use crate::image::{
-    docker::reference::api::DEFAULT_DOCKER_DOMAIN, manifest::DEFAULT_SUPPORTED_MANIFESTS,
-    oci::digest::Digest, types::errors::ImageError, types::ImageManifest,
+    docker::auth::{get_credentials, normalize_registry, store_identity_token, Credentials},
+    docker::blob_locations::record_blob_location,
+    docker::certs::tls_connector_for_host,
+    docker::proxy::ProxyConfig,
+    docker::reference::api::DEFAULT_DOCKER_DOMAIN,
+    docker::retry::{
+        is_retryable_status, is_transient_error, rate_limit_from_headers, retry_after,
+        retry_policy, update_rate_limit, RetryPolicy,
+    },
+    manifest::DEFAULT_SUPPORTED_MANIFESTS,
+    oci::digest::{Digest, DigestError, DigestReader, DigestVerifier},
+    oci::spec_v1::{Descriptor, Index, MEDIA_TYPE_IMAGE_INDEX},
+    types::errors::ImageError,
+    types::ImageManifest,
 };
 use crate::utils::image_blobs_cache_root;
 
 const DOCKER_REGISTRY_V2_HTTPS_URL: &str = "https://registry-1.docker.io";
 
+// Scope required for uploading blobs and manifests to a repository.
+const PUSH_SCOPE: &str = "pull,push";
+
+// Scope required for deleting manifests from a repository.
+const DELETE_SCOPE: &str = "delete";
+
+// Header with the digest of the manifest (or blob) in the response.
+const DOCKER_CONTENT_DIGEST: &str = "Docker-Content-Digest";
+
+// Path used for the catalog in place of a repository path, for the auth scope. Repository paths
+// cannot start with an `_`.
+const CATALOG_PATH: &str = "_catalog";
+
+// Client ID sent to the realm in the OAuth2 token flow.
+const OAUTH2_CLIENT_ID: &str = "intermodal";
+
 #[derive(Debug)]
 pub(super) struct ClientError(String);
 
@@ -44,12 +79,19 @@ impl fmt::Display for ClientError {
     }
 }
 
+// Required to get the catalog.
+#[derive(Debug, Deserialize)]
+struct CatalogInfo {
+    repositories: Option<Vec<String>>,
+}
+
 // Required to get tags list.
 #[derive(Debug, Deserialize)]
 struct TagInfo {
     #[allow(unused)]
     name: String,
-    tags: Vec<String>,
+    // Some Registries send `null` for a repository without tags.
+    tags: Option<Vec<String>>,
 }
 
 impl StdError for ClientError {}
@@ -72,45 +114,115 @@ impl From<std::io::Error> for ClientError {
     }
 }
 
+impl From<InvalidUri> for ClientError {
+    fn from(e: InvalidUri) -> Self {
+        ClientError(format!("Invalid URI: {}", e))
+    }
+}
+
+impl From<DigestError> for ClientError {
+    fn from(e: DigestError) -> Self {
+        ClientError(e.to_string())
+    }
+}
+
 impl From<ClientError> for ImageError {
     fn from(e: ClientError) -> Self {
         ImageError::new().with(e)
     }
 }
 
+/// A Docker Client along with the path of the repository at the Registry.
+///
+/// The path may be different from the path of the reference, when the reference is rewritten to
+/// a different location (see `registries.conf`).
+#[derive(Debug)]
+pub(super) struct DockerEndpoint {
+    pub(super) client: DockerClient,
+    pub(super) path: String,
+}
+
+impl DockerClient {
+    /// Returns the Registry (`host[:port]`) the client is for.
+    pub(super) fn registry(&self) -> &str {
+        &self.registry
+    }
+}
+
+impl DockerEndpoint {
+    /// Returns the fully qualified name (`domain/path`) of the repository at the endpoint.
+    pub(super) fn name(&self) -> String {
+        format!("{}/{}", self.client.registry, self.path)
+    }
+}
+
 /// Structure representing a Client for Docker Repository
 #[derive(Debug)]
 pub(super) struct DockerClient {
-    https_client: HyperClient<HttpsConnector<HttpConnector>, Body>,
+    https_client: HyperClient<ProxyConnector<HttpsConnector<HttpConnector>>, Body>,
+    // Required for adding the proxy headers to the `http` requests.
+    proxy_connector: ProxyConnector<HttpsConnector<HttpConnector>>,
+    // The Registry (`host[:port]`) the Rate Limits reported are saved for.
+    registry: String,
     repo_url: Uri,
-    // FIXME: This should be a Map of <scope, BearerToken>
-    bearer_token: RwLock<Option<BearerToken>>,
+    // Bearer Tokens keyed by their scope (`repository:<path>:<actions>`)
+    bearer_tokens: RwLock<HashMap<String, BearerToken>>,
     auth_required: RwLock<bool>,
+    // Set when the Registry answers with a `Basic` challenge.
+    basic_auth: RwLock<bool>,
+    credentials: Option<Credentials>,
+    // OAuth2 refresh token, from the credentials or returned by the realm.
+    refresh_token: RwLock<Option<String>>,
+    // Refresh tokens are stored in the auth files only for the credentials read from them.
+    persist_refresh_token: bool,
+    // Set if the realm does not support the OAuth2 `POST` flow, so that it is not tried again.
+    oauth2_unsupported: RwLock<bool>,
+    retry_policy: RetryPolicy,
+    // Cleared when there are other endpoints to try, if the Registry can't be connected to.
+    retry_connect_errors: RwLock<bool>,
 }
 
 impl DockerClient {
     /// Creates a New Docker Client from the Repository URL
-    pub(super) fn new(repository: &str) -> Self {
-        // We let panic if the Repo URL is not parseable
+    ///
+    /// If the URL does not have a scheme, `http` is used for an `insecure` Registry and `https`
+    /// otherwise. If the Registry is not configured (`insecure` is `None`, see `registries.conf`),
+    /// `http` is used for a URL with a port.
+    ///
+    /// The requests are sent through the proxies from the environment (see `ProxyConfig`).
+    ///
+    /// Returns an error if the Repository URL is not valid.
+    pub(super) fn new(repository: &str, insecure: Option<bool>) -> Result<Self, ClientError> {
+        Self::new_with_proxy(repository, insecure, &ProxyConfig::from_env())
+    }
 
+    // Creates a New Docker Client, that sends requests through the proxies in `proxy_config`.
+    fn new_with_proxy(
+        repository: &str,
+        insecure: Option<bool>,
+        proxy_config: &ProxyConfig,
+    ) -> Result<Self, ClientError> {
         let mut repo_url: Uri;
         if repository == DEFAULT_DOCKER_DOMAIN {
             repo_url = DOCKER_REGISTRY_V2_HTTPS_URL.parse::<Uri>().unwrap();
         } else {
-            repo_url = repository.parse::<Uri>().unwrap();
+            repo_url = repository.parse::<Uri>().map_err(|e| {
+                log::error!("Invalid Registry '{}': {}", repository, e);
+                ClientError(format!("Invalid Registry '{}': {}", repository, e))
+            })?;
 
-            let scheme = if repo_url.port().is_some() {
-                "http"
-            } else {
-                "https"
-            };
+            let insecure = insecure.unwrap_or_else(|| repo_url.port().is_some());
+            let scheme = if insecure { "http" } else { "https" };
             if repo_url.scheme().is_none() {
                 repo_url = Uri::builder()
                     .scheme(scheme)
                     .authority(repository)
                     .path_and_query("/")
                     .build()
-                    .unwrap();
+                    .map_err(|e| {
+                        log::error!("Invalid Registry '{}': {}", repository, e);
+                        ClientError(format!("Invalid Registry '{}': {}", repository, e))
+                    })?;
             }
         }
 
@@ -122,54 +234,198 @@ impl DockerClient {
             log::warn!("Using Non HTTPS scheme for the URL.");
         }
 
-        let mut https_connector = HttpsConnector::new();
+        // Use the certificates for the Registry (if any) for TLS connections. Invalid certificates
+        // are an error, rather than connecting without them (eg. without the client identity).
+        let host = repo_url.authority().map(|a| a.as_str()).unwrap_or_default();
+        let tls_connector = tls_connector_for_host(host).map_err(|e| {
+            log::error!("Invalid Certificates for '{}': {}", host, e);
+            ClientError(format!("Invalid Certificates for '{}': {}", host, e))
+        })?;
+        let tls_connector = match tls_connector {
+            Some(tls_connector) => tls_connector,
+            None => native_tls::TlsConnector::new().map_err(|e| {
+                log::error!("Error in Creating the TLS Connector: {}", e);
+                ClientError(format!("Error in Creating the TLS Connector: {}", e))
+            })?,
+        };
+
+        let mut http_connector = HttpConnector::new();
+        http_connector.enforce_http(false);
+        let mut https_connector =
+            HttpsConnector::from((http_connector, tls_connector.clone().into()));
         https_connector.https_only(https_only);
 
-        let https_client = HyperClient::builder().build(https_connector);
+        // TLS connections tunneled through a proxy, use the same certificates.
+        let mut proxy_connector = ProxyConnector::unsecured(https_connector);
+        proxy_connector.set_tls(Some(tls_connector));
+        proxy_connector.extend_proxies(proxy_config.proxies());
+
+        let https_client = HyperClient::builder().build(proxy_connector.clone());
+
+        let credentials = get_credentials(repository);
+        let refresh_token = credentials.as_ref().and_then(|c| c.identity_token.clone());
 
-        DockerClient {
+        Ok(DockerClient {
             https_client,
+            proxy_connector,
+            registry: normalize_registry(repository),
             repo_url,
-            bearer_token: RwLock::new(None),
+            bearer_tokens: RwLock::new(HashMap::new()),
             auth_required: RwLock::new(true),
+            basic_auth: RwLock::new(false),
+            credentials,
+            refresh_token: RwLock::new(refresh_token),
+            persist_refresh_token: true,
+            oauth2_unsupported: RwLock::new(false),
+            retry_policy: retry_policy(),
+            retry_connect_errors: RwLock::new(true),
+        })
+    }
+
+    /// Sets the Credentials used for authenticating with the Registry.
+    pub(super) fn set_credentials(&mut self, credentials: Option<Credentials>) {
+        *self.refresh_token.write().unwrap() =
+            credentials.as_ref().and_then(|c| c.identity_token.clone());
+        self.persist_refresh_token = false;
+        self.credentials = credentials;
+    }
+
+    /// Sets whether the requests failing to connect to the Registry are retried (as per the
+    /// `RetryPolicy`). They need not be, if there are other endpoints (eg. mirrors) to try.
+    pub(super) fn set_retry_connect_errors(&self, retry: bool) {
+        *self.retry_connect_errors.write().unwrap() = retry;
+    }
+
+    /// Returns the OAuth2 refresh token, if the realm returned one (or it was in the Credentials).
+    pub(super) fn refresh_token(&self) -> Option<String> {
+        self.refresh_token.read().unwrap().clone()
+    }
+
+    /// Verifies the Credentials with the Registry.
+    ///
+    /// For a Registry using `Basic` auth, the credentials are verified against the `/v2/`
+    /// endpoint, otherwise a token is requested from the realm (of the `Bearer` challenge) for the
+    /// credentials, using the OAuth2 `POST` flow if the realm supports it.
+    pub(super) async fn do_verify_credentials(&self) -> Result<(), ClientError> {
+        let credentials = match &self.credentials {
+            Some(credentials) => credentials,
+            None => return crate::log_err_return!(ClientError, "No Credentials to verify."),
+        };
+
+        let response = self.ping_repository().await?;
+        if response.status().is_success() {
+            log::warn!(
+                "Registry '{}' does not require authentication.",
+                self.repo_url
+            );
+            return Ok(());
+        }
+
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return crate::log_err_return!(
+                ClientError,
+                "Error Checking Registry: {}",
+                response.status()
+            );
+        }
+
+        let www_auth_header = match response.headers().get(WWW_AUTHENTICATE) {
+            Some(header) => header,
+            None => {
+                return crate::log_err_return!(
+                    ClientError,
+                    "No 'WWW-Authenticate' Header found with {}",
+                    response.status()
+                )
+            }
+        };
+
+        let verify_url = if challenge_scheme(www_auth_header).eq_ignore_ascii_case("basic") {
+            format!("{}v2/", self.repo_url)
+        } else {
+            match challenge_realm_service(www_auth_header) {
+                Some((realm, service)) => {
+                    if self.oauth2_token(realm, service, None).await?.is_some() {
+                        return Ok(());
+                    }
+                    format!(
+                        "{}?{}",
+                        realm,
+                        form_urlencoded(&[
+                            ("account", &credentials.username),
+                            ("service", service)
+                        ])
+                    )
+                }
+                None => {
+                    return crate::log_err_return!(
+                        ClientError,
+                        "Invalid 'WWW-Authenticate' Header: {:?}",
+                        www_auth_header
+                    )
+                }
+            }
+        };
+
+        let verify_url = match verify_url.parse::<Uri>() {
+            Ok(uri) => uri,
+            Err(e) => return crate::log_err_return!(ClientError, "Invalid URL: {}", e),
+        };
+
+        log::trace!("Verifying Credentials with {}", verify_url);
+        let response = self
+            .send_with_retries(|| {
+                Request::get(verify_url.clone())
+                    .header(AUTHORIZATION, credentials.basic_auth_header())
+                    .body(Body::empty())
+                    .unwrap()
+            })
+            .await?;
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => crate::log_err_return!(
+                ClientError,
+                "Invalid Credentials for '{}'.",
+                credentials.username
+            ),
+            status => {
+                crate::log_err_return!(ClientError, "Error Verifying Credentials: {}", status)
+            }
         }
     }
 
-    // FIXME: Handle taking 'body' as input
     /// Returns `Response` if it's a valid response or `ClientError`
-    async fn perform_http_request<M, U>(
+    ///
+    /// The request is authorized for the `path` and `scope` (see `perform_authorized_request`).
+    async fn perform_http_request(
         &self,
-        url: U,
-        method: M,
+        path: &str,
+        scope: &str,
+        url: &str,
+        method: HttpMethod,
         headers: Option<&HeaderMap>,
         handle_redirects: bool,
-    ) -> Result<Response<Body>, ClientError>
-    where
-        HttpMethod: TryFrom<M>,
-        <HttpMethod as TryFrom<M>>::Error: Into<HttpError>,
-        Uri: TryFrom<U>,
-        <Uri as TryFrom<U>>::Error: Into<HttpError>,
-        M: Copy,
-    {
-        let mut request = Request::builder()
-            .method(method)
-            .uri(url)
-            .body(Body::from(""))
-            .unwrap();
-
-        if headers.is_some() {
-            let headers = headers.unwrap();
-            let req_headers = request.headers_mut();
-            for (key, value) in headers {
-                req_headers.insert(key, value.clone());
-            }
-        }
+    ) -> Result<Response<Body>, ClientError> {
+        let response = self
+            .perform_authorized_request(path, scope, || {
+                let mut request = Request::builder()
+                    .method(method.clone())
+                    .uri(url)
+                    .body(Body::from(""))
+                    .unwrap();
 
-        log::trace!("Sending Request: {:#?}", request);
-        let response = self.https_client.request(request).await?;
+                if let Some(headers) = headers {
+                    let req_headers = request.headers_mut();
+                    for (key, value) in headers {
+                        req_headers.insert(key, value.clone());
+                    }
+                }
+                request
+            })
+            .await?;
         let status = response.status();
 
-        if status.is_success() {
+        if status.is_success() || is_range_not_satisfiable(&response, headers) {
             log::trace!("Downloaded Successfully!");
             Ok(response)
         } else {
@@ -181,31 +437,30 @@ impl DockerClient {
                 );
             }
             if status.is_redirection() {
-                if !response.headers().contains_key(LOCATION) {
-                    let loc = LOCATION;
-
-                    return crate::log_err_return!(
-                        ClientError,
-                        "Redirect received but no {:?} Header.",
-                        loc
-                    );
-                }
-                let redirect_url = response.headers().get(LOCATION).unwrap().to_str().unwrap();
+                let redirect_url = self.location_url(&response)?;
                 log::trace!(
                     "Received Redirect to: {:?}. Trying to download.",
                     redirect_url
                 );
 
-                let request = Request::builder()
-                    .method(method)
-                    .uri::<&str>(redirect_url)
-                    .body(Body::from(""))
-                    .unwrap();
-
-                log::trace!("Sending Request: {:#?}", request);
-                let response = self.https_client.request(request).await?;
+                let response = self
+                    .send_with_retries(|| {
+                        let mut request = Request::builder()
+                            .method(method.clone())
+                            .uri(redirect_url.clone())
+                            .body(Body::from(""))
+                            .unwrap();
+
+                        // Auth headers are not sent to the redirect target, the others are (eg.
+                        // `Range`).
+                        if let Some(headers) = headers {
+                            request.headers_mut().extend(headers.clone());
+                        }
+                        request
+                    })
+                    .await?;
                 let status = response.status();
-                if status.is_success() {
+                if status.is_success() || is_range_not_satisfiable(&response, headers) {
                     return Ok(response);
                 }
             }
@@ -214,12 +469,262 @@ impl DockerClient {
         }
     }
 
-    /// Actually Get the manifest using the current client
+    // Sends the request returned by `make_request` after adding the auth headers for the `path`
+    // and `scope` to it. Returns the `Response` irrespective of it's status.
+    //
+    // If the Registry responds with a 401 (eg. the token has expired or was revoked), the
+    // challenge in the response is answered and the request is retried once. Transient failures
+    // are retried as per the `RetryPolicy`.
+    async fn perform_authorized_request<F>(
+        &self,
+        path: &str,
+        scope: &str,
+        make_request: F,
+    ) -> Result<Response<Body>, ClientError>
+    where
+        F: Fn() -> Request<Body>,
+    {
+        let mut retried = false;
+        loop {
+            let auth_headers = self.get_auth_headers(path, scope).await?;
+            let response = self
+                .send_with_retries(|| {
+                    let mut request = make_request();
+                    request.headers_mut().extend(auth_headers.clone());
+                    request
+                })
+                .await?;
+            if response.status() != StatusCode::UNAUTHORIZED || retried {
+                return Ok(response);
+            }
+
+            let www_auth_header = match response.headers().get(WWW_AUTHENTICATE) {
+                Some(header) => header.clone(),
+                None => return Ok(response),
+            };
+
+            log::debug!(
+                "Received 401 for '{}', Refreshing the Token for Scope '{}'.",
+                path,
+                scope
+            );
+            self.authenticate_with_challenge(path, scope, &www_auth_header)
+                .await?;
+            retried = true;
+        }
+    }
+
+    // Sends the request using the `https_client`. All the requests (including the ones to the
+    // auth realm and the redirects) are sent using this, so that the proxy headers are added.
+    fn request(&self, mut request: Request<Body>) -> ResponseFuture {
+        if let Some(headers) = self.proxy_connector.http_headers(request.uri()) {
+            request.headers_mut().extend(headers.clone());
+        }
+
+        self.https_client.request(request)
+    }
+
+    // Sends the request returned by `make_request`, retrying it as per the `RetryPolicy`, if it
+    // fails due to a transient error or is rate limited. Returns the `Response` of the first
+    // attempt that does not fail due to a transient error, or the error of the final attempt.
+    async fn send_with_retries<F>(&self, make_request: F) -> Result<Response<Body>, ClientError>
+    where
+        F: Fn() -> Request<Body>,
+    {
+        let policy = &self.retry_policy;
+        let mut attempt = 1;
+        let mut rate_limited = 0;
+        let mut rate_limit_delay = std::time::Duration::ZERO;
+        loop {
+            let request = make_request();
+            let method = request.method().clone();
+            let uri = request.uri().clone();
+
+            log::trace!("Sending Request (Attempt: {}): {:#?}", attempt, request);
+            let result = match policy.timeout {
+                Some(timeout) => tokio::time::timeout(timeout, self.request(request)).await,
+                None => Ok(self.request(request).await),
+            };
+
+            // Rate limited requests are retried as long as the total delay is within the budget,
+            // irrespective of the number of attempts.
+            if let Ok(Ok(response)) = &result {
+                update_rate_limit(&self.registry, response.headers());
+
+                if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                    rate_limited += 1;
+                    let delay = retry_after(response.headers())
+                        .unwrap_or_else(|| policy.backoff(rate_limited));
+                    let quota = match rate_limit_from_headers(response.headers()) {
+                        Some(rate_limit) => format!(
+                            " (Remaining: {} of {})",
+                            rate_limit.remaining, rate_limit.limit
+                        ),
+                        None => String::new(),
+                    };
+
+                    if rate_limit_delay + delay > policy.rate_limit_budget {
+                        return crate::log_err_return!(
+                            ClientError,
+                            "{} {} failed: Rate Limited by the Registry{}, Retry after {:?} exceeds the Rate Limit Budget ({:?} of {:?} used).",
+                            method,
+                            uri,
+                            quota,
+                            delay,
+                            rate_limit_delay,
+                            policy.rate_limit_budget
+                        );
+                    }
+
+                    log::warn!(
+                        "{} {} Rate Limited by the Registry{}. Retrying in {:?}.",
+                        method,
+                        uri,
+                        quota,
+                        delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    rate_limit_delay += delay;
+                    continue;
+                }
+            }
+
+            let (error, delay) = match result {
+                Ok(Ok(response)) if is_retryable_status(response.status()) => (
+                    response.status().to_string(),
+                    retry_after(response.headers()),
+                ),
+                Ok(Ok(response)) => {
+                    log::trace!("Received Response: {:#?}", response);
+                    return Ok(response);
+                }
+                Ok(Err(e)) if e.is_connect() && !*self.retry_connect_errors.read().unwrap() => {
+                    return crate::log_err_return!(ClientError, "{} {} failed: {}", method, uri, e)
+                }
+                Ok(Err(e)) if is_transient_error(&e) => (e.to_string(), None),
+                Err(_) => (
+                    format!("Timed out after {:?}", policy.timeout.unwrap_or_default()),
+                    None,
+                ),
+                Ok(Err(e)) => {
+                    return crate::log_err_return!(ClientError, "{} {} failed: {}", method, uri, e)
+                }
+            };
+
+            if attempt >= policy.max_attempts {
+                return crate::log_err_return!(
+                    ClientError,
+                    "{} {} failed after {} attempt(s): {}",
+                    method,
+                    uri,
+                    attempt,
+                    error
+                );
+            }
+
+            // Not waiting longer than the backoff, even if the Registry asks for it.
+            let delay = delay
+                .map(|delay| delay.min(policy.max_backoff))
+                .unwrap_or_else(|| policy.backoff(attempt));
+            log::warn!(
+                "{} {} failed (Attempt {} of {}): {}. Retrying in {:?}.",
+                method,
+                uri,
+                attempt,
+                policy.max_attempts,
+                error,
+                delay
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    // Sends the request as it is and returns the `Response` irrespective of it's status. Used
+    // by the APIs that need to look at the status of the response (eg. upload APIs).
+    async fn send_request(&self, request: Request<Body>) -> Result<Response<Body>, ClientError> {
+        log::trace!("Sending Request: {:#?}", request);
+        let response = self.request(request).await?;
+        log::trace!("Received Response: {:#?}", response);
+
+        Ok(response)
+    }
+
+    // Returns the headers required for authorization for the `path` and `scope`.
+    //
+    // This will get the bearer token and store it if required.
+    async fn get_auth_headers(&self, path: &str, scope: &str) -> Result<HeaderMap, ClientError> {
+        self.get_bearer_token_for_path_scope(path, Some(scope))
+            .await?;
+
+        let mut headers = HeaderMap::new();
+        if *self.auth_required.read().unwrap() {
+            let auth_header = if *self.basic_auth.read().unwrap() {
+                // Credentials are always present for `Basic` auth.
+                self.credentials.as_ref().unwrap().basic_auth_header()
+            } else {
+                let bearer_tokens = self.bearer_tokens.read().unwrap();
+                match bearer_tokens.get(&token_scope_key(path, scope)) {
+                    Some(bearer_token) => format!("Bearer {}", bearer_token.token),
+                    None => {
+                        return crate::log_err_return!(
+                            ClientError,
+                            "No Bearer Token for '{}'.",
+                            token_scope_key(path, scope)
+                        );
+                    }
+                }
+            };
+            headers.insert(AUTHORIZATION, auth_header.parse().unwrap());
+        }
+
+        Ok(headers)
+    }
+
+    // Returns the absolute URL from the 'Location' header of the response. Registries usually
+    // return a URL relative to the repository URL during uploads.
+    fn location_url(&self, response: &Response<Body>) -> Result<Uri, ClientError> {
+        let location = match response.headers().get(LOCATION) {
+            Some(location) => location.to_str().unwrap_or_default(),
+            None => {
+                return crate::log_err_return!(
+                    ClientError,
+                    "No {:?} Header in the Response with {}.",
+                    LOCATION,
+                    response.status()
+                );
+            }
+        };
+
+        let url = if location.starts_with("http://") || location.starts_with("https://") {
+            location.to_string()
+        } else {
+            format!(
+                "{}://{}{}",
+                self.repo_url.scheme_str().unwrap_or("https"),
+                self.repo_url.authority().unwrap(),
+                location
+            )
+        };
+
+        url.parse::<Uri>().map_err(|e| {
+            log::error!("Invalid {:?} Header '{}': {}", LOCATION, location, e);
+            ClientError(format!(
+                "Invalid {:?} Header '{}': {}",
+                LOCATION, location, e
+            ))
+        })
+    }
+
+    /// Gets the manifest for the `digest_or_tag`.
+    ///
+    /// Returns the manifest along with the digest from the `Docker-Content-Digest` header (if
+    /// any). The manifest is not verified here (see `DockerSource`).
     pub(super) async fn do_get_manifest(
         &self,
         path: &str,
         digest_or_tag: &str,
-    ) -> Result<ImageManifest, ClientError> {
+    ) -> Result<(ImageManifest, Option<Digest>), ClientError> {
         let manifest_url = format!("{}v2/{}/manifests/{}", self.repo_url, path, digest_or_tag);
         log::debug!("Getting Manifest: {}", manifest_url);
 
@@ -228,20 +733,15 @@ impl DockerClient {
         let accept_header = DEFAULT_SUPPORTED_MANIFESTS.join(", ");
         headers.insert(ACCEPT, accept_header.parse().unwrap());
 
-        // This will get the bearer token and store it.
-        self.get_bearer_token_for_path_scope(path, Some("pull"))
-            .await?;
-
-        if *self.auth_required.read().unwrap() {
-            let auth_header = format!(
-                "Bearer {}",
-                self.bearer_token.read().unwrap().as_ref().unwrap().token
-            );
-            headers.insert(AUTHORIZATION, auth_header.parse().unwrap());
-        }
-
         let response = self
-            .perform_http_request(manifest_url, "GET", Some(&headers), true)
+            .perform_http_request(
+                path,
+                "pull",
+                &manifest_url,
+                HttpMethod::GET,
+                Some(&headers),
+                true,
+            )
             .await?;
         let mime_type = response
             .headers()
@@ -250,11 +750,55 @@ impl DockerClient {
             .to_str()
             .unwrap()
             .to_string();
+        let content_digest = content_digest(response.headers());
 
-        Ok(ImageManifest {
+        let manifest = ImageManifest {
             manifest: to_bytes(response).await?.to_vec(),
             mime_type,
-        })
+        };
+        Ok((manifest, content_digest))
+    }
+
+    /// Resolves the `digest_or_tag` to the digest of the manifest, without downloading it.
+    ///
+    /// A `HEAD` request for the manifest is sent and the digest from the `Docker-Content-Digest`
+    /// header is returned. If the Registry does not support it, the manifest is downloaded and
+    /// it's digest is returned.
+    pub(super) async fn do_resolve_digest(
+        &self,
+        path: &str,
+        digest_or_tag: &str,
+    ) -> Result<Digest, ClientError> {
+        let manifest_url = format!("{}v2/{}/manifests/{}", self.repo_url, path, digest_or_tag);
+        log::debug!("Resolving Digest: {}", manifest_url);
+
+        let mut headers = HeaderMap::new();
+        let accept_header = DEFAULT_SUPPORTED_MANIFESTS.join(", ");
+        headers.insert(ACCEPT, accept_header.parse().unwrap());
+
+        match self
+            .perform_http_request(
+                path,
+                "pull",
+                &manifest_url,
+                HttpMethod::HEAD,
+                Some(&headers),
+                true,
+            )
+            .await
+        {
+            Ok(response) => {
+                if let Some(digest) = content_digest(response.headers()) {
+                    log::trace!("Resolved '{}' to '{}'.", digest_or_tag, digest);
+                    return Ok(digest);
+                }
+                log::debug!("No {} Header, Getting the Manifest.", DOCKER_CONTENT_DIGEST);
+            }
+            Err(e) => log::debug!("HEAD request failed ({}), Getting the Manifest.", e),
+        }
+
+        let (manifest, _) = self.do_get_manifest(path, digest_or_tag).await?;
+        Ok(Digest::from_bytes(&manifest.manifest))
     }
 
     pub(super) async fn do_get_blob(
@@ -271,113 +815,617 @@ impl DockerClient {
 
         cache_path.push(digest.hex_digest());
 
-        if cache_path.exists() {
-            log::trace!("Blob exists locally, verifying...{:?}", &cache_path);
-
-            let mut f = File::open(&cache_path).await?;
+        // The Blob is downloaded to the cache by one client (or process) at a time, the others
+        // wait and get the cached Blob. The lock file is kept (never removed), so that all of them
+        // lock the same file.
+        let lock_path = cache_path.with_extension("lock");
+        let _lock = lock_file(&lock_path).await?;
 
-            let digest_matches = digest.verify(&mut f).await;
+        if cache_path.exists() {
+            // Verified as it is read, a corrupted Blob fails the read at the end.
+            log::trace!("Returning cached Blob: {:?}", &cache_path);
+            let f = File::open(&cache_path).await?;
 
-            drop(f);
+            return Ok(Box::new(DigestReader::new(f, digest)?));
+        }
 
-            if digest_matches {
-                log::trace!("Returning cached Blob.");
-                let f = File::open(cache_path).await?;
+        // The partially downloaded Blob is kept next to the cached Blobs, so that an interrupted
+        // download (even from an earlier run) can be resumed using a `Range` request.
+        let blobpath = cache_path.with_extension("partial");
+
+        // The Digest is computed as the Blob is downloaded, so that the Blob is not read again for
+        // verification.
+        let mut verifier: Option<DigestVerifier> = None;
+        let mut attempt = 1;
+        loop {
+            let mut offset = match tokio::fs::metadata(&blobpath).await {
+                Ok(metadata) => metadata.len(),
+                Err(_) => 0,
+            };
 
-                return Ok(Box::new(f));
-            } else {
-                log::trace!("Digest does not match, deleting cached Blob.");
-                tokio::fs::remove_file(&cache_path).await?;
+            let mut headers = HeaderMap::new();
+            if offset > 0 {
+                log::debug!("Resuming download of Blob {} from {}.", digest, offset);
+                headers.insert(RANGE, format!("bytes={}-", offset).parse().unwrap());
             }
-        }
-
-        log::trace!("Downloading Blob from the Registry...");
 
-        // This will get the bearer token and store it if required.
-        self.get_bearer_token_for_path_scope(path, Some("pull"))
-            .await?;
+            log::trace!("Downloading Blob from the Registry...");
+            let response = self
+                .perform_http_request(
+                    path,
+                    "pull",
+                    &blob_url_path,
+                    HttpMethod::GET,
+                    Some(&headers),
+                    true,
+                )
+                .await?;
+
+            // The partial download is not valid (eg. longer than the Blob), start afresh.
+            let status = response.status();
+            if offset > 0
+                && (status == StatusCode::RANGE_NOT_SATISFIABLE
+                    || (status == StatusCode::PARTIAL_CONTENT
+                        && !is_partial_content_from(&response, offset)))
+            {
+                log::warn!(
+                    "Resuming download of Blob {} failed: {}, Downloading afresh.",
+                    digest,
+                    status
+                );
+                tokio::fs::remove_file(&blobpath).await?;
+                verifier = None;
+                continue;
+            }
 
-        let mut headers = HeaderMap::new();
-        if *self.auth_required.read().unwrap() {
-            let auth_header = format!(
-                "Bearer {}",
-                self.bearer_token.read().unwrap().as_ref().unwrap().token
-            );
-            headers.insert(AUTHORIZATION, auth_header.parse().unwrap());
-        }
+            // Registries (or the storage they redirect to) not supporting ranges, send the whole
+            // Blob.
+            if offset > 0 && !is_partial_content_from(&response, offset) {
+                log::debug!(
+                    "Range not supported for Blob {}, Downloading afresh.",
+                    digest
+                );
+                offset = 0;
+            }
 
-        let response = self
-            .perform_http_request(blob_url_path, "GET", Some(&headers), true)
-            .await?;
+            let verifier = match verifier.as_mut() {
+                Some(verifier) if offset > 0 => verifier,
+                _ => {
+                    let mut new_verifier = DigestVerifier::new(digest)?;
+                    // Resuming the download from an earlier run.
+                    if offset > 0 {
+                        let mut f = File::open(&blobpath).await?;
+                        let mut buf = vec![0; 16384];
+                        loop {
+                            let n = f.read(&mut buf).await?;
+                            if n == 0 {
+                                break;
+                            }
+                            new_verifier.update(&buf[..n]);
+                        }
+                    }
+                    verifier.insert(new_verifier)
+                }
+            };
 
-        log::trace!("Saving downloaded blob to local cache.");
+            log::trace!("Saving downloaded blob to local cache.");
+            let mut f = if offset > 0 {
+                tokio::fs::OpenOptions::new()
+                    .append(true)
+                    .open(&blobpath)
+                    .await?
+            } else {
+                File::create(&blobpath).await?
+            };
 
-        let mut blobpath = std::env::temp_dir();
-        blobpath.push("blobs");
-        blobpath.push(digest.algorithm());
-        tokio::fs::create_dir_all(&blobpath).await?;
+            let mut body = response.into_body();
+            let mut body_error = None;
+            let mut received = 0;
+            while let Some(data) = body.next().await {
+                match data {
+                    Ok(data) => {
+                        f.write_all(&data).await?;
+                        verifier.update(&data);
+                        received += data.len();
+                    }
+                    Err(e) => {
+                        body_error = Some(e);
+                        break;
+                    }
+                }
+            }
+            f.flush().await?;
 
-        blobpath.push(digest.hex_digest());
-        let mut f = File::create(&blobpath).await?;
+            // The attempts that made progress are not counted.
+            if received > 0 {
+                attempt = 1;
+            }
 
-        let mut body = response.into_body();
-        while let Some(data) = body.next().await {
-            let data = data?;
-            f.write_all(&data).await?;
+            match body_error {
+                None => break,
+                Some(e) if is_transient_error(&e) && attempt < self.retry_policy.max_attempts => {
+                    let delay = self.retry_policy.backoff(attempt);
+                    log::warn!(
+                        "Downloading Blob {} failed (Attempt {} of {}): {}. Retrying in {:?}.",
+                        digest,
+                        attempt,
+                        self.retry_policy.max_attempts,
+                        e,
+                        delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Some(e) => {
+                    return crate::log_err_return!(
+                        ClientError,
+                        "Downloading Blob {} failed after {} attempt(s): {}",
+                        digest,
+                        attempt,
+                        e
+                    )
+                }
+            }
         }
-        f.flush().await?;
 
         log::trace!("Blobpath: {:?}", &blobpath);
 
-        let mut f = File::open(&blobpath).await?;
-        let result = digest.verify(&mut f).await;
-        if !result {
+        if let Err(e) = verifier.take().unwrap().verify() {
+            tokio::fs::remove_file(&blobpath).await?;
             return crate::log_err_return!(
                 ClientError,
-                "Digest Verification failed for Digest: {}",
-                digest
+                "Digest Verification failed for Digest: {}: {}",
+                digest,
+                e
             );
         }
 
         tokio::fs::rename(&blobpath, &cache_path).await?;
 
+        // The Blob can be mounted from here, when pushed to the same Registry.
+        record_blob_location(digest, &self.registry, path).await;
+
         let f = File::open(cache_path).await?;
 
         Ok(Box::new(f))
     }
 
+    /// Gets all the tags for the repository, following the pages of the tags list.
     pub(super) async fn do_get_repo_tags(&self, path: &str) -> Result<Vec<String>, ClientError> {
         log::debug!("Getting Tags for the Repository: {}", path);
-        let all_tags_url = format!("{}v2/{}/tags/list", self.repo_url, path);
-
-        // This will get the bearer token and store it if required.
-        self.get_bearer_token_for_path_scope(path, Some("pull"))
-            .await?;
 
-        let mut headers = HeaderMap::new();
-        if *self.auth_required.read().unwrap() {
-            let auth_header = format!(
-                "Bearer {}",
-                self.bearer_token.read().unwrap().as_ref().unwrap().token
-            );
-            headers.insert(AUTHORIZATION, auth_header.parse().unwrap());
+        let mut all_tags = vec![];
+        let mut next_url = Some(self.tags_list_url(path, None, None));
+        while let Some(url) = next_url {
+            let (tags, next) = self.do_get_repo_tags_page(path, &url).await?;
+            all_tags.extend(tags);
+            next_url = next;
         }
 
+        Ok(all_tags)
+    }
+
+    /// Returns the URL of the tags list for the repository.
+    ///
+    /// At most `n` tags are returned in the list, starting after the tag `last` (if specified).
+    pub(super) fn tags_list_url(&self, path: &str, n: Option<usize>, last: Option<&str>) -> String {
+        with_page_params(format!("{}v2/{}/tags/list", self.repo_url, path), n, last)
+    }
+
+    /// Gets a page of the tags list at the `url` (see `tags_list_url`).
+    ///
+    /// Returns the tags along with the URL of the next page, if the Registry sends a `Link`
+    /// header for it.
+    pub(super) async fn do_get_repo_tags_page(
+        &self,
+        path: &str,
+        url: &str,
+    ) -> Result<(Vec<String>, Option<String>), ClientError> {
+        log::debug!("Getting Tags: {}", url);
+
         let response = self
-            .perform_http_request(all_tags_url, "GET", Some(&headers), true)
+            .perform_http_request(path, "pull", url, HttpMethod::GET, None, true)
             .await?;
+        let next_url = next_link_url(response.headers(), &self.repo_url, url)?;
 
         let taginfo: TagInfo = serde_json::from_slice(&to_bytes(response).await?)?;
-        log::trace!("Received Tags: {:?}", taginfo);
+        log::trace!("Received Tags: {:?}, Next: {:?}", taginfo, next_url);
 
-        Ok(taginfo.tags)
+        Ok((taginfo.tags.unwrap_or_default(), next_url))
     }
 
-    #[doc(hidden)]
-    /// Performs API version check against the Docker Registry V2 API.
-    ///
-    /// Once the bearer token is obtained, it is cached at the client, so that we do not have to
-    /// get one for every API use.
+    /// Gets all the repositories in the Registry, following the pages of the catalog.
+    pub(super) async fn do_get_catalog(&self) -> Result<Vec<String>, ClientError> {
+        log::debug!("Getting Catalog for the Registry: {}", self.repo_url);
+
+        let mut all_repositories = vec![];
+        let mut next_url = Some(self.catalog_url(None, None));
+        while let Some(url) = next_url {
+            let (repositories, next) = self.do_get_catalog_page(&url).await?;
+            all_repositories.extend(repositories);
+            next_url = next;
+        }
+
+        Ok(all_repositories)
+    }
+
+    /// Returns the URL of the catalog of the Registry.
+    ///
+    /// At most `n` repositories are returned in the catalog, starting after the repository `last`
+    /// (if specified).
+    pub(super) fn catalog_url(&self, n: Option<usize>, last: Option<&str>) -> String {
+        with_page_params(format!("{}v2/_catalog", self.repo_url), n, last)
+    }
+
+    /// Gets a page of the catalog at the `url` (see `catalog_url`).
+    ///
+    /// Returns the repositories along with the URL of the next page, if the Registry sends a
+    /// `Link` header for it.
+    pub(super) async fn do_get_catalog_page(
+        &self,
+        url: &str,
+    ) -> Result<(Vec<String>, Option<String>), ClientError> {
+        log::debug!("Getting Catalog: {}", url);
+
+        let response = self
+            .perform_http_request(CATALOG_PATH, "*", url, HttpMethod::GET, None, true)
+            .await?;
+        let next_url = next_link_url(response.headers(), &self.repo_url, url)?;
+
+        let catalog: CatalogInfo = serde_json::from_slice(&to_bytes(response).await?)?;
+        log::trace!("Received Catalog: {:?}, Next: {:?}", catalog, next_url);
+
+        Ok((catalog.repositories.unwrap_or_default(), next_url))
+    }
+
+    /// Checks whether the blob with the `digest` is present in the repository.
+    pub(super) async fn do_check_blob(
+        &self,
+        path: &str,
+        digest: &Digest,
+    ) -> Result<bool, ClientError> {
+        let blob_url = format!("{}v2/{}/blobs/{}", self.repo_url, path, digest);
+        log::debug!("Checking Blob: {}", blob_url);
+
+        let response = self
+            .perform_authorized_request(path, PUSH_SCOPE, || {
+                Request::head(&blob_url).body(Body::empty()).unwrap()
+            })
+            .await?;
+        let status = response.status();
+
+        // Some registries redirect to the actual storage for the blobs.
+        if status.is_success() || status.is_redirection() {
+            Ok(true)
+        } else if status == StatusCode::NOT_FOUND {
+            Ok(false)
+        } else {
+            crate::log_err_return!(ClientError, "Error in Checking Blob: {}", status)
+        }
+    }
+
+    /// Deletes the manifest with the `digest` from the repository.
+    ///
+    /// Registries do not support deleting a manifest by tag, the tag should be resolved to the
+    /// digest first (see `do_resolve_digest`). All the tags for the manifest are deleted too.
+    pub(super) async fn do_delete_manifest(
+        &self,
+        path: &str,
+        digest: &Digest,
+    ) -> Result<(), ClientError> {
+        let manifest_url = format!("{}v2/{}/manifests/{}", self.repo_url, path, digest);
+        log::debug!("Deleting Manifest: {}", manifest_url);
+
+        let response = self
+            .perform_authorized_request(path, DELETE_SCOPE, || {
+                Request::delete(&manifest_url).body(Body::empty()).unwrap()
+            })
+            .await?;
+        let status = response.status();
+
+        match status {
+            StatusCode::ACCEPTED | StatusCode::OK | StatusCode::NO_CONTENT => Ok(()),
+            StatusCode::METHOD_NOT_ALLOWED => crate::log_err_return!(
+                ClientError,
+                "Deleting Manifest {} failed: Deletion is disabled on the Registry ({}).",
+                digest,
+                status
+            ),
+            StatusCode::NOT_FOUND => crate::log_err_return!(
+                ClientError,
+                "Deleting Manifest {} failed: Manifest not found in '{}' ({}).",
+                digest,
+                path,
+                status
+            ),
+            _ => crate::log_err_return!(
+                ClientError,
+                "Deleting Manifest {} failed: {}",
+                digest,
+                status
+            ),
+        }
+    }
+
+    /// Gets the descriptors of the manifests referring to the manifest with the `digest` (eg. the
+    /// SBOMs and the signatures for an image), of the `artifact_type` only (if specified).
+    ///
+    /// The Referrers API (`/v2/<name>/referrers/<digest>`) is used if the Registry supports it,
+    /// else the index tagged with the Referrers Tag Schema (`<alg>-<hex>`) is used.
+    pub(super) async fn do_get_referrers(
+        &self,
+        path: &str,
+        digest: &Digest,
+        artifact_type: Option<&str>,
+    ) -> Result<Vec<Descriptor>, ClientError> {
+        let mut referrers_url = format!("{}v2/{}/referrers/{}", self.repo_url, path, digest);
+        if let Some(artifact_type) = artifact_type {
+            // eg. `+` in `application/spdx+json` would be read as a space, if not encoded.
+            referrers_url.push('?');
+            referrers_url.push_str(&form_urlencoded(&[("artifactType", artifact_type)]));
+        }
+
+        let mut referrers = vec![];
+        let mut next_url = Some(referrers_url);
+        let mut first_page = true;
+        while let Some(url) = next_url {
+            log::debug!("Getting Referrers: {}", url);
+
+            let response = self
+                .perform_authorized_request(path, "pull", || {
+                    Request::get(&url)
+                        .header(ACCEPT, MEDIA_TYPE_IMAGE_INDEX)
+                        .body(Body::empty())
+                        .unwrap()
+                })
+                .await?;
+            let status = response.status();
+
+            if status == StatusCode::NOT_FOUND && first_page {
+                log::debug!("Referrers API not supported, Using the Referrers Tag Schema.");
+                return self
+                    .do_get_referrers_by_tag(path, digest, artifact_type)
+                    .await;
+            }
+            if !status.is_success() {
+                return crate::log_err_return!(
+                    ClientError,
+                    "Error in Getting Referrers: {}",
+                    status
+                );
+            }
+
+            next_url = next_link_url(response.headers(), &self.repo_url, &url)?;
+            let index: Index = serde_json::from_slice(&to_bytes(response).await?)?;
+            referrers.extend(index.manifests);
+            first_page = false;
+        }
+
+        // Registries may not apply the filter (indicated by the `OCI-Filters-Applied` header),
+        // filtering again is harmless.
+        Ok(filter_referrers(referrers, artifact_type))
+    }
+
+    // Gets the referrers from the index tagged with the Referrers Tag Schema (`<alg>-<hex>`), for
+    // the Registries not supporting the Referrers API. No such tag means no referrers.
+    async fn do_get_referrers_by_tag(
+        &self,
+        path: &str,
+        digest: &Digest,
+        artifact_type: Option<&str>,
+    ) -> Result<Vec<Descriptor>, ClientError> {
+        let tag = format!("{}-{}", digest.algorithm(), digest.hex_digest());
+        let manifest_url = format!("{}v2/{}/manifests/{}", self.repo_url, path, tag);
+        log::debug!("Getting Referrers Index: {}", manifest_url);
+
+        let response = self
+            .perform_authorized_request(path, "pull", || {
+                Request::get(&manifest_url)
+                    .header(ACCEPT, MEDIA_TYPE_IMAGE_INDEX)
+                    .body(Body::empty())
+                    .unwrap()
+            })
+            .await?;
+        let status = response.status();
+
+        if status == StatusCode::NOT_FOUND {
+            return Ok(vec![]);
+        }
+        if !status.is_success() {
+            return crate::log_err_return!(
+                ClientError,
+                "Error in Getting Referrers Index: {}",
+                status
+            );
+        }
+
+        let index: Index = serde_json::from_slice(&to_bytes(response).await?)?;
+        Ok(filter_referrers(index.manifests, artifact_type))
+    }
+
+    /// Uploads the blob to the repository.
+    ///
+    /// The blob is uploaded in a single `PATCH` request of an upload session. The registry
+    /// verifies the uploaded contents against the `digest` when the upload is completed.
+    pub(super) async fn do_put_blob(
+        &self,
+        path: &str,
+        blob: Box<dyn AsyncRead + Unpin + Send + Sync>,
+        digest: &Digest,
+        size: Option<u64>,
+    ) -> Result<(), ClientError> {
+        let upload_url = self.start_blob_upload(path).await?;
+        self.do_upload_blob(path, &upload_url, blob, digest, size)
+            .await
+    }
+
+    /// Mounts the blob with the `digest` from the repository at `from` (in the same Registry) in
+    /// the repository, without uploading it.
+    ///
+    /// Returns `None` if the blob is mounted. If the Registry declines to mount the blob (eg. it's
+    /// not in `from` or it can't be read from there), an upload session is started instead and
+    /// it's URL is returned (see `do_upload_blob`).
+    pub(super) async fn do_mount_blob(
+        &self,
+        path: &str,
+        digest: &Digest,
+        from: &str,
+    ) -> Result<Option<Uri>, ClientError> {
+        let mount_url = format!(
+            "{}v2/{}/blobs/uploads/?mount={}&from={}",
+            self.repo_url, path, digest, from
+        );
+        log::debug!("Mounting Blob: {}", mount_url);
+
+        // The token should allow pulling from the source repository as well.
+        let scope = format!("{} repository:{}:pull", PUSH_SCOPE, from);
+        let response = self
+            .perform_authorized_request(path, &scope, || {
+                Request::post(&mount_url)
+                    .header(CONTENT_LENGTH, 0)
+                    .body(Body::empty())
+                    .unwrap()
+            })
+            .await?;
+
+        match response.status() {
+            StatusCode::CREATED => {
+                log::debug!("Blob {} Mounted from '{}'!", digest, from);
+                Ok(None)
+            }
+            StatusCode::ACCEPTED => {
+                log::debug!("Mounting Blob {} from '{}' declined.", digest, from);
+                Ok(Some(self.location_url(&response)?))
+            }
+            status => crate::log_err_return!(ClientError, "Error in Mounting Blob: {}", status),
+        }
+    }
+
+    // Starts an upload session for a blob in the repository and returns the URL for the upload.
+    async fn start_blob_upload(&self, path: &str) -> Result<Uri, ClientError> {
+        let uploads_url = format!("{}v2/{}/blobs/uploads/", self.repo_url, path);
+        log::debug!("Starting Blob Upload: {}", uploads_url);
+
+        let response = self
+            .perform_authorized_request(path, PUSH_SCOPE, || {
+                Request::post(&uploads_url)
+                    .header(CONTENT_LENGTH, 0)
+                    .body(Body::empty())
+                    .unwrap()
+            })
+            .await?;
+        if response.status() != StatusCode::ACCEPTED {
+            return crate::log_err_return!(
+                ClientError,
+                "Error in Starting Blob Upload: {}",
+                response.status()
+            );
+        }
+
+        self.location_url(&response)
+    }
+
+    /// Uploads the blob to the upload session at `upload_url` (see `do_mount_blob`) and completes
+    /// the upload.
+    pub(super) async fn do_upload_blob(
+        &self,
+        path: &str,
+        upload_url: &Uri,
+        blob: Box<dyn AsyncRead + Unpin + Send + Sync>,
+        digest: &Digest,
+        size: Option<u64>,
+    ) -> Result<(), ClientError> {
+        // Upload the contents. The request can't be retried as the blob is streamed, but the
+        // token is obtained (if not valid) before sending it.
+        log::trace!("Uploading Blob: {} to {}", digest, upload_url);
+        let headers = self.get_auth_headers(path, PUSH_SCOPE).await?;
+        let mut builder =
+            Request::patch(upload_url.clone()).header(CONTENT_TYPE, "application/octet-stream");
+        if let Some(size) = size {
+            builder = builder.header(CONTENT_LENGTH, size);
+        }
+        let mut request = builder
+            .body(Body::wrap_stream(ReaderStream::new(blob)))
+            .unwrap();
+        request.headers_mut().extend(headers);
+
+        let response = self.send_request(request).await?;
+        if response.status() != StatusCode::ACCEPTED {
+            return crate::log_err_return!(
+                ClientError,
+                "Error in Uploading Blob: {}",
+                response.status()
+            );
+        }
+        let upload_url = self.location_url(&response)?;
+
+        // Complete the upload
+        let separator = if upload_url.query().is_some() {
+            '&'
+        } else {
+            '?'
+        };
+        let complete_url =
+            format!("{}{}digest={}", upload_url, separator, digest).parse::<Uri>()?;
+        log::trace!("Completing Blob Upload: {}", complete_url);
+
+        let response = self
+            .perform_authorized_request(path, PUSH_SCOPE, || {
+                Request::put(complete_url.clone())
+                    .header(CONTENT_LENGTH, 0)
+                    .body(Body::empty())
+                    .unwrap()
+            })
+            .await?;
+        if response.status() != StatusCode::CREATED {
+            return crate::log_err_return!(
+                ClientError,
+                "Error in Completing Blob Upload for Digest {}: {}",
+                digest,
+                response.status()
+            );
+        }
+
+        log::debug!("Blob {} Uploaded Successfully!", digest);
+        Ok(())
+    }
+
+    /// Uploads the manifest to the repository with the given `digest_or_tag`.
+    pub(super) async fn do_put_manifest(
+        &self,
+        path: &str,
+        digest_or_tag: &str,
+        manifest: &ImageManifest,
+    ) -> Result<(), ClientError> {
+        let manifest_url = format!("{}v2/{}/manifests/{}", self.repo_url, path, digest_or_tag);
+        log::debug!("Putting Manifest: {}", manifest_url);
+
+        let response = self
+            .perform_authorized_request(path, PUSH_SCOPE, || {
+                Request::put(&manifest_url)
+                    .header(CONTENT_TYPE, &manifest.mime_type)
+                    .header(CONTENT_LENGTH, manifest.manifest.len())
+                    .body(Body::from(manifest.manifest.clone()))
+                    .unwrap()
+            })
+            .await?;
+        if response.status() != StatusCode::CREATED {
+            return crate::log_err_return!(
+                ClientError,
+                "Error in Putting Manifest: {}",
+                response.status()
+            );
+        }
+
+        log::debug!("Manifest for '{}' Put Successfully!", digest_or_tag);
+        Ok(())
+    }
+
+    #[doc(hidden)]
+    /// Performs API version check against the Docker Registry V2 API.
+    ///
+    /// Once the bearer token is obtained, it is cached at the client (per scope), so that we do
+    /// not have to get one for every API use.
     ///
     /// Note: Only Docker Registry V2 is supported.
     ///
@@ -386,10 +1434,17 @@ impl DockerClient {
         path: &str,
         scope: Option<&str>,
     ) -> Result<(), ClientError> {
+        let scope = if let Some(scope) = scope {
+            scope
+        } else {
+            log::trace!("Empty Scope, defaulting to 'pull'.");
+            "pull"
+        };
+
         log::debug!(
             "Getting Bearer Token for Path: '{}', Scope: '{}'",
             path,
-            scope.unwrap_or("")
+            scope
         );
 
         // If we have already determined, no auth is required, no bearer token is needed to be
@@ -398,8 +1453,8 @@ impl DockerClient {
             return Ok(());
         }
 
-        // We have a valid bearer token - No need to get it again.
-        if self.is_valid_bearer_token() {
+        // We have a valid bearer token (or are using `Basic` auth) - No need to get it again.
+        if *self.basic_auth.read().unwrap() || self.is_valid_bearer_token(path, scope) {
             return Ok(());
         }
 
@@ -413,42 +1468,9 @@ impl DockerClient {
         // Got a 401 - We need to get the bearer token
         if response.status() == StatusCode::UNAUTHORIZED {
             log::trace!("Received 401. Checking For 'WWW-Authenticate' header.");
-            if let Some(www_auth_header) = response.headers().get("WWW-Authenticate") {
-                log::trace!(
-                    "Got WWW-Authenticate Header: {}",
-                    www_auth_header.to_str().unwrap()
-                );
-
-                let scope = if scope.is_none() {
-                    log::trace!("Empty Scope, defaulting to 'pull'.");
-                    "pull"
-                } else {
-                    scope.unwrap()
-                };
-
-                log::trace!("Sending Challenge Response.");
-                let challenge_url = self
-                    .prepare_auth_challenge_url(path, scope, www_auth_header)
-                    .parse::<Uri>()
-                    .unwrap();
-                let auth_response = self.https_client.get(challenge_url).await?;
-                let v = to_bytes(auth_response).await?.to_vec();
-                log::trace!("Auth Response: {}", std::str::from_utf8(&v).unwrap());
-                let bearer_token = serde_json::from_slice::<'_, BearerToken>(&v).unwrap();
-
-                log::trace!(
-                    "Got Bearer Token: Issued At: {}, Expiring in: {}",
-                    bearer_token.issued_at,
-                    bearer_token.expires_in
-                );
-
-                {
-                    let mut bt = self.bearer_token.write().unwrap();
-                    *bt = Some(bearer_token);
-                }
-
-                log::debug!("Bearer Token for Client Saved!");
-                Ok(())
+            if let Some(www_auth_header) = response.headers().get(WWW_AUTHENTICATE) {
+                self.authenticate_with_challenge(path, scope, www_auth_header)
+                    .await
             } else {
                 crate::log_err_return!(
                     ClientError,
@@ -456,35 +1478,226 @@ impl DockerClient {
                     response.status()
                 )
             }
-        } else if response.status().is_success() {
-            // unlikely path
-            log::warn!("No Bearer Token for Client, but Ping response Success!. Bearer Token Not Obtained (and saved)!");
-            return Ok(());
         } else {
+            crate::log_err_return!(ClientError, "Error Getting Token: {}", response.status())
+        }
+    }
+
+    // Answers the auth challenge (`WWW-Authenticate` header) from the Registry.
+    //
+    // For a `Basic` challenge, the credentials are used as they are for all the requests. For a
+    // `Bearer` challenge, a token for the `path` and `scope` is obtained from the realm and saved.
+    async fn authenticate_with_challenge(
+        &self,
+        path: &str,
+        scope: &str,
+        www_auth_header: &HeaderValue,
+    ) -> Result<(), ClientError> {
+        log::trace!(
+            "Got WWW-Authenticate Header: {}",
+            www_auth_header.to_str().unwrap()
+        );
+
+        {
+            let mut auth_required = self.auth_required.write().unwrap();
+            *auth_required = true;
+        }
+
+        if challenge_scheme(www_auth_header).eq_ignore_ascii_case("basic") {
+            log::trace!("Registry requires 'Basic' auth.");
+            if self.credentials.is_none() {
+                return crate::log_err_return!(
+                    ClientError,
+                    "Registry requires 'Basic' auth, but no Credentials found for it."
+                );
+            }
+            let mut basic_auth = self.basic_auth.write().unwrap();
+            *basic_auth = true;
+            return Ok(());
+        }
+
+        let (realm, service) = match challenge_realm_service(www_auth_header) {
+            Some(realm_service) => realm_service,
+            None => {
+                return crate::log_err_return!(
+                    ClientError,
+                    "Invalid 'WWW-Authenticate' Header: {:?}",
+                    www_auth_header
+                )
+            }
+        };
+        let bearer_token = match self
+            .oauth2_token(realm, service, Some(&token_scope_key(path, scope)))
+            .await?
+        {
+            Some(bearer_token) => bearer_token,
+            None => self.realm_token(path, scope, www_auth_header).await?,
+        };
+
+        log::trace!(
+            "Got Bearer Token: Issued At: {}, Expiring in: {}",
+            bearer_token.issued_at,
+            bearer_token.expires_in
+        );
+
+        {
+            let mut bearer_tokens = self.bearer_tokens.write().unwrap();
+            bearer_tokens.insert(token_scope_key(path, scope), bearer_token);
+        }
+
+        log::debug!("Bearer Token for '{}' Saved!", token_scope_key(path, scope));
+        Ok(())
+    }
+
+    // Gets a token from the realm using the OAuth2 `POST` flow, with the refresh token (if any) or
+    // the credentials (`password` grant). A refresh token returned by the realm is saved (and
+    // stored in the auth file, see `persist_refresh_token`).
+    //
+    // Returns `None` if there are no credentials, or the realm does not support the flow (`404` or
+    // `405`), in which case the `GET` flow is to be used. A refresh token rejected by the realm is
+    // discarded and the credentials are used instead.
+    async fn oauth2_token(
+        &self,
+        realm: &str,
+        service: &str,
+        scope: Option<&str>,
+    ) -> Result<Option<BearerToken>, ClientError> {
+        if *self.oauth2_unsupported.read().unwrap() {
+            return Ok(None);
+        }
+
+        let mut refresh_token = self.refresh_token();
+        let response = loop {
+            let mut params = vec![("service", service), ("client_id", OAUTH2_CLIENT_ID)];
+            if let Some(scope) = scope {
+                params.push(("scope", scope));
+            }
+
+            match (&refresh_token, &self.credentials) {
+                (Some(refresh_token), _) => {
+                    log::trace!("Using the Refresh Token for the OAuth2 Token.");
+                    params.push(("grant_type", "refresh_token"));
+                    params.push(("refresh_token", refresh_token));
+                }
+                (None, Some(credentials)) if credentials.has_password() => {
+                    log::trace!("Using Credentials for '{}'.", credentials.username);
+                    params.push(("grant_type", "password"));
+                    params.push(("username", &credentials.username));
+                    params.push(("password", &credentials.password));
+                    // Asks for a refresh token.
+                    params.push(("access_type", "offline"));
+                }
+                _ => return Ok(None),
+            }
+            let body = form_urlencoded(&params);
+
+            log::trace!("Requesting OAuth2 Token from {}", realm);
+            let response = self
+                .send_with_retries(|| {
+                    Request::post(realm)
+                        .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                        .body(Body::from(body.clone()))
+                        .unwrap()
+                })
+                .await?;
+            match response.status() {
+                StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED => {
+                    log::debug!(
+                        "OAuth2 Token Flow not supported by the Realm ({}), Using 'GET'.",
+                        response.status()
+                    );
+                    *self.oauth2_unsupported.write().unwrap() = true;
+                    return Ok(None);
+                }
+                // eg. An expired or a revoked refresh token.
+                StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED if refresh_token.is_some() => {
+                    log::warn!(
+                        "Refresh Token rejected by the Realm ({}), Discarding it.",
+                        response.status()
+                    );
+                    *self.refresh_token.write().unwrap() = None;
+                    refresh_token = None;
+                }
+                status if !status.is_success() => {
+                    return crate::log_err_return!(
+                        ClientError,
+                        "Error Getting OAuth2 Token from the Realm: {}",
+                        status
+                    )
+                }
+                _ => break response,
+            }
+        };
+
+        let bearer_token = BearerToken::from_slice(&to_bytes(response).await?)?;
+        if let Some(new_refresh_token) = &bearer_token.refresh_token {
+            if refresh_token.as_ref() != Some(new_refresh_token) {
+                log::debug!("Got a new Refresh Token from the Realm.");
+                *self.refresh_token.write().unwrap() = Some(new_refresh_token.clone());
+                if self.persist_refresh_token {
+                    if let Err(e) = store_identity_token(&self.registry, new_refresh_token) {
+                        log::warn!("Error '{}' in storing the Refresh Token.", e);
+                    }
+                }
+            }
+        }
+
+        Ok(Some(bearer_token))
+    }
+
+    // Gets a token for the `path` and `scope` from the realm using a `GET` request, with the
+    // credentials (if any) for `Basic` authentication.
+    async fn realm_token(
+        &self,
+        path: &str,
+        scope: &str,
+        www_auth_header: &HeaderValue,
+    ) -> Result<BearerToken, ClientError> {
+        log::trace!("Sending Challenge Response.");
+        let challenge_url = self
+            .prepare_auth_challenge_url(path, scope, www_auth_header)
+            .parse::<Uri>()?;
+        let auth_response = self
+            .send_with_retries(|| {
+                let mut request = Request::get(challenge_url.clone())
+                    .body(Body::empty())
+                    .unwrap();
+                if let Some(credentials) = self.credentials.as_ref().filter(|c| c.has_password()) {
+                    log::trace!("Using Credentials for '{}'.", credentials.username);
+                    request.headers_mut().insert(
+                        AUTHORIZATION,
+                        credentials.basic_auth_header().parse().unwrap(),
+                    );
+                }
+                request
+            })
+            .await?;
+        if !auth_response.status().is_success() {
             return crate::log_err_return!(
                 ClientError,
-                "Error Getting Token: {}",
-                response.status()
+                "Error Getting Token from the Realm: {}",
+                auth_response.status()
             );
         }
+
+        BearerToken::from_slice(&to_bytes(auth_response).await?)
     }
 
     async fn ping_repository(&self) -> Result<Response<Body>, ClientError> {
         let ping_url = format!("{}v2/", self.repo_url).parse::<Uri>().unwrap();
 
         log::trace!("Sending Request to {}", ping_url);
-        Ok(self.https_client.get(ping_url).await?)
+        self.send_with_retries(|| Request::get(ping_url.clone()).body(Body::empty()).unwrap())
+            .await
     }
 
-    fn is_valid_bearer_token(&self) -> bool {
-        self.bearer_token.read().unwrap().is_some()
-            && self
-                .bearer_token
-                .read()
-                .unwrap()
-                .as_ref()
-                .unwrap()
-                .is_still_valid()
+    fn is_valid_bearer_token(&self, path: &str, scope: &str) -> bool {
+        self.bearer_tokens
+            .read()
+            .unwrap()
+            .get(&token_scope_key(path, scope))
+            .map(|bearer_token| bearer_token.is_still_valid())
+            .unwrap_or(false)
     }
 
     #[inline]
@@ -495,45 +1708,78 @@ impl DockerClient {
         auth_header: &HeaderValue,
     ) -> String {
         log::trace!("{:?}", auth_header);
-        let mut realm: Option<&str> = None;
-        let mut service: Option<&str> = None;
-        let header_vals: Vec<&str> = auth_header.to_str().unwrap().split_whitespace().collect();
-        let auth_type = header_vals.first().unwrap();
-        let auth_realm = header_vals.get(1).unwrap();
-        log::trace!("auth_type: {}, auth_realm: {}", auth_type, auth_realm);
-        auth_realm.split(',').for_each(|v| {
-            let toks: Vec<&str> = v.split('=').collect();
-            if let Some(first) = toks.first() {
-                if *first == "realm" {
-                    realm = Some(toks.get(1).unwrap().trim_matches('"'));
-                }
-                if *first == "service" {
-                    service = Some(toks.get(1).unwrap().trim_matches('"'));
-                }
+        let (realm, service) = challenge_realm_service(auth_header).expect("For now!");
+
+        // Each of the scopes (eg. for mounting a blob from another repository) is a separate
+        // parameter.
+        let scope_key = token_scope_key(path, scope);
+        let mut params: Vec<(&str, &str)> = scope_key
+            .split_whitespace()
+            .map(|scope| ("scope", scope))
+            .collect();
+        params.push(("service", service));
+
+        format!("{}?{}", realm, form_urlencoded(&params))
+    }
+}
+
+// Returns the `realm` and `service` parameters of the `WWW-Authenticate` header.
+fn challenge_realm_service(auth_header: &HeaderValue) -> Option<(&str, &str)> {
+    let mut realm: Option<&str> = None;
+    let mut service: Option<&str> = None;
+    let header_vals: Vec<&str> = auth_header.to_str().ok()?.split_whitespace().collect();
+    let auth_type = header_vals.first()?;
+    let auth_realm = header_vals.get(1)?;
+    log::trace!("auth_type: {}, auth_realm: {}", auth_type, auth_realm);
+    auth_realm.split(',').for_each(|v| {
+        let toks: Vec<&str> = v.split('=').collect();
+        if let Some(first) = toks.first() {
+            if *first == "realm" {
+                realm = toks.get(1).map(|t| t.trim_matches('"'));
+            }
+            if *first == "service" {
+                service = toks.get(1).map(|t| t.trim_matches('"'));
             }
-        });
-        if realm.is_none() || service.is_none() {
-            panic!("For now!");
         }
+    });
 
-        format!(
-            "{}?scope=repository:{}:{}&service={}",
-            realm.unwrap(),
-            path,
-            scope,
-            service.unwrap()
-        )
+    Some((realm?, service?))
+}
+
+// Returns the key for the Bearer Token cache (same as the scope requested for the token).
+//
+// The `scope` may be followed by other space separated scopes (eg. `repository:<path>:pull`),
+// that are requested along with the scope for the `path`.
+fn token_scope_key(path: &str, scope: &str) -> String {
+    if path == CATALOG_PATH {
+        format!("registry:catalog:{}", scope)
+    } else {
+        format!("repository:{}:{}", path, scope)
     }
 }
 
+// Returns the auth scheme (eg. `Bearer` or `Basic`) of the `WWW-Authenticate` header.
+fn challenge_scheme(auth_header: &HeaderValue) -> &str {
+    auth_header
+        .to_str()
+        .unwrap_or_default()
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+}
+
 #[derive(Debug, Clone, Deserialize)]
 struct BearerToken {
+    // The OAuth2 token responses have only the `access_token`.
+    #[serde(default = "default_token")]
     token: String,
 
     #[serde(default = "default_token")]
-    #[allow(unused)]
     access_token: String,
 
+    #[serde(default)]
+    refresh_token: Option<String>,
+
     #[serde(default = "issued_now")]
     issued_at: String,
 
@@ -541,10 +1787,26 @@ struct BearerToken {
     expires_in: u16,
 }
 
-fn default_token() -> String {
-    "".to_string()
-}
-
+impl BearerToken {
+    // Parses the token response from the realm.
+    fn from_slice(v: &[u8]) -> Result<Self, ClientError> {
+        log::trace!("Auth Response: {}", String::from_utf8_lossy(v));
+        let mut bearer_token = serde_json::from_slice::<'_, BearerToken>(v)?;
+        if bearer_token.token.is_empty() {
+            bearer_token.token = bearer_token.access_token.clone();
+        }
+        if bearer_token.token.is_empty() {
+            return crate::log_err_return!(ClientError, "No Token in the Auth Response.");
+        }
+
+        Ok(bearer_token)
+    }
+}
+
+fn default_token() -> String {
+    "".to_string()
+}
+
 fn issued_now() -> String {
     Utc::now().to_rfc3339()
 }
@@ -566,16 +1828,193 @@ impl BearerToken {
     }
 }
 
+// Returns the digest from the `Docker-Content-Digest` header, if any.
+fn content_digest(headers: &HeaderMap) -> Option<Digest> {
+    headers
+        .get(DOCKER_CONTENT_DIGEST)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<Digest>().ok())
+}
+
+// Returns the `referrers` of the `artifact_type` (all of them if not specified).
+fn filter_referrers(referrers: Vec<Descriptor>, artifact_type: Option<&str>) -> Vec<Descriptor> {
+    match artifact_type {
+        Some(artifact_type) => referrers
+            .into_iter()
+            .filter(|r| r.artifact_type.as_deref() == Some(artifact_type))
+            .collect(),
+        None => referrers,
+    }
+}
+
+// Encodes the `params` as `application/x-www-form-urlencoded`.
+fn form_urlencoded(params: &[(&str, &str)]) -> String {
+    let encode = |s: &str| {
+        s.bytes()
+            .map(|b| match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'*' => {
+                    (b as char).to_string()
+                }
+                b' ' => "+".to_string(),
+                _ => format!("%{:02X}", b),
+            })
+            .collect::<String>()
+    };
+
+    params
+        .iter()
+        .map(|(name, value)| format!("{}={}", encode(name), encode(value)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+// Adds the `n` and `last` parameters of the paginated lists (tags and catalog) to the `url`.
+fn with_page_params(mut url: String, n: Option<usize>, last: Option<&str>) -> String {
+    let n = n.map(|n| n.to_string());
+
+    let mut params = vec![];
+    if let Some(n) = &n {
+        params.push(("n", n.as_str()));
+    }
+    if let Some(last) = last {
+        params.push(("last", last));
+    }
+
+    if !params.is_empty() {
+        url.push('?');
+        url.push_str(&form_urlencoded(&params));
+    }
+    url
+}
+
+// Returns the URL of the `rel="next"` link in the `Link` header (RFC 5988), if any, for the page
+// at the `current` URL.
+//
+// eg. `Link: </v2/library/fedora/tags/list?n=100&last=34>; rel="next"`. A relative URL is
+// resolved against the `base` URL. The links to other hosts are not followed (the credentials
+// for the Registry would be sent to them), nor is a link to the `current` page.
+fn next_link_url(
+    headers: &HeaderMap,
+    base: &Uri,
+    current: &str,
+) -> Result<Option<String>, ClientError> {
+    let url = headers
+        .get_all(LINK)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .find_map(|link| {
+            let mut parts = link.split(';');
+            let url = parts.next()?.trim().strip_prefix('<')?.strip_suffix('>')?;
+            let is_next = parts.any(|param| {
+                matches!(
+                    param.trim().split_once('='),
+                    Some((name, rel)) if name.trim() == "rel"
+                        && rel.trim().trim_matches('"').split_whitespace().any(|r| r == "next")
+                )
+            });
+            if !is_next {
+                return None;
+            }
+
+            if url.contains("://") {
+                Some(url.to_string())
+            } else {
+                let base = base.to_string();
+                Some(format!(
+                    "{}/{}",
+                    base.trim_end_matches('/'),
+                    url.trim_start_matches('/')
+                ))
+            }
+        });
+
+    let url = match url {
+        Some(url) => url,
+        None => return Ok(None),
+    };
+    let next = match url.parse::<Uri>() {
+        Ok(next) => next,
+        Err(e) => {
+            return crate::log_err_return!(ClientError, "Invalid Link Header '{}': {}", url, e)
+        }
+    };
+
+    if next.scheme() != base.scheme() || next.authority() != base.authority() {
+        return crate::log_err_return!(
+            ClientError,
+            "Not following the Link to '{}', not on the Registry '{}'.",
+            next,
+            base
+        );
+    }
+    if current.parse::<Uri>().ok().as_ref() == Some(&next) {
+        log::warn!("Link to the same page '{}', not following it.", next);
+        return Ok(None);
+    }
+
+    Ok(Some(url))
+}
+
+// Returns whether the `response` is a `416 Range Not Satisfiable` for a request with the `headers`
+// asking for a `Range`. The caller handles it by requesting the whole content.
+fn is_range_not_satisfiable(response: &Response<Body>, headers: Option<&HeaderMap>) -> bool {
+    response.status() == StatusCode::RANGE_NOT_SATISFIABLE
+        && headers.is_some_and(|headers| headers.contains_key(RANGE))
+}
+
+// Takes an exclusive lock on the file at `path` (created if required), waiting for the lock to be
+// released if it is held by another. The lock is released when the returned `File` is dropped.
+async fn lock_file(path: &std::path::Path) -> Result<std::fs::File, ClientError> {
+    use std::os::unix::io::AsRawFd;
+
+    let f = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)?;
+    loop {
+        // Safety: `f` is an open file, that is not closed while the call is made.
+        if unsafe { libc::flock(f.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+            return Ok(f);
+        }
+
+        let e = std::io::Error::last_os_error();
+        if e.raw_os_error() != Some(libc::EWOULDBLOCK) {
+            return Err(e.into());
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+}
+
+// Returns whether the `response` is the `206 Partial Content` starting at `offset`.
+fn is_partial_content_from(response: &Response<Body>, offset: u64) -> bool {
+    if response.status() != StatusCode::PARTIAL_CONTENT {
+        return false;
+    }
+
+    // eg. `Content-Range: bytes 1024-4095/4096`
+    response
+        .headers()
+        .get(CONTENT_RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().strip_prefix("bytes "))
+        .and_then(|v| v.split_once('-'))
+        .and_then(|(start, _)| start.trim().parse::<u64>().ok())
+        == Some(offset)
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use crate::image::docker::retry::registry_rate_limit;
     use chrono::Utc;
 
     #[test]
     fn test_new_client_success() {
         let repo_url = "https://registry-1.docker.io/";
-        let client = DockerClient::new(repo_url);
+        let client = DockerClient::new(repo_url, None).unwrap();
 
         assert_eq!(client.repo_url, repo_url);
     }
@@ -584,6 +2023,7 @@ mod tests {
     fn test_bearer_token_valid() {
         let b = BearerToken {
             token: "some random token".to_string(),
+            refresh_token: None,
             access_token: "some random access token".to_string(),
             issued_at: Utc::now().to_rfc3339(),
             expires_in: 2,
@@ -597,7 +2037,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_api_version_check() {
-        let client = DockerClient::new(DOCKER_REGISTRY_V2_HTTPS_URL);
+        let client = DockerClient::new(DOCKER_REGISTRY_V2_HTTPS_URL, None).unwrap();
 
         let result = client
             .get_bearer_token_for_path_scope("library/fedora", Some("pull"))
@@ -605,4 +2045,1168 @@ mod tests {
 
         assert!(result.is_ok());
     }
+
+    fn test_credentials() -> Credentials {
+        Credentials {
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            identity_token: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bearer_token_with_credentials() {
+        use wiremock::{
+            matchers::{header, method, path, query_param},
+            Mock, MockServer, ResponseTemplate,
+        };
+
+        let mock_server = MockServer::start().await;
+        let www_auth = format!(
+            r#"Bearer realm="{}/token",service="test-registry""#,
+            mock_server.uri()
+        );
+
+        Mock::given(method("GET"))
+            .and(path("/v2/"))
+            .respond_with(
+                ResponseTemplate::new(401).insert_header("WWW-Authenticate", www_auth.as_str()),
+            )
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .and(query_param("scope", "repository:private/image:pull"))
+            .and(header("Authorization", "Basic dXNlcjpwYXNz"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"token": "secret"}"#))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mut client = DockerClient::new(&mock_server.address().to_string(), None).unwrap();
+        client.credentials = Some(test_credentials());
+
+        let headers = client.get_auth_headers("private/image", "pull").await;
+        assert!(headers.is_ok(), "{:?}", headers.err());
+        assert_eq!(
+            headers.unwrap().get(AUTHORIZATION).unwrap(),
+            "Bearer secret"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_oauth2_token_flow() {
+        use wiremock::{
+            matchers::{body_string_contains, header, method, path},
+            Mock, ResponseTemplate,
+        };
+
+        let mock_server = setup_mock_token_server().await;
+
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(header("Content-Type", "application/x-www-form-urlencoded"))
+            .and(body_string_contains("grant_type=password"))
+            .and(body_string_contains("username=user&password=pass"))
+            .and(body_string_contains(
+                "scope=repository%3Aprivate%2Fimage%3Apull&",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"access_token": "secret", "refresh_token": "refresh-1", "expires_in": 300}"#,
+            ))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // The refresh token is used afterwards.
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(body_string_contains(
+                "grant_type=refresh_token&refresh_token=refresh-1",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"access_token": "push-secret", "refresh_token": "refresh-2"}"#,
+            ))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mut client = DockerClient::new(&mock_server.address().to_string(), None).unwrap();
+        client.set_credentials(Some(test_credentials()));
+
+        let headers = client.get_auth_headers("private/image", "pull").await;
+        assert!(headers.is_ok(), "{:?}", headers.err());
+        assert_eq!(
+            headers.unwrap().get(AUTHORIZATION).unwrap(),
+            "Bearer secret"
+        );
+        assert_eq!(client.refresh_token().as_deref(), Some("refresh-1"));
+
+        let headers = client.get_auth_headers("private/image", PUSH_SCOPE).await;
+        assert_eq!(
+            headers.unwrap().get(AUTHORIZATION).unwrap(),
+            "Bearer push-secret"
+        );
+        assert_eq!(client.refresh_token().as_deref(), Some("refresh-2"));
+    }
+
+    #[tokio::test]
+    async fn test_oauth2_fallback_to_get() {
+        use wiremock::{
+            matchers::{header, method, path},
+            Mock, ResponseTemplate,
+        };
+
+        let mock_server = setup_mock_token_server().await;
+
+        // Not tried again for the other scopes.
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(405))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .and(header("Authorization", "Basic dXNlcjpwYXNz"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"token": "secret"}"#))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        let mut client = DockerClient::new(&mock_server.address().to_string(), None).unwrap();
+        client.set_credentials(Some(test_credentials()));
+
+        let headers = client.get_auth_headers("private/image", "pull").await;
+        assert!(headers.is_ok(), "{:?}", headers.err());
+        assert_eq!(
+            headers.unwrap().get(AUTHORIZATION).unwrap(),
+            "Bearer secret"
+        );
+        assert!(client.refresh_token().is_none());
+
+        let headers = client.get_auth_headers("private/image", PUSH_SCOPE).await;
+        assert!(headers.is_ok(), "{:?}", headers.err());
+    }
+
+    #[tokio::test]
+    async fn test_oauth2_refresh_token_rejected() {
+        use wiremock::{
+            matchers::{body_string_contains, method, path},
+            Mock, ResponseTemplate,
+        };
+
+        let mock_server = setup_mock_token_server().await;
+
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(body_string_contains("grant_type=refresh_token"))
+            .respond_with(ResponseTemplate::new(400))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(body_string_contains("grant_type=password"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"access_token": "secret", "refresh_token": "refresh-1"}"#),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mut client = DockerClient::new(&mock_server.address().to_string(), None).unwrap();
+        client.set_credentials(Some(test_credentials()));
+        client.persist_refresh_token = false;
+        *client.refresh_token.write().unwrap() = Some("expired".to_string());
+
+        // The credentials are used instead.
+        let headers = client.get_auth_headers("private/image", "pull").await;
+        assert!(headers.is_ok(), "{:?}", headers.err());
+        assert_eq!(
+            headers.unwrap().get(AUTHORIZATION).unwrap(),
+            "Bearer secret"
+        );
+        assert_eq!(client.refresh_token().as_deref(), Some("refresh-1"));
+    }
+
+    #[tokio::test]
+    async fn test_oauth2_refresh_token_rejected_without_password() {
+        use wiremock::{
+            matchers::{method, path},
+            Mock, ResponseTemplate,
+        };
+
+        let mock_server = setup_mock_token_server().await;
+
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(401))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"token": "secret"}"#))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mut client = DockerClient::new(&mock_server.address().to_string(), None).unwrap();
+        client.set_credentials(None);
+        client.persist_refresh_token = false;
+        *client.refresh_token.write().unwrap() = Some("revoked".to_string());
+
+        // Falls back to the `GET` flow.
+        let headers = client.get_auth_headers("library/fedora", "pull").await;
+        assert!(headers.is_ok(), "{:?}", headers.err());
+        assert_eq!(
+            headers.unwrap().get(AUTHORIZATION).unwrap(),
+            "Bearer secret"
+        );
+        assert!(client.refresh_token().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_basic_auth_challenge() {
+        use wiremock::{
+            matchers::{method, path},
+            Mock, MockServer, ResponseTemplate,
+        };
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v2/"))
+            .respond_with(
+                ResponseTemplate::new(401)
+                    .insert_header("WWW-Authenticate", r#"Basic realm="Registry""#),
+            )
+            .mount(&mock_server)
+            .await;
+
+        // Without credentials, we should fail.
+        let mut client = DockerClient::new(&mock_server.address().to_string(), None).unwrap();
+        client.set_credentials(None);
+        let headers = client.get_auth_headers("private/image", "pull").await;
+        assert!(headers.is_err());
+
+        let mut client = DockerClient::new(&mock_server.address().to_string(), None).unwrap();
+        client.set_credentials(Some(test_credentials()));
+
+        let headers = client.get_auth_headers("private/image", "pull").await;
+        assert!(headers.is_ok(), "{:?}", headers.err());
+        assert_eq!(
+            headers.unwrap().get(AUTHORIZATION).unwrap(),
+            "Basic dXNlcjpwYXNz"
+        );
+    }
+
+    async fn setup_mock_token_server() -> wiremock::MockServer {
+        use wiremock::{
+            matchers::{method, path},
+            Mock, MockServer, ResponseTemplate,
+        };
+
+        let mock_server = MockServer::start().await;
+        let www_auth = format!(
+            r#"Bearer realm="{}/token",service="test-registry""#,
+            mock_server.uri()
+        );
+
+        Mock::given(method("GET"))
+            .and(path("/v2/"))
+            .respond_with(
+                ResponseTemplate::new(401).insert_header("WWW-Authenticate", www_auth.as_str()),
+            )
+            .mount(&mock_server)
+            .await;
+
+        mock_server
+    }
+
+    #[tokio::test]
+    async fn test_bearer_tokens_per_scope() {
+        use wiremock::{
+            matchers::{method, path, query_param},
+            Mock, ResponseTemplate,
+        };
+
+        let mock_server = setup_mock_token_server().await;
+        for (scope, token) in [
+            ("repository:library/fedora:pull", "fedora-pull"),
+            ("repository:library/ubuntu:pull", "ubuntu-pull"),
+            ("repository:library/fedora:pull,push", "fedora-push"),
+        ] {
+            Mock::given(method("GET"))
+                .and(path("/token"))
+                .and(query_param("scope", scope))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_string(format!(r#"{{"token": "{}"}}"#, token)),
+                )
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+        }
+
+        let client = DockerClient::new(&mock_server.address().to_string(), None).unwrap();
+        for (path, scope, token) in [
+            ("library/fedora", "pull", "fedora-pull"),
+            ("library/ubuntu", "pull", "ubuntu-pull"),
+            ("library/fedora", PUSH_SCOPE, "fedora-push"),
+            // Cached tokens should be used.
+            ("library/fedora", "pull", "fedora-pull"),
+            ("library/ubuntu", "pull", "ubuntu-pull"),
+        ] {
+            let headers = client.get_auth_headers(path, scope).await;
+            assert!(headers.is_ok(), "{:?}", headers.err());
+            assert_eq!(
+                headers.unwrap().get(AUTHORIZATION).unwrap(),
+                &format!("Bearer {}", token)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bearer_token_for_mount_scope() {
+        use wiremock::{
+            matchers::{method, path, query_param},
+            Mock, ResponseTemplate,
+        };
+
+        // Both the scopes are requested for mounting a blob from another repository.
+        let mock_server = setup_mock_token_server().await;
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .and(query_param("scope", "repository:library/fedora:pull,push"))
+            .and(query_param("scope", "repository:library/base:pull"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"token": "mount"}"#))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = DockerClient::new(&mock_server.address().to_string(), None).unwrap();
+        let scope = format!("{} repository:library/base:pull", PUSH_SCOPE);
+        for _ in 0..2 {
+            let headers = client.get_auth_headers("library/fedora", &scope).await;
+            assert!(headers.is_ok(), "{:?}", headers.err());
+            assert_eq!(headers.unwrap().get(AUTHORIZATION).unwrap(), "Bearer mount");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh_token_on_unauthorized() {
+        use wiremock::{
+            matchers::{header, method, path},
+            Mock, ResponseTemplate,
+        };
+
+        let mock_server = setup_mock_token_server().await;
+
+        // First token is 'revoked' by the registry, second one is accepted.
+        for token in ["revoked", "fresh"] {
+            Mock::given(method("GET"))
+                .and(path("/token"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_string(format!(r#"{{"token": "{}"}}"#, token)),
+                )
+                .up_to_n_times(1)
+                .mount(&mock_server)
+                .await;
+        }
+
+        let www_auth = format!(
+            r#"Bearer realm="{}/token",service="test-registry",scope="repository:library/fedora:pull""#,
+            mock_server.uri()
+        );
+        Mock::given(method("GET"))
+            .and(path("/v2/library/fedora/tags/list"))
+            .and(header("Authorization", "Bearer revoked"))
+            .respond_with(
+                ResponseTemplate::new(401).insert_header("WWW-Authenticate", www_auth.as_str()),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/v2/library/fedora/tags/list"))
+            .and(header("Authorization", "Bearer fresh"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"name": "library/fedora", "tags": ["33", "34"]}"#),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = DockerClient::new(&mock_server.address().to_string(), None).unwrap();
+        let tags = client.do_get_repo_tags("library/fedora").await;
+        assert!(tags.is_ok(), "{:?}", tags.err());
+        assert_eq!(tags.unwrap(), vec!["33", "34"]);
+    }
+
+    #[test]
+    fn test_tags_list_url() {
+        let client = DockerClient::new("registry.example.com", None).unwrap();
+        assert_eq!(
+            client.tags_list_url("library/fedora", None, None),
+            "https://registry.example.com/v2/library/fedora/tags/list"
+        );
+        assert_eq!(
+            client.tags_list_url("library/fedora", Some(50), Some("34")),
+            "https://registry.example.com/v2/library/fedora/tags/list?n=50&last=34"
+        );
+
+        // The `last` is encoded, a repository name has a '/'.
+        assert_eq!(
+            client.catalog_url(Some(10), Some("library/fedora&n=1")),
+            "https://registry.example.com/v2/_catalog?n=10&last=library%2Ffedora%26n%3D1"
+        );
+    }
+
+    #[test]
+    fn test_next_link_url() {
+        let base: Uri = "https://registry.example.com/".parse().unwrap();
+        let current = "https://registry.example.com/v2/_catalog";
+        let mut headers = HeaderMap::new();
+        assert_eq!(next_link_url(&headers, &base, current).unwrap(), None);
+
+        headers.insert(
+            LINK,
+            r#"</v2/library/fedora/tags/list?n=2&last=34>; rel="next""#
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(
+            next_link_url(&headers, &base, current).unwrap().unwrap(),
+            "https://registry.example.com/v2/library/fedora/tags/list?n=2&last=34"
+        );
+
+        headers.insert(
+            LINK,
+            r#"<https://other.example.com/v2/_catalog>; rel="prev", <https://registry.example.com/v2/_catalog?last=b>; rel=next"#
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(
+            next_link_url(&headers, &base, current).unwrap().unwrap(),
+            "https://registry.example.com/v2/_catalog?last=b"
+        );
+
+        headers.insert(LINK, r#"</v2/_catalog>; rel="prev""#.parse().unwrap());
+        assert_eq!(next_link_url(&headers, &base, current).unwrap(), None);
+
+        // Not followed to the other hosts (or schemes).
+        headers.insert(
+            LINK,
+            r#"<https://other.example.com/v2/_catalog?last=b>; rel="next""#
+                .parse()
+                .unwrap(),
+        );
+        assert!(next_link_url(&headers, &base, current).is_err());
+        headers.insert(
+            LINK,
+            r#"<http://registry.example.com/v2/_catalog?last=b>; rel="next""#
+                .parse()
+                .unwrap(),
+        );
+        assert!(next_link_url(&headers, &base, current).is_err());
+
+        // Nor to the same page again.
+        headers.insert(LINK, r#"</v2/_catalog>; rel="next""#.parse().unwrap());
+        assert_eq!(next_link_url(&headers, &base, current).unwrap(), None);
+
+        headers.insert(
+            LINK,
+            r#"</v2/_catalog?last=a b>; rel="next""#.parse().unwrap(),
+        );
+        assert!(next_link_url(&headers, &base, current).is_err());
+    }
+
+    fn test_retry_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff: std::time::Duration::from_millis(10),
+            max_backoff: std::time::Duration::from_millis(50),
+            timeout: Some(std::time::Duration::from_secs(5)),
+            rate_limit_budget: std::time::Duration::from_secs(1),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_on_transient_failures() {
+        use wiremock::{
+            matchers::{method, path},
+            Mock, ResponseTemplate,
+        };
+
+        let mock_server = setup_mock_token_server().await;
+
+        // Token server is unavailable once, Registry twice (once with a 'Retry-After').
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"token": "secret"}"#))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/v2/library/fedora/tags/list"))
+            .respond_with(ResponseTemplate::new(503).insert_header("Retry-After", "0"))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/v2/library/fedora/tags/list"))
+            .respond_with(ResponseTemplate::new(502))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/v2/library/fedora/tags/list"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"name": "library/fedora", "tags": ["33", "34"]}"#),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mut client = DockerClient::new(&mock_server.address().to_string(), None).unwrap();
+        client.retry_policy = test_retry_policy(3);
+
+        let tags = client.do_get_repo_tags("library/fedora").await;
+        assert!(tags.is_ok(), "{:?}", tags.err());
+        assert_eq!(tags.unwrap(), vec!["33", "34"]);
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_bounded() {
+        use wiremock::{
+            matchers::{method, path},
+            Mock, MockServer, ResponseTemplate,
+        };
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v2/"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        // The Registry asks to retry after a day.
+        Mock::given(method("GET"))
+            .and(path("/v2/library/fedora/tags/list"))
+            .respond_with(ResponseTemplate::new(503).insert_header("Retry-After", "86400"))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/v2/library/fedora/tags/list"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"name": "library/fedora", "tags": ["33"]}"#),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mut client = DockerClient::new(&mock_server.address().to_string(), None).unwrap();
+        client.retry_policy = test_retry_policy(3);
+
+        let tags = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            client.do_get_repo_tags("library/fedora"),
+        )
+        .await;
+        assert!(tags.is_ok(), "Retry-After not bounded by the max backoff.");
+        assert_eq!(tags.unwrap().unwrap(), vec!["33"]);
+    }
+
+    #[tokio::test]
+    async fn test_retries_exhausted() {
+        use wiremock::{
+            matchers::{method, path},
+            Mock, ResponseTemplate,
+        };
+
+        let mock_server = setup_mock_token_server().await;
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"token": "secret"}"#))
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/v2/library/fedora/tags/list"))
+            .respond_with(ResponseTemplate::new(502))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        // Not Found is not retried.
+        Mock::given(method("GET"))
+            .and(path("/v2/library/ubuntu/tags/list"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mut client = DockerClient::new(&mock_server.address().to_string(), None).unwrap();
+        client.retry_policy = test_retry_policy(2);
+
+        let tags = client.do_get_repo_tags("library/fedora").await;
+        assert!(tags.is_err());
+        let error = tags.err().unwrap().to_string();
+        assert!(
+            error.contains("/v2/library/fedora/tags/list failed after 2 attempt(s): 502"),
+            "{}",
+            error
+        );
+
+        assert!(client.do_get_repo_tags("library/ubuntu").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_retry() {
+        use wiremock::{
+            matchers::{method, path},
+            Mock, ResponseTemplate,
+        };
+
+        let mock_server = setup_mock_token_server().await;
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"token": "secret"}"#))
+            .mount(&mock_server)
+            .await;
+
+        // Rate limited more times than the `max_attempts`, without a `Retry-After` once.
+        Mock::given(method("GET"))
+            .and(path("/v2/library/fedora/tags/list"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", "0")
+                    .insert_header("RateLimit-Limit", "100;w=21600")
+                    .insert_header("RateLimit-Remaining", "0;w=21600"),
+            )
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/v2/library/fedora/tags/list"))
+            .respond_with(ResponseTemplate::new(429))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/v2/library/fedora/tags/list"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("RateLimit-Limit", "100;w=21600")
+                    .insert_header("RateLimit-Remaining", "99;w=21600")
+                    .set_body_string(r#"{"name": "library/fedora", "tags": ["33", "34"]}"#),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let registry = mock_server.address().to_string();
+        let mut client = DockerClient::new(&registry, None).unwrap();
+        client.retry_policy = test_retry_policy(2);
+
+        let tags = client.do_get_repo_tags("library/fedora").await;
+        assert!(tags.is_ok(), "{:?}", tags.err());
+
+        let rate_limit = registry_rate_limit(&registry).unwrap();
+        assert_eq!(rate_limit.limit, 100);
+        assert_eq!(rate_limit.remaining, 99);
+        assert_eq!(
+            rate_limit.window,
+            Some(std::time::Duration::from_secs(21600))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_budget_exceeded() {
+        use wiremock::{
+            matchers::{method, path},
+            Mock, ResponseTemplate,
+        };
+
+        let mock_server = setup_mock_token_server().await;
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"token": "secret"}"#))
+            .mount(&mock_server)
+            .await;
+
+        // The delay asked for is longer than the budget, the request is not retried.
+        Mock::given(method("GET"))
+            .and(path("/v2/library/fedora/tags/list"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", "3600")
+                    .insert_header("RateLimit-Limit", "100")
+                    .insert_header("RateLimit-Remaining", "0"),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let registry = mock_server.address().to_string();
+        let mut client = DockerClient::new(&registry, None).unwrap();
+        client.retry_policy = test_retry_policy(3);
+
+        let tags = client.do_get_repo_tags("library/fedora").await;
+        assert!(tags.is_err());
+        let error = tags.err().unwrap().to_string();
+        assert!(
+            error.contains("Rate Limited by the Registry (Remaining: 0 of 100)"),
+            "{}",
+            error
+        );
+        assert_eq!(registry_rate_limit(&registry).unwrap().remaining, 0);
+    }
+
+    // Returns a (unique) test Blob and the path of it's partial download in the cache.
+    fn test_partial_blob() -> (Vec<u8>, Digest, std::path::PathBuf) {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let blob = format!("test blob {}", nanos).repeat(100).into_bytes();
+        let digest = Digest::from_bytes(&blob);
+
+        let mut partial_path = image_blobs_cache_root().unwrap();
+        partial_path.push(digest.algorithm());
+        std::fs::create_dir_all(&partial_path).unwrap();
+        partial_path.push(format!("{}.partial", digest.hex_digest()));
+
+        (blob, digest, partial_path)
+    }
+
+    async fn read_blob(client: &DockerClient, digest: &Digest) -> Vec<u8> {
+        use tokio::io::AsyncReadExt;
+
+        let reader = client.do_get_blob("library/fedora", digest).await;
+        assert!(reader.is_ok(), "{:?}", reader.err());
+
+        let mut data = vec![];
+        reader.unwrap().read_to_end(&mut data).await.unwrap();
+        data
+    }
+
+    #[tokio::test]
+    async fn test_resume_blob_download() {
+        use wiremock::{
+            matchers::{header, method, path},
+            Mock, ResponseTemplate,
+        };
+
+        let (blob, digest, partial_path) = test_partial_blob();
+        let offset = blob.len() / 3;
+        std::fs::write(&partial_path, &blob[..offset]).unwrap();
+
+        let mock_server = setup_mock_token_server().await;
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"token": "secret"}"#))
+            .mount(&mock_server)
+            .await;
+
+        let content_range = format!("bytes {}-{}/{}", offset, blob.len() - 1, blob.len());
+        Mock::given(method("GET"))
+            .and(path(format!("/v2/library/fedora/blobs/{}", digest)))
+            .and(header("Range", format!("bytes={}-", offset).as_str()))
+            .respond_with(
+                ResponseTemplate::new(206)
+                    .insert_header("Content-Range", content_range.as_str())
+                    .set_body_bytes(&blob[offset..]),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = DockerClient::new(&mock_server.address().to_string(), None).unwrap();
+        assert_eq!(read_blob(&client, &digest).await, blob);
+        assert!(!partial_path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_resume_blob_download_range_not_supported() {
+        use wiremock::{
+            matchers::{method, path},
+            Mock, ResponseTemplate,
+        };
+
+        let (blob, digest, partial_path) = test_partial_blob();
+        std::fs::write(&partial_path, &blob[..blob.len() / 2]).unwrap();
+
+        let mock_server = setup_mock_token_server().await;
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"token": "secret"}"#))
+            .mount(&mock_server)
+            .await;
+
+        // The `Range` is ignored and the whole Blob is sent.
+        Mock::given(method("GET"))
+            .and(path(format!("/v2/library/fedora/blobs/{}", digest)))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(blob.clone()))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = DockerClient::new(&mock_server.address().to_string(), None).unwrap();
+        assert_eq!(read_blob(&client, &digest).await, blob);
+        assert!(!partial_path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_resume_blob_download_range_not_satisfiable() {
+        use wiremock::{
+            matchers::{header, method, path},
+            Mock, ResponseTemplate,
+        };
+
+        let (blob, digest, partial_path) = test_partial_blob();
+        std::fs::write(&partial_path, [&blob[..], b"extra"].concat()).unwrap();
+
+        let mock_server = setup_mock_token_server().await;
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"token": "secret"}"#))
+            .mount(&mock_server)
+            .await;
+
+        let range = format!("bytes={}-", blob.len() + 5);
+        Mock::given(method("GET"))
+            .and(path(format!("/v2/library/fedora/blobs/{}", digest)))
+            .and(header("Range", range.as_str()))
+            .respond_with(ResponseTemplate::new(416))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path(format!("/v2/library/fedora/blobs/{}", digest)))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(blob.clone()))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = DockerClient::new(&mock_server.address().to_string(), None).unwrap();
+        assert_eq!(read_blob(&client, &digest).await, blob);
+        assert!(!partial_path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_resume_blob_download_error_keeps_partial() {
+        use wiremock::{
+            matchers::{method, path},
+            Mock, ResponseTemplate,
+        };
+
+        let (blob, digest, partial_path) = test_partial_blob();
+        std::fs::write(&partial_path, &blob[..blob.len() / 2]).unwrap();
+
+        let mock_server = setup_mock_token_server().await;
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"token": "secret"}"#))
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path(format!("/v2/library/fedora/blobs/{}", digest)))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = DockerClient::new(&mock_server.address().to_string(), None).unwrap();
+        assert!(client.do_get_blob("library/fedora", &digest).await.is_err());
+
+        // Can be resumed later.
+        assert_eq!(
+            std::fs::read(&partial_path).unwrap(),
+            &blob[..blob.len() / 2]
+        );
+        std::fs::remove_file(&partial_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_blob_downloads() {
+        use wiremock::{
+            matchers::{method, path},
+            Mock, ResponseTemplate,
+        };
+
+        let (blob, digest, partial_path) = test_partial_blob();
+
+        let mock_server = setup_mock_token_server().await;
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"token": "secret"}"#))
+            .mount(&mock_server)
+            .await;
+
+        // The other download waits for this one and gets the cached Blob.
+        Mock::given(method("GET"))
+            .and(path(format!("/v2/library/fedora/blobs/{}", digest)))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_bytes(blob.clone())
+                    .set_delay(std::time::Duration::from_millis(300)),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = DockerClient::new(&mock_server.address().to_string(), None).unwrap();
+        let (first, second) =
+            tokio::join!(read_blob(&client, &digest), read_blob(&client, &digest));
+        assert_eq!(first, blob);
+        assert_eq!(second, blob);
+        assert!(!partial_path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_corrupted_cached_blob() {
+        use tokio::io::AsyncReadExt;
+
+        let (_, digest, partial_path) = test_partial_blob();
+        let cache_path = partial_path.with_extension("");
+        std::fs::write(&cache_path, b"corrupted").unwrap();
+
+        // The cached Blob is verified as it is read.
+        let mock_server = setup_mock_token_server().await;
+        let client = DockerClient::new(&mock_server.address().to_string(), None).unwrap();
+        let mut reader = client.do_get_blob("library/fedora", &digest).await.unwrap();
+        let mut data = vec![];
+        assert!(reader.read_to_end(&mut data).await.is_err());
+
+        std::fs::remove_file(&cache_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_blob_location_recorded_on_download() {
+        use crate::image::docker::blob_locations::blob_locations;
+        use wiremock::{
+            matchers::{method, path},
+            Mock, ResponseTemplate,
+        };
+
+        let (blob, digest, _) = test_partial_blob();
+
+        let mock_server = setup_mock_token_server().await;
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"token": "secret"}"#))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("/v2/library/fedora/blobs/{}", digest)))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(blob.clone()))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let registry = mock_server.address().to_string();
+        let client = DockerClient::new(&registry, None).unwrap();
+        assert_eq!(read_blob(&client, &digest).await, blob);
+        assert_eq!(blob_locations(&digest, &registry), vec!["library/fedora"]);
+
+        // Not recorded for the Blob in the cache, the Registry may not have it.
+        let other_server = setup_mock_token_server().await;
+        let other_registry = other_server.address().to_string();
+        let other_client = DockerClient::new(&other_registry, None).unwrap();
+        assert_eq!(read_blob(&other_client, &digest).await, blob);
+        assert!(blob_locations(&digest, &other_registry).is_empty());
+    }
+
+    fn proxy_config(var: &'static str, proxy_url: String) -> ProxyConfig {
+        ProxyConfig::from_vars(|name| Some(proxy_url.clone()).filter(|_| name == var))
+    }
+
+    #[tokio::test]
+    async fn test_requests_through_http_proxy() {
+        use wiremock::{
+            matchers::{header, method, path},
+            Mock, MockServer, ResponseTemplate,
+        };
+
+        // The proxy stand-in answers for the registry and it's auth realm (on another host).
+        let proxy_server = MockServer::start().await;
+        let proxy_auth = "Basic dXNlcjpzZWNyZXQ=";
+
+        Mock::given(method("GET"))
+            .and(path("/v2/"))
+            .and(header("host", "registry.invalid:5000"))
+            .and(header("proxy-authorization", proxy_auth))
+            .respond_with(ResponseTemplate::new(401).insert_header(
+                "WWW-Authenticate",
+                r#"Bearer realm="http://auth.invalid/token",service="test-registry""#,
+            ))
+            .mount(&proxy_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .and(header("host", "auth.invalid"))
+            .and(header("proxy-authorization", proxy_auth))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"token": "abcd"}"#))
+            .expect(1)
+            .mount(&proxy_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/v2/library/fedora/tags/list"))
+            .and(header("authorization", "Bearer abcd"))
+            .and(header("proxy-authorization", proxy_auth))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"name": "library/fedora", "tags": ["latest"]}"#),
+            )
+            .mount(&proxy_server)
+            .await;
+
+        let config = proxy_config(
+            "HTTP_PROXY",
+            format!("http://user:secret@{}", proxy_server.address()),
+        );
+        let client =
+            DockerClient::new_with_proxy("registry.invalid:5000", Some(true), &config).unwrap();
+
+        let tags = client.do_get_repo_tags("library/fedora").await;
+        assert!(tags.is_ok(), "{:?}", tags.err());
+        assert_eq!(tags.unwrap(), vec!["latest"]);
+    }
+
+    #[test]
+    fn test_invalid_registry_certificates() {
+        use crate::image::docker::certs::{set_registry_certificates, RegistryCertificates};
+
+        let host = "invalid-certs.example.com:5000";
+        set_registry_certificates(
+            host,
+            RegistryCertificates {
+                ca_certs: vec![b"Not a certificate.".to_vec()],
+                client_identity: None,
+            },
+        );
+
+        assert!(DockerClient::new(host, Some(false)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_https_through_connect_proxy() {
+        use std::sync::{Arc, Mutex};
+
+        use tokio::io::{AsyncRead, AsyncReadExt};
+        use tokio::net::{TcpListener, TcpStream};
+
+        use crate::image::docker::{
+            certs::{set_registry_certificates, RegistryCertificates},
+            testdata::{TEST_CA_CERT, TEST_SERVER_CERT, TEST_SERVER_KEY},
+        };
+
+        async fn read_head<R: AsyncRead + Unpin>(stream: &mut R) -> String {
+            let mut head = vec![];
+            let mut buf = [0u8; 1024];
+            while !head.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                assert!(n > 0);
+                head.extend_from_slice(&buf[..n]);
+            }
+            String::from_utf8(head).unwrap()
+        }
+
+        // A registry serving `https` for 'localhost'.
+        let registry = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let registry_port = registry.local_addr().unwrap().port();
+        let identity = native_tls::Identity::from_pkcs8(
+            TEST_SERVER_CERT.as_bytes(),
+            TEST_SERVER_KEY.as_bytes(),
+        )
+        .unwrap();
+        let acceptor: tokio_native_tls::TlsAcceptor =
+            native_tls::TlsAcceptor::new(identity).unwrap().into();
+        tokio::spawn(async move {
+            let (stream, _) = registry.accept().await.unwrap();
+            let mut stream = acceptor.accept(stream).await.unwrap();
+            read_head(&mut stream).await;
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .await
+                .unwrap();
+            stream.flush().await.unwrap();
+        });
+
+        // The proxy stand-in tunnels the connection to the registry.
+        let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_address = proxy.local_addr().unwrap();
+        let connect_line = Arc::new(Mutex::new(String::new()));
+        let proxy_connect_line = connect_line.clone();
+        tokio::spawn(async move {
+            let (mut stream, _) = proxy.accept().await.unwrap();
+            let head = read_head(&mut stream).await;
+            *proxy_connect_line.lock().unwrap() = head.lines().next().unwrap().to_string();
+
+            let mut upstream = TcpStream::connect(("127.0.0.1", registry_port))
+                .await
+                .unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+                .await
+                .unwrap();
+            let _ = tokio::io::copy_bidirectional(&mut stream, &mut upstream).await;
+        });
+
+        let registry_host = format!("localhost:{}", registry_port);
+        set_registry_certificates(
+            &registry_host,
+            RegistryCertificates {
+                ca_certs: vec![TEST_CA_CERT.as_bytes().to_vec()],
+                client_identity: None,
+            },
+        );
+
+        let config = proxy_config("HTTPS_PROXY", format!("http://{}", proxy_address));
+        let client = DockerClient::new_with_proxy(&registry_host, Some(false), &config).unwrap();
+
+        let response = client.ping_repository().await;
+        assert!(response.is_ok(), "{:?}", response.err());
+        assert_eq!(response.unwrap().status(), StatusCode::OK);
+        assert_eq!(
+            *connect_line.lock().unwrap(),
+            format!("CONNECT {} HTTP/1.1", registry_host)
+        );
+    }
 }
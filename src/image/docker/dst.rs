@@ -1 +1,138 @@
 //! Stuff related to handling Docker Registry Image destinations.
+//!
+//! Implementation of Docker specific ImageDestination, that pushes the images to a Docker
+//! Registry (V2).
+//!
+//! Blobs that are known to be in other repositories in the Registry (see `blob_locations`) are
+//! mounted from there, instead of being uploaded again.
+use async_trait::async_trait;
+use tokio::io::AsyncRead;
+
+use crate::image::{
+    oci::digest::Digest,
+    types::{errors::ImageResult, ImageDestination, ImageManifest, ImageReference},
+};
+
+use super::blob_locations::{blob_locations, record_blob_location};
+use super::client::DockerEndpoint;
+use super::reference::types::DockerReference;
+
+/// DockerDestination structure. This structure implements `ImageDestination` trait.
+#[derive(Debug)]
+pub(crate) struct DockerDestination {
+    pub(crate) reference: DockerReference,
+    pub(super) endpoint: DockerEndpoint,
+}
+
+impl DockerDestination {
+    /// Deletes the manifest for the reference from the Registry and returns it's digest.
+    ///
+    /// If the reference does not have a digest, the tag is resolved to the digest first.
+    pub(crate) async fn delete_manifest(&self) -> ImageResult<Digest> {
+        let client = &self.endpoint.client;
+        let path = &self.endpoint.path;
+
+        let digest = match &self.reference.digest {
+            Some(digest) => digest.clone(),
+            None => client.do_resolve_digest(path, &self.reference.tag).await?,
+        };
+
+        client.do_delete_manifest(path, &digest).await?;
+        log::info!("Deleted Manifest '{}' from '{}'.", digest, path);
+
+        Ok(digest)
+    }
+}
+
+#[async_trait]
+impl ImageDestination for DockerDestination {
+    fn reference(&self) -> Box<dyn ImageReference> {
+        Box::new(self.reference.clone())
+    }
+
+    fn supports_digests(&self) -> bool {
+        true
+    }
+
+    async fn has_blob(&self, digest: &Digest) -> ImageResult<bool> {
+        let client = &self.endpoint.client;
+        let path = &self.endpoint.path;
+
+        let exists = client.do_check_blob(path, digest).await?;
+        if exists {
+            record_blob_location(digest, client.registry(), path).await;
+        }
+
+        Ok(exists)
+    }
+
+    async fn put_blob(
+        &self,
+        blob: Box<dyn AsyncRead + Unpin + Send + Sync>,
+        digest: &Digest,
+        size: Option<u64>,
+    ) -> ImageResult<()> {
+        let client = &self.endpoint.client;
+        let path = &self.endpoint.path;
+
+        // Mount the blob from another repository in the Registry it's known to be in, if any. If
+        // the Registry declines, the blob is uploaded to the upload session it starts instead.
+        let mut upload_url = None;
+        for from in blob_locations(digest, client.registry()) {
+            if &from == path {
+                continue;
+            }
+
+            match client.do_mount_blob(path, digest, &from).await {
+                Ok(None) => {
+                    log::info!("Mounted Blob {} from '{}'.", digest, from);
+                    record_blob_location(digest, client.registry(), path).await;
+                    return Ok(());
+                }
+                Ok(Some(url)) => {
+                    upload_url = Some(url);
+                    break;
+                }
+                Err(e) => log::warn!("Error in Mounting Blob {} from '{}': {}", digest, from, e),
+            }
+        }
+
+        match upload_url {
+            Some(url) => {
+                client
+                    .do_upload_blob(path, &url, blob, digest, size)
+                    .await?
+            }
+            None => client.do_put_blob(path, blob, digest, size).await?,
+        }
+        record_blob_location(digest, client.registry(), path).await;
+
+        Ok(())
+    }
+
+    async fn put_manifest(
+        &mut self,
+        manifest: &ImageManifest,
+        instance_digest: Option<&Digest>,
+    ) -> ImageResult<()> {
+        let digest_or_tag = if let Some(digest) = instance_digest {
+            digest.to_string()
+        } else if let Some(ref_digest) = &self.reference.digest {
+            ref_digest.to_string()
+        } else {
+            self.reference.tag.clone()
+        };
+
+        Ok(self
+            .endpoint
+            .client
+            .do_put_manifest(&self.endpoint.path, &digest_or_tag, manifest)
+            .await?)
+    }
+
+    async fn commit(&mut self) -> ImageResult<()> {
+        // Nothing to be done, the image is visible as soon as the manifest is put.
+        log::debug!("Image '{}' pushed.", self.reference.input_ref);
+        Ok(())
+    }
+}
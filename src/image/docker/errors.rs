@@ -5,6 +5,8 @@ use std::error::Error as StdError;
 use std::fmt;
 use std::result::Result;
 
+use crate::image::types::errors::ImageError;
+
 use super::client::ClientError;
 use super::reference::ReferenceError;
 
@@ -24,6 +26,9 @@ pub enum DockerImageError {
     /// Error inside Docker Image Source
     SourceError(String),
 
+    /// Manifest does not match the requested digest or the `Docker-Content-Digest`
+    ManifestDigestMismatch(String),
+
     /// Catchall Error
     GenericError(String),
 }
@@ -41,6 +46,9 @@ impl fmt::Display for DockerImageError {
                 write!(f, "Docker Transport Error ({})", msg)
             }
             DockerImageError::SourceError(ref msg) => write!(f, "Docker Source Error ({})", msg),
+            DockerImageError::ManifestDigestMismatch(ref msg) => {
+                write!(f, "Docker Manifest Digest Mismatch ({})", msg)
+            }
             DockerImageError::GenericError(ref msg) => write!(f, "Docker Generic Error ({})", msg),
         }
     }
@@ -52,6 +60,12 @@ impl From<ClientError> for DockerImageError {
     }
 }
 
+impl From<DockerImageError> for ImageError {
+    fn from(e: DockerImageError) -> Self {
+        ImageError::new().with(e)
+    }
+}
+
 impl From<ReferenceError> for DockerImageError {
     fn from(e: ReferenceError) -> Self {
         DockerImageError::ReferenceError(format!("{}", e))
@@ -85,6 +85,10 @@ impl Image for DockerImage {
         self.source.as_ref()
     }
 
+    fn source_mut(&mut self) -> &mut (dyn ImageSource + Send + Sync) {
+        self.source.as_mut()
+    }
+
     async fn manifest(&mut self) -> ImageResult<ImageManifest> {
         Ok(self.source.get_manifest(None).await?)
     }
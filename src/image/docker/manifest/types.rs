@@ -7,6 +7,7 @@ use crate::image::types::ImageSource;
 use super::schema2;
 
 #[derive(Debug)]
+#[allow(dead_code)]
 pub(crate) struct DockerManifestSchema2 {
     _source: Box<dyn ImageSource>,
     _schema: schema2::Schema2,
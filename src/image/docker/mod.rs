@@ -3,12 +3,20 @@
 //! References:
 //! [Docker Implementation](https://github.com/containers/image/tree/master/docker)
 
+pub mod archive;
+pub(crate) mod auth;
+pub(crate) mod blob_locations;
+pub(crate) mod catalog;
+pub mod certs;
 pub mod client;
 pub mod dst;
 pub mod errors;
 pub mod image;
 mod manifest;
+pub(crate) mod proxy;
 pub mod reference;
+pub(crate) mod registries;
+pub mod retry;
 pub mod source;
 pub mod transport;
 
@@ -16,6 +24,12 @@ pub(crate) const MEDIA_TYPE_DOCKER_V2_SCHEMA2_MANIFEST: &str =
     "application/vnd.docker.distribution.manifest.v2+json";
 pub(crate) const MEDIA_TYPE_DOCKER_V2_LIST: &str =
     "application/vnd.docker.distribution.manifest.list.v2+json";
+pub(crate) const MEDIA_TYPE_DOCKER_V2_SCHEMA2_CONFIG: &str =
+    "application/vnd.docker.container.image.v1+json";
+pub(crate) const MEDIA_TYPE_DOCKER_V2_SCHEMA2_LAYER: &str =
+    "application/vnd.docker.image.rootfs.diff.tar.gzip";
+pub(crate) const MEDIA_TYPE_DOCKER_V2_SCHEMA2_LAYER_UNCOMPRESSED: &str =
+    "application/vnd.docker.image.rootfs.diff.tar";
 
 #[cfg(test)]
 mod testdata;
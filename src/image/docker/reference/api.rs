@@ -22,6 +22,7 @@ const MAX_REFNAME_LEN: usize = 256;
 /// - 'docker.io/image' -> 'docker.io/library/image:latest'
 /// - 'docker.io/image:latest' -> 'docker.io/library/image:latest'
 /// - 'foo/bar:baz' -> 'docker.io/foo/bar:baz'
+///
 /// Note: Converting 'docker.io' to actual Domain Name is taken care of by Docker Client.
 ///
 pub(crate) fn parse(input_ref: &str) -> DockerReferenceResult {
@@ -34,6 +35,7 @@ pub(crate) fn parse(input_ref: &str) -> DockerReferenceResult {
     // expression.
     // localhost/foo/bar is -> domain('localhost'), path('foo/bar'), but
     // foo/bar is -> domain('docker.io'), path('foo/bar')
+    let qualified = is_qualified(input_ref);
     let input_ref = get_domain_name(input_ref);
     let (name, mut tag, digest): (String, String, &str);
     let captured_ref = ANCHORED_REFERENCE_RE.captures(&input_ref);
@@ -54,7 +56,7 @@ pub(crate) fn parse(input_ref: &str) -> DockerReferenceResult {
             }
 
             tag = String::from(c.get(2).map_or("", |m| m.as_str()));
-            digest = c.get(2).map_or("", |m| m.as_str());
+            digest = c.get(3).map_or("", |m| m.as_str());
 
             let name_captures = ANCHORED_CAPTURING_NAME_RE.captures(&name);
 
@@ -105,6 +107,18 @@ pub(crate) fn parse(input_ref: &str) -> DockerReferenceResult {
                         tag = String::from(DEFAULT_TAG);
                     }
 
+                    // Unqualified names are resolved using the Registries Configuration.
+                    let short_name = if qualified {
+                        None
+                    } else {
+                        let default_prefix = format!("{}/", DEFAULT_DOCKER_DOMAIN);
+                        Some(
+                            name.strip_prefix(&default_prefix)
+                                .unwrap_or(&name)
+                                .to_string(),
+                        )
+                    };
+
                     Ok(DockerReference {
                         repo: DockerRepo {
                             domain,
@@ -113,6 +127,7 @@ pub(crate) fn parse(input_ref: &str) -> DockerReferenceResult {
                         tag,
                         digest: Digest::new_from_str(digest),
                         input_ref,
+                        short_name,
                     })
                 }
                 None => Err(ReferenceError::NameNotCanonical),
@@ -122,6 +137,16 @@ pub(crate) fn parse(input_ref: &str) -> DockerReferenceResult {
     }
 }
 
+// Returns whether the `input` has a domain (eg. 'quay.io/foo/bar' or 'localhost/foo').
+fn is_qualified(input: &str) -> bool {
+    match input.split_once('/') {
+        Some((maybe_domain, _)) => {
+            maybe_domain.contains(&['.', ':'][..]) || maybe_domain == "localhost"
+        }
+        None => false,
+    }
+}
+
 fn get_domain_name(input: &str) -> String {
     let slash = input.find('/');
     if slash.is_none() {
@@ -135,7 +160,7 @@ fn get_domain_name(input: &str) -> String {
         if maybe_domain == "localhost" {
             return input.to_string();
         }
-        return vec![DEFAULT_DOCKER_DOMAIN.to_string(), input.to_string()].join("/");
+        return [DEFAULT_DOCKER_DOMAIN.to_string(), input.to_string()].join("/");
     }
 
     input.to_string()
@@ -164,6 +189,7 @@ mod tests {
                     tag: String::from("latest"),
                     digest: None,
                     input_ref: String::from("fedora"),
+                    short_name: Some(String::from("fedora")),
                 }),
             },
             ParseTC {
@@ -176,6 +202,7 @@ mod tests {
                     tag: String::from("v9"),
                     digest: None,
                     input_ref: String::from("docker.io/rustvmm/dev:v9"),
+                    short_name: Some(String::from("rustvmm/dev")),
                 }),
             },
             ParseTC {
@@ -188,6 +215,7 @@ mod tests {
                     tag: String::from("latest"),
                     digest: None,
                     input_ref: String::from("docker.io/foo/bar/baz"),
+                    short_name: Some(String::from("foo/bar/baz")),
                 }),
             },
             ParseTC {
@@ -200,6 +228,7 @@ mod tests {
                     tag: String::from("latest"),
                     digest: None,
                     input_ref: String::from("localhost/foo/bar"),
+                    short_name: None,
                 }),
             },
             ParseTC {
@@ -212,6 +241,7 @@ mod tests {
                     tag: String::from("latest"),
                     digest: None,
                     input_ref: String::from("localhost:8000/foo/bar"),
+                    short_name: None,
                 }),
             },
             ParseTC {
@@ -224,6 +254,7 @@ mod tests {
                     tag: String::from("latest"),
                     digest: None,
                     input_ref: String::from("a.b.c.d:8000/foo/bar"),
+                    short_name: None,
                 }),
             },
             ParseTC {
@@ -244,6 +275,37 @@ mod tests {
                     tag: String::from("f32"),
                     digest: None,
                     input_ref: String::from("fedora:f32"),
+                    short_name: Some(String::from("fedora")),
+                }),
+            },
+            ParseTC {
+                input_ref: "fedora:f32@sha256:fdf235fa167d2aa5d820fba274ec1d2edeb0534bd32d28d602a19b31bad79b80",
+                output_ref_result: Ok(DockerReference {
+                    repo: DockerRepo {
+                        domain: String::from(DEFAULT_DOCKER_DOMAIN),
+                        path: String::from("library/fedora"),
+                    },
+                    tag: String::from("f32"),
+                    digest: Digest::new_from_str(
+                        "sha256:fdf235fa167d2aa5d820fba274ec1d2edeb0534bd32d28d602a19b31bad79b80",
+                    ),
+                    input_ref: String::from("fedora:f32@sha256:fdf235fa167d2aa5d820fba274ec1d2edeb0534bd32d28d602a19b31bad79b80"),
+                    short_name: Some(String::from("fedora")),
+                }),
+            },
+            ParseTC {
+                input_ref: "localhost:5000/foo/bar@sha256:fdf235fa167d2aa5d820fba274ec1d2edeb0534bd32d28d602a19b31bad79b80",
+                output_ref_result: Ok(DockerReference {
+                    repo: DockerRepo {
+                        domain: String::from("localhost:5000"),
+                        path: String::from("foo/bar"),
+                    },
+                    tag: String::from("latest"),
+                    digest: Digest::new_from_str(
+                        "sha256:fdf235fa167d2aa5d820fba274ec1d2edeb0534bd32d28d602a19b31bad79b80",
+                    ),
+                    input_ref: String::from("localhost:5000/foo/bar@sha256:fdf235fa167d2aa5d820fba274ec1d2edeb0534bd32d28d602a19b31bad79b80"),
+                    short_name: None,
                 }),
             },
             ParseTC {
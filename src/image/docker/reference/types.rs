@@ -6,10 +6,11 @@ use std::collections::HashMap;
 
 use crate::image::{
     docker::{
-        client::DockerClient, image::DockerImage, source::DockerSource, transport::DockerTransport,
+        dst::DockerDestination, image::DockerImage, registries::RegistriesConfig,
+        source::DockerSource, transport::DockerTransport,
     },
     oci::digest::Digest,
-    types::{Image, ImageReference, ImageResult, ImageSource, ImageTransport},
+    types::{Image, ImageDestination, ImageReference, ImageResult, ImageSource, ImageTransport},
 };
 
 use super::errors::ReferenceError;
@@ -32,6 +33,9 @@ pub(crate) struct DockerReference {
     pub(crate) tag: String,
     pub(crate) digest: Option<Digest>,
     pub(crate) input_ref: String, // The string that was originally sent to us
+
+    // The name (sans Tag and Digest) as sent to us, if it was not fully qualified (eg. 'fedora').
+    pub(crate) short_name: Option<String>,
 }
 
 impl DockerReference {
@@ -43,6 +47,34 @@ impl DockerReference {
         &self.repo.path
     }
 
+    /// Returns a `DockerSource` for the reference, with the endpoints from the `config`.
+    pub(crate) fn new_docker_source(&self, config: &RegistriesConfig) -> ImageResult<DockerSource> {
+        let endpoints = config
+            .pull_endpoints(self)?
+            .into_iter()
+            .map(|endpoint| endpoint.into_docker_endpoint())
+            .collect::<Result<_, _>>()?;
+
+        Ok(DockerSource {
+            reference: self.clone(),
+            endpoints,
+            manifest_cache: HashMap::new(),
+        })
+    }
+
+    /// Returns a `DockerDestination` for the reference, with the endpoint from the `config`.
+    pub(crate) fn new_docker_destination(
+        &self,
+        config: &RegistriesConfig,
+    ) -> ImageResult<DockerDestination> {
+        let endpoint = config.push_endpoint(self)?.into_docker_endpoint()?;
+
+        Ok(DockerDestination {
+            reference: self.clone(),
+            endpoint,
+        })
+    }
+
     pub(crate) fn get_string_within_transport(&self) -> String {
         let mut s = format!("//{}/{}", self.repo.domain, self.repo.path);
         if !self.tag.is_empty() {
@@ -73,14 +105,7 @@ impl ImageReference for DockerReference {
 
     /// Returns an object implementing trait 'ImageSource' (in our case 'DockerSource').
     fn new_image_source(&self) -> ImageResult<Box<dyn ImageSource + Send + Sync>> {
-        let domain = self.domain();
-        let client = DockerClient::new(domain);
-
-        Ok(Box::new(DockerSource {
-            reference: self.clone(),
-            client,
-            manifest_cache: HashMap::new(),
-        }))
+        Ok(Box::new(self.new_docker_source(&RegistriesConfig::load())?))
     }
 
     /// Returns an object implementing trait 'Image' in our case 'DockerImage'
@@ -100,6 +125,13 @@ impl ImageReference for DockerReference {
     fn docker_reference(&self) -> Option<Box<dyn DockerImageReference>> {
         Some(Box::new(self.clone()))
     }
+
+    /// Returns an object implementing trait 'ImageDestination' (in our case 'DockerDestination').
+    fn new_image_destination(&self) -> ImageResult<Box<dyn ImageDestination + Send + Sync>> {
+        Ok(Box::new(
+            self.new_docker_destination(&RegistriesConfig::load())?,
+        ))
+    }
 }
 
 impl DockerImageReference for DockerReference {
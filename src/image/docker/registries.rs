@@ -0,0 +1,873 @@
+//! Support for the Registries Configuration (`registries.conf`)
+//!
+//! The configuration is read from the [registries.conf][registries_conf] (Version 2) file. The
+//! file is looked up in the following order and the first one found is used.
+//!
+//! 1. `$CONTAINERS_REGISTRIES_CONF`
+//! 2. `$XDG_CONFIG_HOME/containers/registries.conf` (usually `~/.config/containers/registries.conf`)
+//! 3. `/etc/containers/registries.conf`
+//!
+//! Each `[[registry]]` table applies to the references matching it's `prefix` (the longest
+//! matching prefix is used). A `prefix` is either a `host[:port][/path]` matching at the path
+//! component boundary or a wildcard `*.domain` matching all the sub-domains of the `domain`. The
+//! matching references are rewritten to the `location` of the registry and are pulled from it's
+//! mirrors (`[[registry.mirror]]`) in the given order, before the `location` itself. For a
+//! wildcard `prefix`, the matching domain is rewritten to the location of the mirror. Pulling from
+//! or pushing to a `blocked` registry is an error. Whether a registry is `insecure` (accessed
+//! over `http`) is given by the configuration, rather than guessed from the URL.
+//!
+//! A short name (eg. `fedora`, without a registry) is resolved using the `[aliases]` table (eg.
+//! `"fedora" = "registry.fedoraproject.org/fedora"`), if it has an alias for the name. Otherwise
+//! the name is looked up in the `unqualified-search-registries` (that are not blocked) in the
+//! given order. If neither is configured, `docker.io` is used. The aliases are also read from the
+//! `shortnames.conf` file (looked up like `registries.conf`), which take precedence over the ones
+//! in `registries.conf`.
+//!
+//! [registries_conf]: https://github.com/containers/image/blob/main/docs/containers-registries.conf.5.md
+
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use directories::BaseDirs;
+use serde::Deserialize;
+
+use crate::image::{
+    docker::{
+        client::{ClientError, DockerClient, DockerEndpoint},
+        reference::{api::parse, types::DockerReference},
+    },
+    types::errors::ImageError,
+};
+
+const SYSTEM_REGISTRIES_CONF_PATH: &str = "/etc/containers/registries.conf";
+
+const SYSTEM_SHORTNAMES_CONF_PATH: &str = "/etc/containers/shortnames.conf";
+
+#[derive(Debug)]
+pub(crate) struct RegistriesError(String);
+
+impl fmt::Display for RegistriesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Registries Error: {}", self.0)
+    }
+}
+
+impl StdError for RegistriesError {}
+
+impl From<RegistriesError> for ImageError {
+    fn from(e: RegistriesError) -> Self {
+        ImageError::new().with(e)
+    }
+}
+
+/// Registries Configuration read from `registries.conf`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct RegistriesConfig {
+    #[serde(default, rename = "registry")]
+    registries: Vec<Registry>,
+
+    #[serde(default)]
+    unqualified_search_registries: Vec<String>,
+
+    // Short names to fully qualified names (without tag or digest).
+    #[serde(default)]
+    aliases: HashMap<String, String>,
+}
+
+// Short name aliases read from `shortnames.conf`.
+#[derive(Debug, Default, Deserialize)]
+struct ShortNamesConfig {
+    #[serde(default)]
+    aliases: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Registry {
+    // Defaults to the `location`.
+    #[serde(default)]
+    prefix: String,
+
+    #[serde(default)]
+    location: String,
+
+    #[serde(default)]
+    insecure: bool,
+
+    #[serde(default)]
+    blocked: bool,
+
+    // Mirrors are used only when pulling by digest.
+    #[serde(default)]
+    mirror_by_digest_only: bool,
+
+    #[serde(default, rename = "mirror")]
+    mirrors: Vec<Mirror>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Mirror {
+    location: String,
+
+    #[serde(default)]
+    insecure: bool,
+}
+
+/// A repository at a Registry, that an image is pulled from or pushed to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RegistryEndpoint {
+    pub(crate) domain: String,
+    pub(crate) path: String,
+
+    // `None` if the Registry is not configured.
+    pub(crate) insecure: Option<bool>,
+}
+
+impl RegistryEndpoint {
+    /// Returns a Docker Client for the endpoint.
+    pub(super) fn into_docker_endpoint(self) -> Result<DockerEndpoint, ClientError> {
+        Ok(DockerEndpoint {
+            client: DockerClient::new(&self.domain, self.insecure)?,
+            path: self.path,
+        })
+    }
+}
+
+impl RegistriesConfig {
+    /// Loads the configuration from the first `registries.conf` found.
+    ///
+    /// If no file is found (or the file is invalid), an empty configuration is returned.
+    pub(crate) fn load() -> Self {
+        Self::load_from(&registries_conf_paths(), &shortnames_conf_paths())
+    }
+
+    // Loads the configuration from the first of the `registries_paths` found, with the aliases
+    // from the first of the `shortnames_paths` found.
+    fn load_from(registries_paths: &[PathBuf], shortnames_paths: &[PathBuf]) -> Self {
+        let mut config = match registries_paths.iter().find(|p| p.exists()) {
+            Some(path) => {
+                log::debug!("Reading Registries Configuration from {:?}.", path);
+                Self::from_file(path).unwrap_or_else(|e| {
+                    log::warn!("Ignoring the Registries Configuration {:?}: {}", path, e);
+                    Self::default()
+                })
+            }
+            None => Self::default(),
+        };
+
+        if let Some(path) = shortnames_paths.iter().find(|p| p.exists()) {
+            log::debug!("Reading Short Name Aliases from {:?}.", path);
+            let aliases = std::fs::read_to_string(path)
+                .map_err(|e| RegistriesError(format!("Error reading {:?}: {}", path, e)))
+                .and_then(|contents| parse_short_name_aliases(&contents));
+            match aliases {
+                Ok(aliases) => config.aliases.extend(aliases),
+                Err(e) => log::warn!("Ignoring the Short Name Aliases {:?}: {}", path, e),
+            }
+        }
+
+        config
+    }
+
+    /// Reads the configuration from the file at `path`.
+    pub(crate) fn from_file(path: &Path) -> Result<Self, RegistriesError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| RegistriesError(format!("Error reading {:?}: {}", path, e)))?;
+
+        Self::parse(&contents)
+    }
+
+    /// Parses the configuration from a TOML string.
+    pub(crate) fn parse(contents: &str) -> Result<Self, RegistriesError> {
+        let mut config: RegistriesConfig =
+            toml::from_str(contents).map_err(|e| RegistriesError(e.to_string()))?;
+
+        for registry in &mut config.registries {
+            if registry.prefix.is_empty() {
+                registry.prefix = registry.location.clone();
+            }
+            if registry.prefix.is_empty() {
+                return crate::log_err_return!(
+                    RegistriesError,
+                    "One of 'prefix' or 'location' is required for a registry."
+                );
+            }
+            if registry.prefix.starts_with("*.") && !registry.location.is_empty() {
+                return crate::log_err_return!(
+                    RegistriesError,
+                    "Wildcard prefix '{}' can't have a location.",
+                    registry.prefix
+                );
+            }
+        }
+
+        check_short_name_aliases(&config.aliases)?;
+
+        Ok(config)
+    }
+
+    /// Returns the endpoints to pull the image for the `reference` from, in the order they should
+    /// be tried.
+    ///
+    /// For a short name, the endpoints for each of the names it resolves to (see
+    /// `qualified_names`) are returned in order.
+    pub(crate) fn pull_endpoints(
+        &self,
+        reference: &DockerReference,
+    ) -> Result<Vec<RegistryEndpoint>, RegistriesError> {
+        let mut endpoints = vec![];
+        for name in self.qualified_names(reference)? {
+            // A short name is looked up in the other registries, if one of them is blocked.
+            let blocked = self.find_registry(&name).is_some_and(|r| r.blocked);
+            if blocked && reference.short_name.is_some() {
+                log::warn!("Skipping '{}', the Registry for it is blocked.", name);
+                continue;
+            }
+            endpoints.extend(self.name_pull_endpoints(&name, reference.digest.is_some())?);
+        }
+
+        if endpoints.is_empty() {
+            return crate::log_err_return!(
+                RegistriesError,
+                "Registries for '{}' are blocked.",
+                reference.input_ref
+            );
+        }
+
+        Ok(endpoints)
+    }
+
+    // Returns the endpoints to pull the image with the fully qualified `name` from.
+    fn name_pull_endpoints(
+        &self,
+        name: &str,
+        by_digest: bool,
+    ) -> Result<Vec<RegistryEndpoint>, RegistriesError> {
+        let registry = match self.find_registry(name) {
+            Some(registry) => registry,
+            None => return Ok(vec![unconfigured_endpoint(name)]),
+        };
+
+        if registry.blocked {
+            return crate::log_err_return!(RegistriesError, "Registry for '{}' is blocked.", name);
+        }
+
+        let mut endpoints = vec![];
+        if !registry.mirror_by_digest_only || by_digest {
+            for mirror in &registry.mirrors {
+                endpoints.push(registry.endpoint(&mirror.location, mirror.insecure, name)?);
+            }
+        }
+        endpoints.push(registry.endpoint(&registry.location, registry.insecure, name)?);
+
+        log::debug!("Pull Endpoints for '{}': {:?}", name, endpoints);
+        Ok(endpoints)
+    }
+
+    /// Returns the fully qualified names (`domain/path`) for the `reference`, in the order they
+    /// should be tried.
+    ///
+    /// A short name is resolved to it's alias (if any), otherwise it is qualified with each of
+    /// the `unqualified-search-registries`. If neither is configured, the name is qualified with
+    /// `docker.io` (as done by the parser).
+    pub(crate) fn qualified_names(
+        &self,
+        reference: &DockerReference,
+    ) -> Result<Vec<String>, RegistriesError> {
+        let default_name = format!("{}/{}", reference.domain(), reference.path());
+        let short_name = match &reference.short_name {
+            Some(short_name) => short_name,
+            None => return Ok(vec![default_name]),
+        };
+
+        if let Some(alias) = self.aliases.get(short_name) {
+            log::debug!("Using the Alias '{}' for '{}'.", alias, short_name);
+            return Ok(vec![qualified_name(alias)?]);
+        }
+
+        if self.unqualified_search_registries.is_empty() {
+            return Ok(vec![default_name]);
+        }
+
+        let names = self
+            .unqualified_search_registries
+            .iter()
+            .map(|registry| qualified_name(&format!("{}/{}", registry, short_name)))
+            .collect::<Result<Vec<_>, _>>()?;
+        log::debug!("Searching for '{}' as: {:?}", short_name, names);
+
+        Ok(names)
+    }
+
+    /// Returns the endpoint to push the image for the `reference` to. Mirrors are never pushed
+    /// to.
+    ///
+    /// A short name is resolved to it's alias (if any), the search registries are not used (a
+    /// name to push to should not be ambiguous), it is qualified with `docker.io` instead.
+    pub(crate) fn push_endpoint(
+        &self,
+        reference: &DockerReference,
+    ) -> Result<RegistryEndpoint, RegistriesError> {
+        let alias = reference
+            .short_name
+            .as_ref()
+            .and_then(|short_name| self.aliases.get(short_name));
+        let name = match alias {
+            Some(alias) => {
+                log::debug!("Using the Alias '{}' for Pushing.", alias);
+                qualified_name(alias)?
+            }
+            None => format!("{}/{}", reference.domain(), reference.path()),
+        };
+        match self.find_registry(&name) {
+            Some(registry) if registry.blocked => {
+                crate::log_err_return!(RegistriesError, "Registry for '{}' is blocked.", name)
+            }
+            Some(registry) => registry.endpoint(&registry.location, registry.insecure, &name),
+            None => Ok(unconfigured_endpoint(&name)),
+        }
+    }
+
+    /// Returns whether the Registry for the `domain` is insecure, `None` if it is not configured.
+    pub(crate) fn registry_insecure(&self, domain: &str) -> Option<bool> {
+        self.find_registry(domain).map(|registry| registry.insecure)
+    }
+
+    // Returns the Registry with the longest prefix matching the `name`.
+    fn find_registry(&self, name: &str) -> Option<&Registry> {
+        self.registries
+            .iter()
+            .filter(|registry| prefix_matches(&registry.prefix, name))
+            .max_by_key(|registry| registry.prefix.len())
+    }
+}
+
+impl Registry {
+    // Returns the endpoint at the `location` for the `name` matching the prefix.
+    fn endpoint(
+        &self,
+        location: &str,
+        insecure: bool,
+        name: &str,
+    ) -> Result<RegistryEndpoint, RegistriesError> {
+        let rewritten = if location.is_empty() {
+            name.to_string()
+        } else if self.prefix.starts_with("*.") {
+            // The wildcard matches the domain of the `name`, which is replaced.
+            let domain_len = name.find('/').unwrap_or(name.len());
+            format!("{}{}", location, &name[domain_len..])
+        } else {
+            format!("{}{}", location, &name[self.prefix.len()..])
+        };
+
+        match rewritten.split_once('/') {
+            Some((domain, path)) if !domain.is_empty() && !path.is_empty() => {
+                Ok(RegistryEndpoint {
+                    domain: domain.to_string(),
+                    path: path.to_string(),
+                    insecure: Some(insecure),
+                })
+            }
+            _ => crate::log_err_return!(
+                RegistriesError,
+                "Invalid reference '{}' after rewriting '{}'.",
+                rewritten,
+                name
+            ),
+        }
+    }
+}
+
+// Returns the endpoint for the fully qualified `name` (`domain/path`) as it is.
+fn unconfigured_endpoint(name: &str) -> RegistryEndpoint {
+    let (domain, path) = name.split_once('/').unwrap_or((name, ""));
+    RegistryEndpoint {
+        domain: domain.to_string(),
+        path: path.to_string(),
+        insecure: None,
+    }
+}
+
+// Returns the `name` as `domain/path`, with the defaults applied (eg. `docker.io/fedora` is
+// `docker.io/library/fedora`).
+fn qualified_name(name: &str) -> Result<String, RegistriesError> {
+    match parse(name) {
+        Ok(reference) => Ok(format!("{}/{}", reference.domain(), reference.path())),
+        Err(e) => crate::log_err_return!(RegistriesError, "Invalid name '{}': {}", name, e),
+    }
+}
+
+// Parses the `[aliases]` table of `shortnames.conf`.
+fn parse_short_name_aliases(contents: &str) -> Result<HashMap<String, String>, RegistriesError> {
+    let config: ShortNamesConfig =
+        toml::from_str(contents).map_err(|e| RegistriesError(e.to_string()))?;
+    check_short_name_aliases(&config.aliases)?;
+
+    Ok(config.aliases)
+}
+
+// Checks that the aliases are for short names and are fully qualified names without a tag or
+// digest.
+fn check_short_name_aliases(aliases: &HashMap<String, String>) -> Result<(), RegistriesError> {
+    for (short_name, alias) in aliases {
+        let short_ref = parse(short_name).ok();
+        if short_ref.map(|r| r.short_name.is_none()).unwrap_or(true) {
+            return crate::log_err_return!(
+                RegistriesError,
+                "Alias key '{}' is not a short name.",
+                short_name
+            );
+        }
+
+        let alias_ref = parse(alias).ok();
+        let is_valid = alias_ref
+            .map(|r| r.short_name.is_none() && r.digest.is_none() && !alias.contains('@'))
+            .unwrap_or(false);
+        let has_tag = alias
+            .rsplit('/')
+            .next()
+            .map(|last| last.contains(':'))
+            .unwrap_or(false);
+        if !is_valid || has_tag {
+            return crate::log_err_return!(
+                RegistriesError,
+                "Alias '{}' for '{}' is not a fully qualified name without a tag or digest.",
+                alias,
+                short_name
+            );
+        }
+    }
+
+    Ok(())
+}
+
+// Returns whether the `prefix` matches the `name` (`domain/path`).
+fn prefix_matches(prefix: &str, name: &str) -> bool {
+    if let Some(parent) = prefix.strip_prefix("*.") {
+        let domain = name.split('/').next().unwrap_or_default();
+        return domain
+            .strip_suffix(parent)
+            .map(|sub| sub.ends_with('.') && sub.len() > 1)
+            .unwrap_or(false);
+    }
+
+    name.strip_prefix(prefix)
+        .map(|rest| rest.is_empty() || rest.starts_with('/'))
+        .unwrap_or(false)
+}
+
+// Returns the paths for `registries.conf`, in the order they are looked up.
+fn registries_conf_paths() -> Vec<PathBuf> {
+    conf_paths_from(
+        |name| std::env::var_os(name),
+        user_config_dir(),
+        "CONTAINERS_REGISTRIES_CONF",
+        "registries.conf",
+        SYSTEM_REGISTRIES_CONF_PATH,
+    )
+}
+
+// Returns the paths for `shortnames.conf`, in the order they are looked up.
+fn shortnames_conf_paths() -> Vec<PathBuf> {
+    conf_paths_from(
+        |name| std::env::var_os(name),
+        user_config_dir(),
+        "CONTAINERS_SHORTNAMES_CONF",
+        "shortnames.conf",
+        SYSTEM_SHORTNAMES_CONF_PATH,
+    )
+}
+
+// Returns the user's configuration directory (usually `~/.config`).
+fn user_config_dir() -> Option<PathBuf> {
+    BaseDirs::new().map(|base_dirs| base_dirs.config_dir().to_path_buf())
+}
+
+// Returns the paths for the configuration file `file_name` in the order they are looked up. The
+// path given by the environment variable `var_name` (`var` returns the value of a variable),
+// followed by the file in the user's `config_dir` and the `system_path`.
+fn conf_paths_from<F>(
+    var: F,
+    config_dir: Option<PathBuf>,
+    var_name: &str,
+    file_name: &str,
+    system_path: &str,
+) -> Vec<PathBuf>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    let mut paths = vec![];
+
+    if let Some(path) = var(var_name) {
+        paths.push(PathBuf::from(path));
+    }
+
+    if let Some(mut path) = config_dir {
+        path.push("containers");
+        path.push(file_name);
+        paths.push(path);
+    }
+
+    paths.push(PathBuf::from(system_path));
+
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::image::docker::reference::api::parse;
+
+    const TEST_REGISTRIES_CONF: &str = r#"
+unqualified-search-registries = ["docker.io"]
+
+[[registry]]
+prefix = "example.com/foo"
+location = "internal.example.com/bar"
+insecure = true
+
+[[registry]]
+prefix = "example.com"
+location = "example.com"
+
+[[registry.mirror]]
+location = "mirror-1.example.com"
+
+[[registry.mirror]]
+location = "mirror-2.example.com:5000"
+insecure = true
+
+[[registry]]
+location = "registry.local:5000"
+
+[[registry]]
+prefix = "*.blocked.io"
+blocked = true
+
+[[registry]]
+location = "digest-mirrored.io"
+mirror-by-digest-only = true
+
+[[registry.mirror]]
+location = "mirror.digest-mirrored.io"
+"#;
+
+    fn endpoint(domain: &str, path: &str, insecure: Option<bool>) -> RegistryEndpoint {
+        RegistryEndpoint {
+            domain: domain.to_string(),
+            path: path.to_string(),
+            insecure,
+        }
+    }
+
+    #[test]
+    fn test_prefix_matches() {
+        assert!(prefix_matches("example.com", "example.com/foo"));
+        assert!(prefix_matches("example.com/foo", "example.com/foo"));
+        assert!(prefix_matches("example.com/foo", "example.com/foo/bar"));
+        assert!(!prefix_matches("example.com/foo", "example.com/foobar"));
+        assert!(!prefix_matches("example.com", "example.community/foo"));
+
+        assert!(prefix_matches("*.example.com", "a.example.com/foo"));
+        assert!(prefix_matches("*.example.com", "a.b.example.com/foo"));
+        assert!(!prefix_matches("*.example.com", "example.com/foo"));
+        assert!(!prefix_matches("*.example.com", "aexample.com/foo"));
+    }
+
+    #[test]
+    fn test_pull_endpoints() {
+        let config = RegistriesConfig::parse(TEST_REGISTRIES_CONF).unwrap();
+
+        // Longest prefix, with location rewritten.
+        let reference = parse("example.com/foo/app:latest").unwrap();
+        assert_eq!(
+            config.pull_endpoints(&reference).unwrap(),
+            vec![endpoint("internal.example.com", "bar/app", Some(true))]
+        );
+
+        // Mirrors in order, followed by the location.
+        let reference = parse("example.com/other/app").unwrap();
+        assert_eq!(
+            config.pull_endpoints(&reference).unwrap(),
+            vec![
+                endpoint("mirror-1.example.com", "other/app", Some(false)),
+                endpoint("mirror-2.example.com:5000", "other/app", Some(true)),
+                endpoint("example.com", "other/app", Some(false)),
+            ]
+        );
+
+        // A secure registry with a port.
+        let reference = parse("registry.local:5000/app").unwrap();
+        assert_eq!(
+            config.pull_endpoints(&reference).unwrap(),
+            vec![endpoint("registry.local:5000", "app", Some(false))]
+        );
+
+        // Not configured.
+        let reference = parse("quay.io/app/app").unwrap();
+        assert_eq!(
+            config.pull_endpoints(&reference).unwrap(),
+            vec![endpoint("quay.io", "app/app", None)]
+        );
+    }
+
+    #[test]
+    fn test_wildcard_registry_mirrors() {
+        let config = RegistriesConfig::parse(
+            r#"
+[[registry]]
+prefix = "*.example.io"
+
+[[registry.mirror]]
+location = "mirror.example.com/example-io"
+
+[[registry.mirror]]
+location = "mirror.example.net:5000"
+insecure = true
+"#,
+        )
+        .unwrap();
+
+        // The matched domain is rewritten to the mirrors, the name is used as it is otherwise.
+        let reference = parse("registry.example.io/app/app:latest").unwrap();
+        assert_eq!(
+            config.pull_endpoints(&reference).unwrap(),
+            vec![
+                endpoint("mirror.example.com", "example-io/app/app", Some(false)),
+                endpoint("mirror.example.net:5000", "app/app", Some(true)),
+                endpoint("registry.example.io", "app/app", Some(false)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mirror_by_digest_only() {
+        let config = RegistriesConfig::parse(TEST_REGISTRIES_CONF).unwrap();
+
+        let reference = parse("digest-mirrored.io/app:latest").unwrap();
+        assert_eq!(config.pull_endpoints(&reference).unwrap().len(), 1);
+
+        let reference = parse(
+            "digest-mirrored.io/app@sha256:fdf235fa167d2aa5d820fba274ec1d2edeb0534bd32d28d602a19b31bad79b80",
+        )
+        .unwrap();
+        let endpoints = config.pull_endpoints(&reference).unwrap();
+        assert_eq!(endpoints.len(), 2);
+        assert_eq!(endpoints[0].domain, "mirror.digest-mirrored.io");
+    }
+
+    #[test]
+    fn test_blocked_registry() {
+        let config = RegistriesConfig::parse(TEST_REGISTRIES_CONF).unwrap();
+
+        let reference = parse("registry.blocked.io/app").unwrap();
+        assert!(config.pull_endpoints(&reference).is_err());
+        assert!(config.push_endpoint(&reference).is_err());
+
+        // A short name is looked up in the search registries that are not blocked.
+        let config = RegistriesConfig::parse(
+            r#"
+unqualified-search-registries = ["registry.blocked.io", "docker.io"]
+
+[[registry]]
+prefix = "*.blocked.io"
+blocked = true
+"#,
+        )
+        .unwrap();
+        let reference = parse("fedora").unwrap();
+        assert_eq!(
+            config.pull_endpoints(&reference).unwrap(),
+            vec![endpoint("docker.io", "library/fedora", None)]
+        );
+        let reference = parse("registry.blocked.io/fedora").unwrap();
+        assert!(config.pull_endpoints(&reference).is_err());
+
+        let config = RegistriesConfig::parse(
+            "unqualified-search-registries = [\"registry.blocked.io\"]\n\n[[registry]]\nprefix = \"*.blocked.io\"\nblocked = true\n",
+        )
+        .unwrap();
+        assert!(config.pull_endpoints(&parse("fedora").unwrap()).is_err());
+
+        // Push never goes to the mirrors.
+        let config = RegistriesConfig::parse(TEST_REGISTRIES_CONF).unwrap();
+        let reference = parse("example.com/other/app").unwrap();
+        assert_eq!(
+            config.push_endpoint(&reference).unwrap(),
+            endpoint("example.com", "other/app", Some(false))
+        );
+    }
+
+    #[test]
+    fn test_short_names() {
+        let config = RegistriesConfig::parse(
+            r#"
+unqualified-search-registries = ["registry.fedoraproject.org", "docker.io"]
+
+[aliases]
+"ubi" = "registry.access.redhat.com/ubi8/ubi"
+"hub/busybox" = "docker.io/busybox"
+
+[[registry]]
+location = "registry.fedoraproject.org"
+
+[[registry.mirror]]
+location = "mirror.fedoraproject.org"
+"#,
+        )
+        .unwrap();
+
+        // Searched in the order, with the mirrors.
+        let reference = parse("fedora:38").unwrap();
+        assert_eq!(
+            config.pull_endpoints(&reference).unwrap(),
+            vec![
+                endpoint("mirror.fedoraproject.org", "fedora", Some(false)),
+                endpoint("registry.fedoraproject.org", "fedora", Some(false)),
+                endpoint("docker.io", "library/fedora", None),
+            ]
+        );
+
+        // Aliases are not searched.
+        let reference =
+            parse("ubi@sha256:fdf235fa167d2aa5d820fba274ec1d2edeb0534bd32d28d602a19b31bad79b80")
+                .unwrap();
+        assert_eq!(
+            config.qualified_names(&reference).unwrap(),
+            vec!["registry.access.redhat.com/ubi8/ubi"]
+        );
+        let reference = parse("hub/busybox").unwrap();
+        assert_eq!(
+            config.qualified_names(&reference).unwrap(),
+            vec!["docker.io/library/busybox"]
+        );
+
+        // Pushed to the alias, else to 'docker.io'.
+        let reference = parse("ubi").unwrap();
+        assert_eq!(
+            config.push_endpoint(&reference).unwrap(),
+            endpoint("registry.access.redhat.com", "ubi8/ubi", None)
+        );
+        let reference = parse("fedora").unwrap();
+        assert_eq!(
+            config.push_endpoint(&reference).unwrap(),
+            endpoint("docker.io", "library/fedora", None)
+        );
+
+        // Fully qualified names are used as they are.
+        let reference = parse("docker.io/fedora").unwrap();
+        assert_eq!(
+            config.qualified_names(&reference).unwrap(),
+            vec!["docker.io/library/fedora"]
+        );
+
+        // Defaults to 'docker.io'.
+        let reference = parse("fedora").unwrap();
+        assert_eq!(
+            RegistriesConfig::default()
+                .qualified_names(&reference)
+                .unwrap(),
+            vec!["docker.io/library/fedora"]
+        );
+    }
+
+    #[test]
+    fn test_short_name_aliases() {
+        let aliases = parse_short_name_aliases(
+            "[aliases]\n\"fedora\" = \"registry.fedoraproject.org/fedora\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            aliases.get("fedora").map(String::as_str),
+            Some("registry.fedoraproject.org/fedora")
+        );
+
+        // Only short names can have aliases, which should be fully qualified.
+        assert!(
+            parse_short_name_aliases("[aliases]\n\"quay.io/foo\" = \"quay.io/bar\"\n").is_err()
+        );
+        assert!(parse_short_name_aliases("[aliases]\n\"foo\" = \"bar\"\n").is_err());
+        assert!(parse_short_name_aliases("[aliases]\n\"foo\" = \"quay.io/foo:latest\"\n").is_err());
+        assert!(RegistriesConfig::parse("[aliases]\n\"foo\" = \"foo/bar\"\n").is_err());
+    }
+
+    #[test]
+    fn test_conf_paths() {
+        let paths = conf_paths_from(
+            |name| match name {
+                "CONTAINERS_REGISTRIES_CONF" => Some("/tmp/registries.conf".into()),
+                _ => None,
+            },
+            Some(PathBuf::from("/home/user/.config")),
+            "CONTAINERS_REGISTRIES_CONF",
+            "registries.conf",
+            SYSTEM_REGISTRIES_CONF_PATH,
+        );
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("/tmp/registries.conf"),
+                PathBuf::from("/home/user/.config/containers/registries.conf"),
+                PathBuf::from("/etc/containers/registries.conf"),
+            ]
+        );
+
+        let paths = conf_paths_from(
+            |_| None,
+            None,
+            "CONTAINERS_SHORTNAMES_CONF",
+            "shortnames.conf",
+            SYSTEM_SHORTNAMES_CONF_PATH,
+        );
+        assert_eq!(paths, vec![PathBuf::from(SYSTEM_SHORTNAMES_CONF_PATH)]);
+    }
+
+    #[test]
+    fn test_load_user_config_first() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let missing = tempdir.path().join("missing.conf");
+        let user = tempdir.path().join("user-registries.conf");
+        let system = tempdir.path().join("system-registries.conf");
+        std::fs::write(&user, "unqualified-search-registries = [\"quay.io\"]\n").unwrap();
+        std::fs::write(
+            &system,
+            "unqualified-search-registries = [\"docker.io\"]\n\n[aliases]\n\"ubi\" = \"registry.access.redhat.com/ubi8/ubi\"\n",
+        )
+        .unwrap();
+        let shortnames = tempdir.path().join("shortnames.conf");
+        std::fs::write(
+            &shortnames,
+            "[aliases]\n\"fedora\" = \"registry.fedoraproject.org/fedora\"\n",
+        )
+        .unwrap();
+
+        let paths = vec![missing.clone(), user.clone(), system.clone()];
+        let config = RegistriesConfig::load_from(&paths, &[missing.clone(), shortnames]);
+        assert_eq!(config.unqualified_search_registries, vec!["quay.io"]);
+        assert!(!config.aliases.contains_key("ubi"));
+        assert_eq!(
+            config.aliases.get("fedora").map(String::as_str),
+            Some("registry.fedoraproject.org/fedora")
+        );
+
+        let config = RegistriesConfig::load_from(&[missing.clone(), system], &[missing]);
+        assert_eq!(config.unqualified_search_registries, vec!["docker.io"]);
+        assert!(config.aliases.contains_key("ubi"));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(RegistriesConfig::parse("[[registry]]\ninsecure = true\n").is_err());
+        assert!(RegistriesConfig::parse(
+            "[[registry]]\nprefix = \"*.example.com\"\nlocation = \"example.com\"\n"
+        )
+        .is_err());
+        assert!(RegistriesConfig::parse("[[registry]\n").is_err());
+    }
+}
@@ -0,0 +1,350 @@
+//! Retrying the requests to the Registries
+//!
+//! Requests failing due to transient errors (connection errors, timeouts and `408`, `500`, `502`,
+//! `503` and `504` responses) are retried as per the `RetryPolicy`. The delay between the
+//! attempts grows exponentially (with a random jitter), unless the Registry asks for a specific
+//! delay using the `Retry-After` header (not longer than the `max_backoff` though). Requests
+//! failing to connect are not retried if there are other endpoints (eg. mirrors) to try.
+//!
+//! Requests that are rate limited by the Registry (`429` responses) are retried after the delay
+//! asked for (or the backoff), as long as the total delay for a request is within the
+//! `rate_limit_budget` of the `RetryPolicy`. The quota reported by the Registry in the
+//! `RateLimit-Limit` and `RateLimit-Remaining` headers is available using `registry_rate_limit`.
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use hyper::http::{header::RETRY_AFTER, HeaderMap, StatusCode};
+use hyper::Error as HyperError;
+use lazy_static::lazy_static;
+
+use super::auth::normalize_registry;
+
+lazy_static! {
+    static ref DEFAULT_RETRY_POLICY: RwLock<RetryPolicy> = RwLock::new(RetryPolicy::default());
+    static ref RATE_LIMITS: Mutex<HashMap<String, RateLimit>> = Mutex::new(HashMap::new());
+}
+
+const RATELIMIT_LIMIT: &str = "RateLimit-Limit";
+
+const RATELIMIT_REMAINING: &str = "RateLimit-Remaining";
+
+/// Policy for retrying the requests to the Registries that fail due to transient errors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts for a request (including the first one). `1` disables the
+    /// retries.
+    pub max_attempts: u32,
+
+    /// Delay before the first retry, doubled for every subsequent retry.
+    pub initial_backoff: Duration,
+
+    /// Maximum delay between two attempts, including the one asked for using `Retry-After` (except
+    /// for the rate limited requests, see `rate_limit_budget`).
+    pub max_backoff: Duration,
+
+    /// Timeout for receiving the response for an attempt. `None` waits forever.
+    pub timeout: Option<Duration>,
+
+    /// Maximum total delay for a request that is rate limited by the Registry (`429`). A request
+    /// is not retried if the Registry asks for a longer delay. `Duration::ZERO` disables the
+    /// retries.
+    pub rate_limit_budget: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+            timeout: Some(Duration::from_secs(60)),
+            rate_limit_budget: Duration::from_secs(300),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns the delay before the attempt following the `attempt` (starting at 1).
+    ///
+    /// The delay is a random value between half and all of the exponential backoff.
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let backoff = self
+            .initial_backoff
+            .checked_mul(factor)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff);
+
+        let half = backoff / 2;
+        let jitter_nanos = (backoff - half).as_nanos() as u64;
+        if jitter_nanos == 0 {
+            return backoff;
+        }
+
+        half + Duration::from_nanos(random_u64() % (jitter_nanos + 1))
+    }
+}
+
+/// Sets the `RetryPolicy` for all the clients for the Registries created afterwards.
+pub fn set_retry_policy(policy: RetryPolicy) {
+    log::debug!("Setting Retry Policy: {:?}", policy);
+    *DEFAULT_RETRY_POLICY.write().unwrap() = policy;
+}
+
+/// Returns the current `RetryPolicy`.
+pub(crate) fn retry_policy() -> RetryPolicy {
+    DEFAULT_RETRY_POLICY.read().unwrap().clone()
+}
+
+/// Rate Limit (quota) of a Registry, as reported in the `RateLimit-Limit` and
+/// `RateLimit-Remaining` headers of it's latest response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Number of requests allowed in the `window`.
+    pub limit: u64,
+
+    /// Number of requests remaining in the current `window`.
+    pub remaining: u64,
+
+    /// Duration of the window, if reported (eg. `RateLimit-Limit: 100;w=21600`).
+    pub window: Option<Duration>,
+}
+
+/// Returns the latest Rate Limit reported by the `registry` (`host[:port]`), if any.
+///
+/// The Rate Limit is reported by some Registries only (eg. `docker.io`) and only for some of the
+/// requests (eg. for the manifests).
+///
+/// # Example:
+///
+/// ```rust,no_run
+/// # use intermodal_rs::image::api::registry_rate_limit;
+///
+/// if let Some(rate_limit) = registry_rate_limit("docker.io") {
+///     println!("Remaining: {} of {}", rate_limit.remaining, rate_limit.limit);
+/// }
+/// ```
+pub fn registry_rate_limit(registry: &str) -> Option<RateLimit> {
+    let registry = normalize_registry(registry);
+    RATE_LIMITS.lock().unwrap().get(&registry).cloned()
+}
+
+/// Saves the Rate Limit in the `headers` (if any) of a response from the `registry`.
+pub(crate) fn update_rate_limit(registry: &str, headers: &HeaderMap) {
+    if let Some(rate_limit) = rate_limit_from_headers(headers) {
+        log::trace!("Rate Limit for '{}': {:?}", registry, rate_limit);
+        RATE_LIMITS
+            .lock()
+            .unwrap()
+            .insert(registry.to_string(), rate_limit);
+    }
+}
+
+/// Returns the Rate Limit from the `RateLimit-Limit` and `RateLimit-Remaining` headers.
+///
+/// The headers are of the form `<quota>[;w=<window in seconds>]`, eg. `100;w=21600`.
+pub(crate) fn rate_limit_from_headers(headers: &HeaderMap) -> Option<RateLimit> {
+    let parse = |name: &str| -> Option<(u64, Option<Duration>)> {
+        let value = headers.get(name)?.to_str().ok()?;
+        let mut parts = value.split(';');
+        let quota = parts.next()?.trim().parse::<u64>().ok()?;
+        let window = parts.find_map(|param| match param.trim().split_once('=') {
+            Some(("w", w)) => w.trim().parse::<u64>().ok().map(Duration::from_secs),
+            _ => None,
+        });
+        Some((quota, window))
+    };
+
+    let (limit, window) = parse(RATELIMIT_LIMIT)?;
+    let (remaining, remaining_window) = parse(RATELIMIT_REMAINING)?;
+
+    Some(RateLimit {
+        limit,
+        remaining,
+        window: window.or(remaining_window),
+    })
+}
+
+/// Returns whether the request is to be retried for the response `status`.
+pub(crate) fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::REQUEST_TIMEOUT
+            | StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Returns whether the request is to be retried for the error `e`.
+pub(crate) fn is_transient_error(e: &HyperError) -> bool {
+    if e.is_connect() || e.is_timeout() || e.is_incomplete_message() || e.is_closed() {
+        return true;
+    }
+
+    // Connection resets etc. are reported as `io::Error` within the error.
+    let mut source = e.source();
+    while let Some(err) = source {
+        if let Some(io_err) = err.downcast_ref::<std::io::Error>() {
+            return matches!(
+                io_err.kind(),
+                std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::BrokenPipe
+                    | std::io::ErrorKind::TimedOut
+                    | std::io::ErrorKind::UnexpectedEof
+            );
+        }
+        source = err.source();
+    }
+
+    false
+}
+
+/// Returns the delay asked for by the `Retry-After` header (either seconds or an HTTP date).
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        date.with_timezone(&Utc)
+            .signed_duration_since(Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+// Returns a random number, good enough for the jitter.
+fn random_u64() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use hyper::http::HeaderValue;
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5),
+            timeout: None,
+            rate_limit_budget: Duration::ZERO,
+        };
+
+        for _ in 0..10 {
+            let delay = policy.backoff(1);
+            assert!(delay >= Duration::from_millis(500) && delay <= Duration::from_secs(1));
+
+            let delay = policy.backoff(3);
+            assert!(delay >= Duration::from_secs(2) && delay <= Duration::from_secs(4));
+
+            // Capped at the `max_backoff`.
+            let delay = policy.backoff(10);
+            assert!(delay >= Duration::from_millis(2500) && delay <= Duration::from_secs(5));
+        }
+
+        // Does not overflow.
+        assert!(policy.backoff(u32::MAX) <= policy.max_backoff);
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(120)));
+
+        // A date in the past.
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Sun, 06 Nov 1994 08:49:37 GMT"),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(0)));
+
+        let later = (Utc::now() + chrono::Duration::seconds(60)).to_rfc2822();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(&later).unwrap());
+        let delay = retry_after(&headers).unwrap();
+        assert!(delay > Duration::from_secs(50) && delay <= Duration::from_secs(60));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("soon"));
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[test]
+    fn test_rate_limit_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(rate_limit_from_headers(&headers), None);
+
+        headers.insert(RATELIMIT_LIMIT, HeaderValue::from_static("100;w=21600"));
+        headers.insert(RATELIMIT_REMAINING, HeaderValue::from_static("76;w=21600"));
+        assert_eq!(
+            rate_limit_from_headers(&headers),
+            Some(RateLimit {
+                limit: 100,
+                remaining: 76,
+                window: Some(Duration::from_secs(21600)),
+            })
+        );
+
+        headers.insert(RATELIMIT_LIMIT, HeaderValue::from_static("200"));
+        headers.insert(RATELIMIT_REMAINING, HeaderValue::from_static("0"));
+        assert_eq!(
+            rate_limit_from_headers(&headers),
+            Some(RateLimit {
+                limit: 200,
+                remaining: 0,
+                window: None,
+            })
+        );
+
+        headers.insert(RATELIMIT_REMAINING, HeaderValue::from_static("many"));
+        assert_eq!(rate_limit_from_headers(&headers), None);
+
+        update_rate_limit("ratelimit-test.example.com", &headers);
+        assert_eq!(registry_rate_limit("ratelimit-test.example.com"), None);
+
+        headers.insert(RATELIMIT_REMAINING, HeaderValue::from_static("10"));
+        update_rate_limit("ratelimit-test.example.com", &headers);
+        assert_eq!(
+            registry_rate_limit("https://ratelimit-test.example.com")
+                .unwrap()
+                .remaining,
+            10
+        );
+    }
+
+    #[test]
+    fn test_retryable_status() {
+        assert!(is_retryable_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(is_retryable_status(StatusCode::BAD_GATEWAY));
+        // Rate limited requests are retried within the budget (see `RetryPolicy`).
+        assert!(!is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_retryable_status(StatusCode::NOT_FOUND));
+        assert!(!is_retryable_status(StatusCode::UNAUTHORIZED));
+        assert!(!is_retryable_status(StatusCode::NOT_IMPLEMENTED));
+    }
+}
@@ -1,44 +1,125 @@
 //! Implementation of Docker specific ImageSource
 use std::collections::HashMap;
+use std::future::Future;
 
 use async_trait::async_trait;
+use futures_util::stream::{self, BoxStream, StreamExt, TryStreamExt};
 use tokio::io::AsyncRead;
 
 use crate::image::{
-    oci::digest::Digest,
-    types::{errors::ImageResult, ImageManifest, ImageReference, ImageSource},
+    oci::digest::{Digest, DigestVerifier},
+    oci::spec_v1::Descriptor,
+    types::{
+        errors::{ImageError, ImageResult},
+        ImageManifest, ImageReference, ImageSource,
+    },
 };
 
-use super::client::DockerClient;
+use super::client::{ClientError, DockerEndpoint};
+use super::errors::DockerImageError;
 use super::reference::types::DockerReference;
 
 /// DockerSource structure. This structure implements `ImageSource` trait.
+///
+/// The image is pulled from the first of the `endpoints` (mirrors followed by the registry) that
+/// has it.
 #[derive(Debug)]
 pub(crate) struct DockerSource {
     pub(crate) reference: DockerReference,
-    pub(super) client: DockerClient,
+    pub(super) endpoints: Vec<DockerEndpoint>,
     pub(crate) manifest_cache: HashMap<String, ImageManifest>,
 }
 
 impl DockerSource {
+    // Runs the `op` on the endpoints in order, until it succeeds. Returns the index of the
+    // endpoint it succeeded on, along with the result.
+    async fn try_endpoints<'a, T, F, Fut>(&'a self, op: F) -> Result<(usize, T), ClientError>
+    where
+        F: Fn(&'a DockerEndpoint) -> Fut,
+        Fut: Future<Output = Result<T, ClientError>>,
+    {
+        let mut last_err = None;
+        for (idx, endpoint) in self.endpoints.iter().enumerate() {
+            // An endpoint that can't be connected to is not retried, if there are others to try.
+            let is_last = idx + 1 == self.endpoints.len();
+            endpoint.client.set_retry_connect_errors(is_last);
+
+            match op(endpoint).await {
+                Ok(result) => return Ok((idx, result)),
+                Err(e) => {
+                    if idx + 1 < self.endpoints.len() {
+                        log::warn!("Trying the next endpoint after: {}", e);
+                    }
+                    last_err = Some(e);
+                }
+            }
+        }
+
+        // There's always at least one endpoint (the registry itself).
+        Err(last_err.expect("No endpoints to pull from."))
+    }
+
+    // Returns the `digest` (if specified), else the digest or the tag of the reference.
+    fn digest_or_tag(&self, digest: Option<&Digest>) -> String {
+        if let Some(digest) = digest {
+            digest.to_string()
+        } else if let Some(ref_digest) = &self.reference.digest {
+            let s = ref_digest.to_string();
+            log::trace!("Reference Digest Found {}", &s);
+            s
+        } else {
+            log::trace!(
+                "Empty Reference Digest. Using the Tag (default or specified) to get the manifest!"
+            );
+            self.reference.tag.clone()
+        }
+    }
+
+    /// Returns the digest of the manifest for the reference, without downloading the manifest
+    /// (if the Registry supports it), along with the name of the repository (`domain/path`) it is
+    /// resolved from (eg. a mirror or a registry from the search registries).
+    pub(crate) async fn resolve_digest(&self) -> ImageResult<(String, Digest)> {
+        let digest_or_tag = self.digest_or_tag(None);
+
+        let (idx, digest) = self
+            .try_endpoints(|endpoint| {
+                endpoint
+                    .client
+                    .do_resolve_digest(&endpoint.path, &digest_or_tag)
+            })
+            .await?;
+
+        Ok((self.endpoints[idx].name(), digest))
+    }
+
+    /// Returns the descriptors of the manifests referring to the image (eg. SBOMs and
+    /// signatures), of the `artifact_type` only (if specified).
+    pub(crate) async fn get_referrers(
+        &self,
+        artifact_type: Option<&str>,
+    ) -> ImageResult<Vec<Descriptor>> {
+        // A pinned reference need not be resolved.
+        let digest = match &self.reference.digest {
+            Some(digest) => digest.clone(),
+            None => self.resolve_digest().await?.1,
+        };
+
+        let (_, referrers) = self
+            .try_endpoints(|endpoint| {
+                endpoint
+                    .client
+                    .do_get_referrers(&endpoint.path, &digest, artifact_type)
+            })
+            .await?;
+
+        Ok(referrers)
+    }
+
     async fn cached_or_fetch_manifest(
         &mut self,
         digest: Option<&Digest>,
     ) -> ImageResult<ImageManifest> {
-        let digest_or_tag = if digest.is_none() {
-            if self.reference.digest.is_none() {
-                log::trace!(
-                    "Empty Reference Digest. Using the Tag (default or specified) to get the manifest!"
-                );
-                self.reference.tag.clone()
-            } else {
-                let s = self.reference.digest.as_ref().unwrap().to_string();
-                log::trace!("Reference Digest Found {}", &s);
-                s
-            }
-        } else {
-            digest.unwrap().to_string()
-        };
+        let digest_or_tag = self.digest_or_tag(digest);
 
         if self.manifest_cache.contains_key(&digest_or_tag) {
             log::trace!("Cached Manifest found: Returning Cached!");
@@ -46,11 +127,35 @@ impl DockerSource {
         }
 
         log::trace!("Downloading Manifest!");
-        let manifest = self
-            .client
-            .do_get_manifest(self.reference.path(), &digest_or_tag)
+        let (idx, (manifest, content_digest)) = self
+            .try_endpoints(|endpoint| {
+                endpoint
+                    .client
+                    .do_get_manifest(&endpoint.path, &digest_or_tag)
+            })
             .await?;
 
+        // A pinned manifest should match the digest it was requested with and any manifest should
+        // match the digest the Registry claims it to have.
+        if let Ok(requested_digest) = digest_or_tag.parse::<Digest>() {
+            verify_manifest_digest(&manifest.manifest, &requested_digest, "Requested")?;
+        }
+        if let Some(content_digest) = content_digest {
+            verify_manifest_digest(&manifest.manifest, &content_digest, "Docker-Content-Digest")?;
+        }
+
+        // Report where a short name was found, since it could be any of the search registries.
+        if let (Some(short_name), None) = (&self.reference.short_name, digest) {
+            log::info!(
+                "Resolved '{}' to '{}'.",
+                short_name,
+                self.endpoints[idx].name()
+            );
+        }
+
+        // The blobs for the manifest are likely to be found at the same endpoint.
+        self.endpoints[..=idx].rotate_right(1);
+
         log::trace!(
             "Got Manifest: {:#?}",
             std::str::from_utf8(&manifest.manifest).unwrap()
@@ -76,14 +181,91 @@ impl ImageSource for DockerSource {
         &self,
         digest: &Digest,
     ) -> ImageResult<Box<dyn AsyncRead + Unpin + Send + Sync>> {
-        Ok(self
-            .client
-            .do_get_blob(self.reference.path(), digest)
-            .await?)
+        let (_, blob) = self
+            .try_endpoints(|endpoint| endpoint.client.do_get_blob(&endpoint.path, digest))
+            .await?;
+
+        Ok(blob)
     }
 
     async fn get_repo_tags(&self) -> ImageResult<Vec<String>> {
         log::debug!("ImageSource.get_repo_tags");
-        Ok(self.client.do_get_repo_tags(self.reference.path()).await?)
+        let (_, tags) = self
+            .try_endpoints(|endpoint| endpoint.client.do_get_repo_tags(&endpoint.path))
+            .await?;
+
+        Ok(tags)
+    }
+
+    fn get_repo_tags_stream<'a>(
+        &'a self,
+        page_size: Option<usize>,
+        last: Option<&'a str>,
+    ) -> BoxStream<'a, ImageResult<String>> {
+        log::debug!("ImageSource.get_repo_tags_stream");
+
+        // The first page is fetched from the first endpoint that has it and the following pages
+        // from the same endpoint.
+        let pages = stream::try_unfold(TagsPage::First, move |page| async move {
+            let (idx, (tags, next_url)) = match page {
+                TagsPage::First => {
+                    self.try_endpoints(|endpoint| {
+                        let url = endpoint
+                            .client
+                            .tags_list_url(&endpoint.path, page_size, last);
+                        async move {
+                            endpoint
+                                .client
+                                .do_get_repo_tags_page(&endpoint.path, &url)
+                                .await
+                        }
+                    })
+                    .await?
+                }
+                TagsPage::Next(idx, url) => {
+                    let endpoint = &self.endpoints[idx];
+                    let page = endpoint
+                        .client
+                        .do_get_repo_tags_page(&endpoint.path, &url)
+                        .await?;
+                    (idx, page)
+                }
+                TagsPage::Done => return Ok(None),
+            };
+
+            let next_page = match next_url {
+                Some(url) => TagsPage::Next(idx, url),
+                None => TagsPage::Done,
+            };
+            Ok::<_, ImageError>(Some((tags, next_page)))
+        });
+
+        pages
+            .map_ok(|tags| stream::iter(tags.into_iter().map(Ok)))
+            .try_flatten()
+            .boxed()
     }
 }
+
+// Verifies the `manifest` against the `expected` digest (`kind` of the digest is for the error).
+fn verify_manifest_digest(
+    manifest: &[u8],
+    expected: &Digest,
+    kind: &str,
+) -> Result<(), DockerImageError> {
+    let mut verifier = DigestVerifier::new(expected)
+        .map_err(|e| DockerImageError::ManifestDigestMismatch(format!("{}: {}", kind, e)))?;
+    verifier.update(manifest);
+
+    verifier.verify().map_err(|e| {
+        log::error!("Manifest does not match the {} Digest: {}", kind, e);
+        DockerImageError::ManifestDigestMismatch(format!("{}: {}", kind, e))
+    })
+}
+
+// State of the paginated tags list.
+enum TagsPage {
+    First,
+    Next(usize, String),
+    Done,
+}
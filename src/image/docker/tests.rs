@@ -1,22 +1,28 @@
 use env_logger::Env;
 use wiremock::{
-    matchers::{method, path},
+    matchers::{body_bytes, header, method, path, path_regex, query_param},
     Mock, MockServer, ResponseTemplate,
 };
 
 use super::{
+    blob_locations::{blob_locations, record_blob_location},
     testdata::{DOCKER_IMAGE_CONFIG_BLOB, DOCKER_IMAGE_MANIFEST_BLOB, DOCKER_LIST_MANIFEST_BLOB},
     MEDIA_TYPE_DOCKER_V2_LIST, MEDIA_TYPE_DOCKER_V2_SCHEMA2_MANIFEST,
 };
 use crate::image::{
+    api::{copy_image, delete_image, list_referrers, resolve_digest, CopyOptions},
+    docker::{reference::api::parse, registries::RegistriesConfig},
+    oci::{digest::Digest, spec_v1::MEDIA_TYPE_IMAGE_INDEX, testdata::create_test_image_layout},
     transports,
-    types::{errors::ImageError, ImageReference},
+    types::{errors::ImageError, ImageManifest, ImageReference, ImageSource},
 };
 
 fn init() {
     let _ = env_logger::Builder::from_env(Env::default().default_filter_or("trace"))
         .is_test(true)
         .try_init();
+
+    transports::init_transports();
 }
 
 async fn setup_mock_docker_api_server() -> MockServer {
@@ -41,7 +47,7 @@ async fn setup_mock_docker_api_server() -> MockServer {
         MEDIA_TYPE_DOCKER_V2_SCHEMA2_MANIFEST,
     );
     let mock_blob_manifest = Mock::given(method("GET"))
-        .and(path("/v2/library/fedora/manifests/sha256:fdf235fa167d2aa5d820fba274ec1d2edeb0534bd32d28d602a19b31bad79b80"))
+        .and(path("/v2/library/fedora/manifests/sha256:11216ef546b5bb072f662a8194bff719a954169c2cac3794489391ac5016c655"))
         .respond_with(mock_blob_response);
     mock_server.register(mock_blob_manifest).await;
 
@@ -65,6 +71,7 @@ fn create_mock_reference<'a>(
 
 #[tokio::test]
 async fn test_new_image() {
+    init();
     let server = setup_mock_docker_api_server().await;
     let image_name = format!("docker://{}/library/fedora", server.address());
 
@@ -163,3 +170,840 @@ async fn test_get_image_inspect() {
     assert_eq!(inspect.architecture, "amd64");
     assert_eq!(inspect.os, "linux");
 }
+
+async fn setup_mock_docker_push_server() -> MockServer {
+    let mock_server = MockServer::start().await;
+
+    let mock_ping = Mock::given(method("GET"))
+        .and(path("/v2/"))
+        .respond_with(ResponseTemplate::new(200));
+    mock_server.register(mock_ping).await;
+
+    let config_digest = Digest::from_bytes(DOCKER_IMAGE_CONFIG_BLOB.as_bytes());
+
+    let mock_blob_exists = Mock::given(method("HEAD"))
+        .and(path(format!("/v2/library/fedora/blobs/{}", config_digest)))
+        .respond_with(ResponseTemplate::new(200));
+    mock_server.register(mock_blob_exists).await;
+
+    let mock_blob_missing = Mock::given(method("HEAD")).respond_with(ResponseTemplate::new(404));
+    mock_server.register(mock_blob_missing).await;
+
+    let mock_start_upload = Mock::given(method("POST"))
+        .and(path("/v2/library/fedora/blobs/uploads/"))
+        .respond_with(
+            ResponseTemplate::new(202)
+                .insert_header("Location", "/v2/library/fedora/blobs/uploads/uuid-1"),
+        );
+    mock_server.register(mock_start_upload).await;
+
+    let mock_upload = Mock::given(method("PATCH"))
+        .and(path("/v2/library/fedora/blobs/uploads/uuid-1"))
+        .and(body_bytes(DOCKER_IMAGE_CONFIG_BLOB.as_bytes()))
+        .respond_with(ResponseTemplate::new(202).insert_header(
+            "Location",
+            "/v2/library/fedora/blobs/uploads/uuid-1?_state=1",
+        ));
+    mock_server.register(mock_upload).await;
+
+    let mock_complete_upload = Mock::given(method("PUT"))
+        .and(path("/v2/library/fedora/blobs/uploads/uuid-1"))
+        .and(query_param("_state", "1"))
+        .and(query_param("digest", config_digest.to_string()))
+        .respond_with(ResponseTemplate::new(201));
+    mock_server.register(mock_complete_upload).await;
+
+    let mock_put_manifest = Mock::given(method("PUT"))
+        .and(path("/v2/library/fedora/manifests/latest"))
+        .and(header(
+            "Content-Type",
+            MEDIA_TYPE_DOCKER_V2_SCHEMA2_MANIFEST,
+        ))
+        .and(body_bytes(DOCKER_IMAGE_MANIFEST_BLOB.as_bytes()))
+        .respond_with(ResponseTemplate::new(201));
+    mock_server.register(mock_put_manifest).await;
+
+    mock_server
+}
+
+#[tokio::test]
+async fn test_has_blob() {
+    init();
+    let mock_server = setup_mock_docker_push_server().await;
+
+    let image_name = format!("docker://{}/library/fedora", mock_server.address());
+    let mock_ref = create_mock_reference(&image_name).unwrap();
+
+    let dest = mock_ref.new_image_destination();
+    assert!(dest.is_ok(), "{:?}", dest);
+    let dest = dest.unwrap();
+    assert!(dest.supports_digests());
+
+    let config_digest = Digest::from_bytes(DOCKER_IMAGE_CONFIG_BLOB.as_bytes());
+    let result = dest.has_blob(&config_digest).await;
+    assert!(result.is_ok(), "{:?}", result);
+    assert!(result.unwrap());
+
+    let other_digest = Digest::from_bytes(b"not uploaded");
+    let result = dest.has_blob(&other_digest).await;
+    assert!(result.is_ok(), "{:?}", result);
+    assert!(!result.unwrap());
+}
+
+#[tokio::test]
+async fn test_put_blob_and_manifest() {
+    init();
+    let mock_server = setup_mock_docker_push_server().await;
+
+    let image_name = format!("docker://{}/library/fedora", mock_server.address());
+    let mock_ref = create_mock_reference(&image_name).unwrap();
+
+    let mut dest = mock_ref.new_image_destination().unwrap();
+
+    let config = DOCKER_IMAGE_CONFIG_BLOB.as_bytes();
+    let config_digest = Digest::from_bytes(config);
+    let result = dest
+        .put_blob(
+            Box::new(std::io::Cursor::new(config.to_vec())),
+            &config_digest,
+            Some(config.len() as u64),
+        )
+        .await;
+    assert!(result.is_ok(), "{:?}", result);
+
+    let manifest = ImageManifest {
+        manifest: DOCKER_IMAGE_MANIFEST_BLOB.as_bytes().to_vec(),
+        mime_type: MEDIA_TYPE_DOCKER_V2_SCHEMA2_MANIFEST.to_string(),
+    };
+    let result = dest.put_manifest(&manifest, None).await;
+    assert!(result.is_ok(), "{:?}", result);
+
+    assert!(dest.commit().await.is_ok());
+}
+
+#[tokio::test]
+async fn test_put_blob_upload_failure() {
+    init();
+    let mock_server = MockServer::start().await;
+
+    let mock_ping = Mock::given(method("GET"))
+        .and(path("/v2/"))
+        .respond_with(ResponseTemplate::new(200));
+    mock_server.register(mock_ping).await;
+
+    let mock_start_upload = Mock::given(method("POST"))
+        .and(path("/v2/library/fedora/blobs/uploads/"))
+        .respond_with(ResponseTemplate::new(403));
+    mock_server.register(mock_start_upload).await;
+
+    let image_name = format!("docker://{}/library/fedora", mock_server.address());
+    let mock_ref = create_mock_reference(&image_name).unwrap();
+    let dest = mock_ref.new_image_destination().unwrap();
+
+    let result = dest
+        .put_blob(
+            Box::new(std::io::Cursor::new(b"blob".to_vec())),
+            &Digest::from_bytes(b"blob"),
+            None,
+        )
+        .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_put_blob_invalid_location() {
+    init();
+    let mock_server = MockServer::start().await;
+
+    let mock_ping = Mock::given(method("GET"))
+        .and(path("/v2/"))
+        .respond_with(ResponseTemplate::new(200));
+    mock_server.register(mock_ping).await;
+
+    let mock_start_upload = Mock::given(method("POST"))
+        .and(path("/v2/library/fedora/blobs/uploads/"))
+        .respond_with(
+            ResponseTemplate::new(202)
+                .insert_header("Location", "/v2/library/fedora/blobs/uploads/bad uuid"),
+        );
+    mock_server.register(mock_start_upload).await;
+
+    let image_name = format!("docker://{}/library/fedora", mock_server.address());
+    let mock_ref = create_mock_reference(&image_name).unwrap();
+    let dest = mock_ref.new_image_destination().unwrap();
+
+    let result = dest
+        .put_blob(
+            Box::new(std::io::Cursor::new(b"blob".to_vec())),
+            &Digest::from_bytes(b"blob"),
+            None,
+        )
+        .await;
+    assert!(result.is_err());
+}
+
+// Sets up a mock Registry that mounts the blob from `library/base` to `library/fedora` if
+// `mounted`, else declines to and expects the blob to be uploaded to the session started instead.
+async fn setup_mock_docker_mount_server(blob: &[u8], mounted: bool) -> MockServer {
+    let mock_server = MockServer::start().await;
+    let digest = Digest::from_bytes(blob);
+
+    let mock_ping = Mock::given(method("GET"))
+        .and(path("/v2/"))
+        .respond_with(ResponseTemplate::new(200));
+    mock_server.register(mock_ping).await;
+
+    let mount_response = if mounted {
+        ResponseTemplate::new(201)
+    } else {
+        ResponseTemplate::new(202)
+            .insert_header("Location", "/v2/library/fedora/blobs/uploads/uuid-2")
+    };
+    let mock_mount = Mock::given(method("POST"))
+        .and(path("/v2/library/fedora/blobs/uploads/"))
+        .and(query_param("mount", digest.to_string()))
+        .and(query_param("from", "library/base"))
+        .respond_with(mount_response)
+        .with_priority(1)
+        .expect(1);
+    mock_server.register(mock_mount).await;
+
+    // No other upload sessions should be started.
+    let mock_start_upload = Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(0);
+    mock_server.register(mock_start_upload).await;
+
+    let mock_upload = Mock::given(method("PATCH"))
+        .and(path("/v2/library/fedora/blobs/uploads/uuid-2"))
+        .and(body_bytes(blob))
+        .respond_with(ResponseTemplate::new(202).insert_header(
+            "Location",
+            "/v2/library/fedora/blobs/uploads/uuid-2?_state=2",
+        ))
+        .expect(if mounted { 0 } else { 1 });
+    mock_server.register(mock_upload).await;
+
+    let mock_complete_upload = Mock::given(method("PUT"))
+        .and(path("/v2/library/fedora/blobs/uploads/uuid-2"))
+        .and(query_param("digest", digest.to_string()))
+        .respond_with(ResponseTemplate::new(201))
+        .expect(if mounted { 0 } else { 1 });
+    mock_server.register(mock_complete_upload).await;
+
+    mock_server
+}
+
+#[tokio::test]
+async fn test_put_blob_mounts_from_known_repository() {
+    init();
+
+    for mounted in [true, false] {
+        // Blob unique to the test, so that no other locations are known for it.
+        let tempdir = tempfile::tempdir().unwrap();
+        let blob = format!("blob-{}-{}", tempdir.path().display(), mounted).into_bytes();
+        let digest = Digest::from_bytes(&blob);
+
+        let mock_server = setup_mock_docker_mount_server(&blob, mounted).await;
+        let registry = mock_server.address().to_string();
+        record_blob_location(&digest, &registry, "library/base").await;
+
+        let image_name = format!("docker://{}/library/fedora", registry);
+        let mock_ref = create_mock_reference(&image_name).unwrap();
+        let dest = mock_ref.new_image_destination().unwrap();
+
+        let result = dest
+            .put_blob(
+                Box::new(std::io::Cursor::new(blob.clone())),
+                &digest,
+                Some(blob.len() as u64),
+            )
+            .await;
+        assert!(result.is_ok(), "mounted: {}: {:?}", mounted, result);
+
+        assert_eq!(
+            blob_locations(&digest, &registry),
+            vec!["library/fedora", "library/base"]
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_copy_skips_existing_blobs() {
+    init();
+    let mock_server = MockServer::start().await;
+
+    let mock_ping = Mock::given(method("GET"))
+        .and(path("/v2/"))
+        .respond_with(ResponseTemplate::new(200));
+    mock_server.register(mock_ping).await;
+
+    // All the blobs exist, so no uploads should be started.
+    let mock_blob_exists = Mock::given(method("HEAD")).respond_with(ResponseTemplate::new(200));
+    mock_server.register(mock_blob_exists).await;
+
+    let mock_start_upload = Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(0);
+    mock_server.register(mock_start_upload).await;
+
+    let mock_put_manifest = Mock::given(method("PUT"))
+        .and(path("/v2/library/fedora/manifests/copied"))
+        .respond_with(ResponseTemplate::new(201))
+        .expect(1);
+    mock_server.register(mock_put_manifest).await;
+
+    let tempdir = tempfile::tempdir().unwrap();
+    let layout_path = create_test_image_layout(tempdir.path()).await;
+
+    let src_reference = format!("oci:{}", layout_path.display());
+    let dst_reference = format!("docker://{}/library/fedora:copied", mock_server.address());
+
+    let result = copy_image(&src_reference, &dst_reference, &CopyOptions::default()).await;
+    assert!(result.is_ok(), "{:?}", result);
+}
+
+#[tokio::test]
+async fn test_pull_from_mirrors_in_order() {
+    init();
+    let registry_server = setup_mock_docker_api_server().await;
+
+    // A mirror without the image, followed by an unreachable mirror.
+    let mirror_server = MockServer::start().await;
+    let mock_ping = Mock::given(method("GET"))
+        .and(path("/v2/"))
+        .respond_with(ResponseTemplate::new(200));
+    mirror_server.register(mock_ping).await;
+
+    // Once the manifest is found at the registry, the blobs are pulled from it.
+    let mock_no_blobs = Mock::given(method("GET"))
+        .and(path_regex("^/v2/library/fedora/blobs/"))
+        .respond_with(ResponseTemplate::new(404))
+        .expect(0);
+    mirror_server.register(mock_no_blobs).await;
+
+    let config = RegistriesConfig::parse(&format!(
+        r#"
+[[registry]]
+prefix = "registry.example.com"
+location = "{}"
+insecure = true
+
+[[registry.mirror]]
+location = "{}"
+insecure = true
+
+[[registry.mirror]]
+location = "127.0.0.1:1"
+insecure = true
+"#,
+        registry_server.address(),
+        mirror_server.address()
+    ))
+    .unwrap();
+
+    let reference = parse("registry.example.com/library/fedora").unwrap();
+    let mut source = reference.new_docker_source(&config).unwrap();
+    assert_eq!(source.endpoints.len(), 3);
+
+    // The unreachable mirror is not retried before moving on to the Registry.
+    let start = std::time::Instant::now();
+    let manifest = source.get_manifest(None).await;
+    assert!(manifest.is_ok(), "{:?}", manifest);
+    assert!(start.elapsed() < std::time::Duration::from_millis(1500));
+
+    let digest = Digest::new_from_str(
+        "sha256:a78267678b7e6e849c7e960b09227b737a38d5073a5071b041a16bd4b609ef92",
+    )
+    .unwrap();
+    let blob = source.get_blob(&digest).await;
+    assert!(blob.is_ok(), "{:?}", blob.err());
+}
+
+#[tokio::test]
+async fn test_secure_registry_from_config() {
+    init();
+    let registry_server = setup_mock_docker_api_server().await;
+
+    // The mock server is not serving `https`, although the port would make it `http`.
+    let config = RegistriesConfig::parse(&format!(
+        "[[registry]]\nlocation = \"{}\"\ninsecure = false\n",
+        registry_server.address()
+    ))
+    .unwrap();
+
+    let image_name = format!("{}/library/fedora", registry_server.address());
+    let reference = parse(&image_name).unwrap();
+    let mut source = reference.new_docker_source(&config).unwrap();
+    assert!(source.get_manifest(None).await.is_err());
+
+    let config = RegistriesConfig::parse(&format!(
+        "[[registry]]\nlocation = \"{}\"\nblocked = true\n",
+        registry_server.address()
+    ))
+    .unwrap();
+    assert!(reference.new_docker_source(&config).is_err());
+    assert!(reference.new_docker_destination(&config).is_err());
+}
+
+#[tokio::test]
+async fn test_pull_short_name() {
+    init();
+
+    // The image is not found at the first of the search registries.
+    let empty_server = MockServer::start().await;
+    let registry_server = setup_mock_docker_api_server().await;
+
+    let config = RegistriesConfig::parse(&format!(
+        r#"
+unqualified-search-registries = ["{0}", "{1}"]
+
+[[registry]]
+location = "{0}"
+insecure = true
+
+[[registry]]
+location = "{1}"
+insecure = true
+"#,
+        empty_server.address(),
+        registry_server.address()
+    ))
+    .unwrap();
+
+    let reference = parse("library/fedora").unwrap();
+    let mut source = reference.new_docker_source(&config).unwrap();
+    assert_eq!(source.endpoints.len(), 2);
+
+    // The name is of the registry the digest is resolved from.
+    let head_digest = Digest::from_bytes(DOCKER_LIST_MANIFEST_BLOB.as_bytes());
+    Mock::given(method("HEAD"))
+        .and(path("/v2/library/fedora/manifests/latest"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("Docker-Content-Digest", head_digest.to_string().as_str()),
+        )
+        .mount(&registry_server)
+        .await;
+    let resolved = source.resolve_digest().await;
+    assert!(resolved.is_ok(), "{:?}", resolved);
+    assert_eq!(
+        resolved.unwrap(),
+        (
+            format!("{}/library/fedora", registry_server.address()),
+            head_digest
+        )
+    );
+
+    let manifest = source.get_manifest(None).await;
+    assert!(manifest.is_ok(), "{:?}", manifest);
+
+    // The registry that has the image is tried first afterwards.
+    assert_eq!(
+        source.endpoints[0].name(),
+        format!("{}/library/fedora", registry_server.address())
+    );
+}
+
+#[tokio::test]
+async fn test_get_repo_tags_paginated() {
+    use futures_util::TryStreamExt;
+
+    init();
+    let mock_server = setup_mock_docker_api_server().await;
+
+    // Both relative and absolute URLs in the 'Link' header should be followed.
+    let pages = [
+        (
+            None,
+            vec!["33", "34"],
+            Some("</v2/library/fedora/tags/list?n=2&last=34>; rel=\"next\"".to_string()),
+        ),
+        (
+            Some("34"),
+            vec!["35", "36"],
+            Some(format!(
+                "<{}/v2/library/fedora/tags/list?n=2&last=36>; rel=\"next\"",
+                mock_server.uri()
+            )),
+        ),
+        (Some("36"), vec!["latest"], None),
+    ];
+    for (last, tags, link) in pages {
+        let body = serde_json::json!({"name": "library/fedora", "tags": tags});
+        let mut response = ResponseTemplate::new(200).set_body_json(body);
+        if let Some(link) = link {
+            response = response.insert_header("Link", link.as_str());
+        }
+
+        let mock = Mock::given(method("GET")).and(path("/v2/library/fedora/tags/list"));
+        let mock = match last {
+            Some(last) => mock
+                .and(query_param("last", last))
+                .respond_with(response)
+                .with_priority(1),
+            None => mock.respond_with(response),
+        };
+        // The pages after "34" are streamed again, starting from "34".
+        let expected_requests = if last.is_some() { 3 } else { 2 };
+        mock_server.register(mock.expect(expected_requests)).await;
+    }
+
+    let image_name = format!("docker://{}/library/fedora", mock_server.address());
+    let source = create_mock_reference(&image_name)
+        .unwrap()
+        .new_image_source()
+        .unwrap();
+
+    let expected = vec!["33", "34", "35", "36", "latest"];
+
+    let tags = source.get_repo_tags().await;
+    assert!(tags.is_ok(), "{:?}", tags.err());
+    assert_eq!(tags.unwrap(), expected);
+
+    let tags: Result<Vec<String>, _> = source.get_repo_tags_stream(None, None).try_collect().await;
+    assert!(tags.is_ok(), "{:?}", tags.err());
+    assert_eq!(tags.unwrap(), expected);
+
+    let tags: Result<Vec<String>, _> = source
+        .get_repo_tags_stream(Some(2), Some("34"))
+        .try_collect()
+        .await;
+    assert!(tags.is_ok(), "{:?}", tags.err());
+    assert_eq!(tags.unwrap(), &expected[2..]);
+}
+
+#[tokio::test]
+async fn test_resolve_digest() {
+    init();
+    let head_digest = "sha256:fdf235fa167d2aa5d820fba274ec1d2edeb0534bd32d28d602a19b31bad79b80";
+
+    let mock_server = setup_mock_docker_api_server().await;
+    Mock::given(method("HEAD"))
+        .and(path("/v2/library/fedora/manifests/latest"))
+        .respond_with(
+            ResponseTemplate::new(200).insert_header("Docker-Content-Digest", head_digest),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    // The manifest should not be downloaded.
+    Mock::given(method("GET"))
+        .and(path("/v2/library/fedora/manifests/latest"))
+        .respond_with(ResponseTemplate::new(500))
+        .with_priority(1)
+        .expect(0)
+        .mount(&mock_server)
+        .await;
+
+    let reference = format!("docker://{}/library/fedora", mock_server.address());
+    let digest = resolve_digest(&reference).await;
+    assert!(digest.is_ok(), "{:?}", digest);
+    let (name, digest) = digest.unwrap();
+    assert_eq!(name, format!("{}/library/fedora", mock_server.address()));
+    assert_eq!(digest.to_string(), head_digest);
+}
+
+#[tokio::test]
+async fn test_resolve_digest_without_header() {
+    init();
+
+    // 'HEAD' is not supported, the manifest is downloaded instead.
+    let mock_server = setup_mock_docker_api_server().await;
+    Mock::given(method("HEAD"))
+        .and(path("/v2/library/fedora/manifests/latest"))
+        .respond_with(ResponseTemplate::new(405))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let reference = format!("docker://{}/library/fedora", mock_server.address());
+    let digest = resolve_digest(&reference).await;
+    assert!(digest.is_ok(), "{:?}", digest);
+    assert_eq!(
+        digest.unwrap().1,
+        Digest::from_bytes(DOCKER_LIST_MANIFEST_BLOB.as_bytes())
+    );
+
+    assert!(resolve_digest("oci:/path/to/layout").await.is_err());
+}
+
+#[tokio::test]
+async fn test_pinned_manifest_digest_mismatch() {
+    init();
+    let mock_server = setup_mock_docker_api_server().await;
+
+    // The Registry returns a different manifest for the digest.
+    let pinned_digest = Digest::from_bytes(b"some other manifest");
+    Mock::given(method("GET"))
+        .and(path(format!(
+            "/v2/library/fedora/manifests/{}",
+            pinned_digest
+        )))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            DOCKER_IMAGE_MANIFEST_BLOB.as_bytes().to_owned(),
+            MEDIA_TYPE_DOCKER_V2_SCHEMA2_MANIFEST,
+        ))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let image_name = format!(
+        "docker://{}/library/fedora@{}",
+        mock_server.address(),
+        pinned_digest
+    );
+    let mut source = create_mock_reference(&image_name)
+        .unwrap()
+        .new_image_source()
+        .unwrap();
+
+    let manifest = source.get_manifest(None).await;
+    assert!(manifest.is_err());
+    assert!(manifest
+        .err()
+        .unwrap()
+        .to_string()
+        .contains("Manifest Digest Mismatch"),);
+}
+
+#[tokio::test]
+async fn test_content_digest_mismatch() {
+    init();
+    let mock_server = setup_mock_docker_api_server().await;
+
+    let manifest_digest = Digest::from_bytes(DOCKER_IMAGE_MANIFEST_BLOB.as_bytes());
+    for (tag, content_digest) in [
+        ("valid", manifest_digest),
+        ("tampered", Digest::from_bytes(b"some other manifest")),
+    ] {
+        Mock::given(method("GET"))
+            .and(path(format!("/v2/library/fedora/manifests/{}", tag)))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Docker-Content-Digest", content_digest.to_string().as_str())
+                    .set_body_raw(
+                        DOCKER_IMAGE_MANIFEST_BLOB.as_bytes().to_owned(),
+                        MEDIA_TYPE_DOCKER_V2_SCHEMA2_MANIFEST,
+                    ),
+            )
+            .mount(&mock_server)
+            .await;
+    }
+
+    for (tag, valid) in [("valid", true), ("tampered", false)] {
+        let image_name = format!("docker://{}/library/fedora:{}", mock_server.address(), tag);
+        let mut source = create_mock_reference(&image_name)
+            .unwrap()
+            .new_image_source()
+            .unwrap();
+
+        let manifest = source.get_manifest(None).await;
+        assert_eq!(manifest.is_ok(), valid, "{}: {:?}", tag, manifest);
+    }
+}
+
+#[tokio::test]
+async fn test_delete_image() {
+    init();
+    let mock_server = setup_mock_docker_api_server().await;
+
+    let manifest_digest = Digest::from_bytes(DOCKER_IMAGE_MANIFEST_BLOB.as_bytes());
+    Mock::given(method("HEAD"))
+        .and(path("/v2/library/fedora/manifests/ci-123"))
+        .respond_with(ResponseTemplate::new(200).insert_header(
+            "Docker-Content-Digest",
+            manifest_digest.to_string().as_str(),
+        ))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("DELETE"))
+        .and(path(format!(
+            "/v2/library/fedora/manifests/{}",
+            manifest_digest
+        )))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let reference = format!("docker://{}/library/fedora:ci-123", mock_server.address());
+    let digest = delete_image(&reference).await;
+    assert!(digest.is_ok(), "{:?}", digest);
+    assert_eq!(digest.unwrap(), manifest_digest);
+}
+
+#[tokio::test]
+async fn test_delete_image_disabled() {
+    init();
+    let mock_server = setup_mock_docker_api_server().await;
+
+    let manifest_digest = Digest::from_bytes(DOCKER_IMAGE_MANIFEST_BLOB.as_bytes());
+    Mock::given(method("DELETE"))
+        .and(path(format!(
+            "/v2/library/fedora/manifests/{}",
+            manifest_digest
+        )))
+        .respond_with(ResponseTemplate::new(405))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    // A pinned reference is not resolved.
+    let reference = format!(
+        "docker://{}/library/fedora@{}",
+        mock_server.address(),
+        manifest_digest
+    );
+    let result = delete_image(&reference).await;
+    assert!(result.is_err());
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("Deletion is disabled on the Registry"),);
+}
+
+fn referrers_index() -> String {
+    format!(
+        r#"{{
+  "schemaVersion": 2,
+  "mediaType": "{}",
+  "manifests": [
+    {{
+      "mediaType": "application/vnd.oci.image.manifest.v1+json",
+      "size": 1024,
+      "digest": "sha256:9834876dcfb05cb167a5c24953eba58c4ac89b1adf57f28f2f9d09af107ee8f0",
+      "artifactType": "application/spdx+json"
+    }},
+    {{
+      "mediaType": "application/vnd.oci.image.manifest.v1+json",
+      "size": 512,
+      "digest": "sha256:3c3a4604a545cdc127456d94e421cd355bca5b528f4a9c1905b15da2eb4a4c6b",
+      "artifactType": "application/vnd.dev.cosign.artifact.sig.v1+json"
+    }}
+  ]
+}}"#,
+        MEDIA_TYPE_IMAGE_INDEX
+    )
+}
+
+#[tokio::test]
+async fn test_list_referrers() {
+    init();
+    let mock_server = setup_mock_docker_api_server().await;
+
+    let manifest_digest = Digest::from_bytes(DOCKER_IMAGE_MANIFEST_BLOB.as_bytes());
+    Mock::given(method("HEAD"))
+        .and(path("/v2/library/fedora/manifests/latest"))
+        .respond_with(ResponseTemplate::new(200).insert_header(
+            "Docker-Content-Digest",
+            manifest_digest.to_string().as_str(),
+        ))
+        .mount(&mock_server)
+        .await;
+
+    // The Registry does not apply the filter, the client should.
+    Mock::given(method("GET"))
+        .and(path(format!(
+            "/v2/library/fedora/referrers/{}",
+            manifest_digest
+        )))
+        .and(header("Accept", MEDIA_TYPE_IMAGE_INDEX))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw(referrers_index().into_bytes(), MEDIA_TYPE_IMAGE_INDEX),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    // The filter is sent (encoded) in the query.
+    Mock::given(method("GET"))
+        .and(path(format!(
+            "/v2/library/fedora/referrers/{}",
+            manifest_digest
+        )))
+        .and(query_param("artifactType", "application/spdx+json"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw(referrers_index().into_bytes(), MEDIA_TYPE_IMAGE_INDEX),
+        )
+        .with_priority(1)
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let reference = format!("docker://{}/library/fedora", mock_server.address());
+    let referrers = list_referrers(&reference, None).await;
+    assert!(referrers.is_ok(), "{:?}", referrers);
+    assert_eq!(referrers.unwrap().len(), 2);
+
+    let referrers = list_referrers(&reference, Some("application/spdx+json"))
+        .await
+        .unwrap();
+    assert_eq!(referrers.len(), 1);
+    assert_eq!(
+        referrers[0].artifact_type.as_deref(),
+        Some("application/spdx+json")
+    );
+}
+
+#[tokio::test]
+async fn test_list_referrers_tag_schema() {
+    init();
+    let mock_server = setup_mock_docker_api_server().await;
+
+    // The Referrers API is not supported, the index is tagged with the digest instead.
+    let manifest_digest = Digest::from_bytes(DOCKER_IMAGE_MANIFEST_BLOB.as_bytes());
+    Mock::given(method("GET"))
+        .and(path(format!(
+            "/v2/library/fedora/referrers/{}",
+            manifest_digest
+        )))
+        .respond_with(ResponseTemplate::new(404))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(format!(
+            "/v2/library/fedora/manifests/sha256-{}",
+            manifest_digest.hex_digest()
+        )))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw(referrers_index().into_bytes(), MEDIA_TYPE_IMAGE_INDEX),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let reference = format!(
+        "docker://{}/library/fedora@{}",
+        mock_server.address(),
+        manifest_digest
+    );
+    let referrers = list_referrers(
+        &reference,
+        Some("application/vnd.dev.cosign.artifact.sig.v1+json"),
+    )
+    .await;
+    assert!(referrers.is_ok(), "{:?}", referrers);
+    let referrers = referrers.unwrap();
+    assert_eq!(referrers.len(), 1);
+    assert_eq!(
+        referrers[0].digest.to_string(),
+        "sha256:3c3a4604a545cdc127456d94e421cd355bca5b528f4a9c1905b15da2eb4a4c6b"
+    );
+
+    // Nothing attached to the image.
+    let reference = format!(
+        "docker://{}/library/fedora@{}",
+        mock_server.address(),
+        Digest::from_bytes(DOCKER_IMAGE_CONFIG_BLOB.as_bytes())
+    );
+    let referrers = list_referrers(&reference, None).await;
+    assert!(referrers.is_ok(), "{:?}", referrers);
+    assert!(referrers.unwrap().is_empty());
+}
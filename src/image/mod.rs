@@ -1,10 +1,12 @@
 //! Functionality for Handling Container Images
 //!
 //! # References
-//! - Tries to implement a functionality similar to the following `Go` library
+//!
+//! Tries to implement a functionality similar to the following `Go` library
 //! [Container Images Go library](https://github.com/containers/image/)
 
 pub mod api;
+pub(crate) mod archive;
 pub mod docker;
 pub mod manifest;
 pub mod oci;
@@ -0,0 +1,111 @@
+//! Implementation of OCI Archive specific ImageDestination
+
+use async_trait::async_trait;
+use tempfile::TempDir;
+use tokio::io::AsyncRead;
+
+use crate::image::{
+    oci::{digest::Digest, dst::OCIDestination, reference::OCIReference},
+    types::{errors::ImageResult, ImageDestination, ImageManifest, ImageReference},
+};
+
+use super::reference::OCIArchiveReference;
+
+/// OCIArchiveDestination structure. This structure implements `ImageDestination` trait for an OCI
+/// Image Layout inside a tar archive.
+///
+/// The image is written to an Image Layout in a temporary directory, which is archived to the
+/// path of the reference, when the destination is committed. An existing archive at the path is
+/// overwritten.
+#[derive(Debug)]
+pub(crate) struct OCIArchiveDestination {
+    pub(crate) reference: OCIArchiveReference,
+    tempdir: TempDir,
+    layout_dst: OCIDestination,
+}
+
+impl OCIArchiveDestination {
+    /// Returns a destination for the reference.
+    pub(crate) fn new(reference: &OCIArchiveReference) -> ImageResult<Self> {
+        let tempdir = tempfile::tempdir()?;
+        log::debug!(
+            "Using temporary directory {:?} for the archive {:?}.",
+            tempdir.path(),
+            reference.path
+        );
+
+        let layout_dst = OCIDestination::new(&OCIReference {
+            path: tempdir.path().to_path_buf(),
+            tag: reference.tag.clone(),
+        })?;
+
+        Ok(OCIArchiveDestination {
+            reference: reference.clone(),
+            tempdir,
+            layout_dst,
+        })
+    }
+}
+
+#[async_trait]
+impl ImageDestination for OCIArchiveDestination {
+    fn reference(&self) -> Box<dyn ImageReference> {
+        Box::new(self.reference.clone())
+    }
+
+    fn supports_digests(&self) -> bool {
+        self.layout_dst.supports_digests()
+    }
+
+    async fn has_blob(&self, digest: &Digest) -> ImageResult<bool> {
+        self.layout_dst.has_blob(digest).await
+    }
+
+    async fn put_blob(
+        &self,
+        blob: Box<dyn AsyncRead + Unpin + Send + Sync>,
+        digest: &Digest,
+        size: Option<u64>,
+    ) -> ImageResult<()> {
+        self.layout_dst.put_blob(blob, digest, size).await
+    }
+
+    async fn put_manifest(
+        &mut self,
+        manifest: &ImageManifest,
+        instance_digest: Option<&Digest>,
+    ) -> ImageResult<()> {
+        self.layout_dst
+            .put_manifest(manifest, instance_digest)
+            .await
+    }
+
+    async fn commit(&mut self) -> ImageResult<()> {
+        self.layout_dst.commit().await?;
+
+        log::debug!("Writing the archive {:?}.", self.reference.path);
+        let layout_path = self.tempdir.path().to_path_buf();
+        let archive_path = self.reference.path.clone();
+        tokio::task::spawn_blocking(move || -> std::io::Result<()> {
+            // An existing archive is replaced only once the new one is written completely.
+            let archive_dir = match archive_path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+                _ => std::path::PathBuf::from("."),
+            };
+            let archive = tempfile::Builder::new()
+                .permissions(std::os::unix::fs::PermissionsExt::from_mode(0o644))
+                .tempfile_in(archive_dir)?;
+            let mut builder = tar::Builder::new(archive);
+            builder.append_dir_all(".", layout_path)?;
+            let archive = builder.into_inner()?;
+            archive.as_file().sync_all()?;
+            archive.persist(&archive_path).map_err(|e| e.error)?;
+
+            Ok(())
+        })
+        .await
+        .map_err(std::io::Error::from)??;
+
+        Ok(())
+    }
+}
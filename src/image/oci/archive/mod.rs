@@ -0,0 +1,16 @@
+//! Implementation of 'oci-archive' Transport
+//!
+//! An 'oci-archive' is an [OCI Image Layout][oci_layout] in a single (uncompressed) tar file. The
+//! reference for the transport looks like `oci-archive:<path>[:<tag>]`. The images are read
+//! directly from the archive, without unpacking the archive. When writing images, the archive
+//! is (re)created with the single image written to it.
+//!
+//! [oci_layout]: https://github.com/opencontainers/image-spec/blob/master/image-layout.md
+
+pub(crate) mod dst;
+pub(crate) mod reference;
+pub(crate) mod source;
+pub mod transport;
+
+#[cfg(test)]
+mod tests;
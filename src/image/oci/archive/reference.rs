@@ -0,0 +1,71 @@
+//! Implementation of OCI Archive Image Reference
+
+use std::path::PathBuf;
+
+use crate::image::{
+    archive::TarArchive,
+    oci::{
+        image::OCIImage, layout::INDEX_JSON_FILENAME, reference::OCIReference, spec_v1::Index,
+        transport::TransportError,
+    },
+    types::{Image, ImageDestination, ImageReference, ImageResult, ImageSource, ImageTransport},
+};
+
+use super::{dst::OCIArchiveDestination, source::OCIArchiveSource, transport::OCIArchiveTransport};
+
+/// A structure implementing a Reference to an OCI Image Layout inside a tar archive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct OCIArchiveReference {
+    pub(crate) path: PathBuf,
+    pub(crate) tag: Option<String>,
+}
+
+impl OCIArchiveReference {
+    /// Parses the reference of the form `<path>[:<tag>]`. (Same as `OCIReference`.)
+    pub(crate) fn parse(reference: &str) -> Result<Self, TransportError> {
+        let OCIReference { path, tag } = OCIReference::parse(reference)?;
+
+        Ok(OCIArchiveReference { path, tag })
+    }
+}
+
+impl ImageReference for OCIArchiveReference {
+    fn transport(&self) -> Box<dyn ImageTransport + Send + Sync> {
+        Box::new(OCIArchiveTransport::new())
+    }
+
+    fn string_within_transport(&self) -> String {
+        match &self.tag {
+            Some(tag) => format!("{}:{}", self.path.display(), tag),
+            None => format!("{}", self.path.display()),
+        }
+    }
+
+    /// Returns an object implementing trait 'ImageSource' (in our case 'OCIArchiveSource').
+    fn new_image_source(&self) -> ImageResult<Box<dyn ImageSource + Send + Sync>> {
+        let archive = TarArchive::open(&self.path)?;
+        let index: Index =
+            serde_json::from_slice(&archive.read_entry_blocking(INDEX_JSON_FILENAME)?)?;
+
+        Ok(Box::new(OCIArchiveSource {
+            reference: self.clone(),
+            archive,
+            index,
+        }))
+    }
+
+    /// Returns an object implementing trait 'Image' in our case 'OCIImage'
+    fn new_image(&self) -> ImageResult<Box<dyn Image + Send + Sync>> {
+        let source = self.new_image_source()?;
+
+        Ok(Box::new(OCIImage {
+            source,
+            cfgblob: None,
+        }))
+    }
+
+    /// Returns an object implementing trait 'ImageDestination' (in our case 'OCIArchiveDestination').
+    fn new_image_destination(&self) -> ImageResult<Box<dyn ImageDestination + Send + Sync>> {
+        Ok(Box::new(OCIArchiveDestination::new(self)?))
+    }
+}
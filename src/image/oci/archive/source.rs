@@ -0,0 +1,105 @@
+//! Implementation of OCI Archive specific ImageSource
+
+use async_trait::async_trait;
+use tokio::io::AsyncRead;
+
+use crate::image::{
+    archive::TarArchive,
+    oci::{
+        digest::{Digest, DigestError, DigestReader},
+        layout::{manifest_descriptor_for_tag, tags_in_index, BLOBS_DIRNAME},
+        source::guess_manifest_mime_type,
+        spec_v1::Index,
+    },
+    types::{
+        errors::{ImageError, ImageResult},
+        ImageManifest, ImageReference, ImageSource,
+    },
+};
+
+use super::reference::OCIArchiveReference;
+
+/// OCIArchiveSource structure. This structure implements `ImageSource` trait for an OCI Image
+/// Layout inside a tar archive.
+#[derive(Debug)]
+pub(crate) struct OCIArchiveSource {
+    pub(crate) reference: OCIArchiveReference,
+    pub(crate) archive: TarArchive,
+    pub(crate) index: Index,
+}
+
+impl OCIArchiveSource {
+    fn blob_entry_name(digest: &Digest) -> String {
+        format!(
+            "{}/{}/{}",
+            BLOBS_DIRNAME,
+            digest.algorithm(),
+            digest.hex_digest()
+        )
+    }
+
+    async fn read_manifest_blob(&self, digest: &Digest) -> ImageResult<Vec<u8>> {
+        let manifest = self
+            .archive
+            .read_entry(&Self::blob_entry_name(digest))
+            .await?;
+
+        if Digest::from_bytes(&manifest) != *digest {
+            log::error!("Manifest blob for digest '{}' is corrupted.", digest);
+            return Err(ImageError::new().with(DigestError::InvalidDigest));
+        }
+
+        Ok(manifest)
+    }
+}
+
+#[async_trait]
+impl ImageSource for OCIArchiveSource {
+    fn reference(&self) -> Box<dyn ImageReference> {
+        Box::new(self.reference.clone())
+    }
+
+    async fn get_manifest(&mut self, digest: Option<&Digest>) -> ImageResult<ImageManifest> {
+        let (digest, mime_type) = match digest {
+            Some(digest) => (digest.clone(), None),
+            None => {
+                let descriptor = manifest_descriptor_for_tag(
+                    &self.index,
+                    self.reference.tag.as_deref(),
+                    self.archive.archive_path(),
+                )?;
+                (descriptor.digest, descriptor.mediatype)
+            }
+        };
+
+        log::trace!("Reading Manifest for Digest: {}", digest);
+        let manifest = self.read_manifest_blob(&digest).await?;
+        let mime_type = match mime_type {
+            Some(mime_type) => mime_type,
+            None => guess_manifest_mime_type(&manifest)?,
+        };
+
+        Ok(ImageManifest {
+            manifest,
+            mime_type,
+        })
+    }
+
+    async fn get_blob(
+        &self,
+        digest: &Digest,
+    ) -> ImageResult<Box<dyn AsyncRead + Unpin + Send + Sync>> {
+        log::debug!("Reading Blob: {}", digest);
+        let entry_name = Self::blob_entry_name(digest);
+
+        // Verified as it is read, a corrupted Blob fails the read at the end.
+        let reader = self.archive.entry_reader(&entry_name).await?;
+        let reader = DigestReader::new(reader, digest).map_err(|e| ImageError::new().with(e))?;
+
+        Ok(Box::new(reader))
+    }
+
+    async fn get_repo_tags(&self) -> ImageResult<Vec<String>> {
+        Ok(tags_in_index(&self.index))
+    }
+}
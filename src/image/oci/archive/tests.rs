@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+
+use tokio::io::AsyncReadExt;
+
+use crate::image::{
+    oci::{
+        digest::Digest,
+        spec_v1::{Manifest, MEDIA_TYPE_IMAGE_MANIFEST},
+        testdata::{create_test_image_layout, test_layer_blobs, TEST_LAYOUT_TAG},
+    },
+    transports,
+};
+
+// Creates an 'oci-archive' from the test Image Layout.
+async fn create_test_archive(path: &Path) -> PathBuf {
+    let layout_path = create_test_image_layout(path).await;
+
+    let archive_path = path.join("image.tar");
+    let mut builder = tar::Builder::new(std::fs::File::create(&archive_path).unwrap());
+    builder.append_dir_all(".", layout_path).unwrap();
+    builder.finish().unwrap();
+
+    archive_path
+}
+
+#[tokio::test]
+async fn test_get_manifest_and_blobs() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let archive_path = create_test_archive(tempdir.path()).await;
+
+    transports::init_transports();
+    let image_name = format!("oci-archive:{}:{}", archive_path.display(), TEST_LAYOUT_TAG);
+    let image_ref = transports::parse_image_name(&image_name);
+    assert!(image_ref.is_ok(), "{:?}", image_ref.err());
+
+    let mut image = image_ref.unwrap().new_image().unwrap();
+    let manifest = image.manifest().await.unwrap();
+    assert_eq!(manifest.mime_type, MEDIA_TYPE_IMAGE_MANIFEST);
+
+    let manifest: Manifest = serde_json::from_slice(&manifest.manifest).unwrap();
+    let mut layer = image
+        .source_ref()
+        .get_blob(&manifest.layers[0].digest)
+        .await
+        .unwrap();
+    let mut blob = Vec::new();
+    layer.read_to_end(&mut blob).await.unwrap();
+    assert_eq!(blob, test_layer_blobs().1);
+
+    let inspect = image.inspect().await.unwrap();
+    assert_eq!(inspect.architecture, "amd64");
+
+    let tags = image.source_ref().get_repo_tags().await.unwrap();
+    assert_eq!(tags, vec![TEST_LAYOUT_TAG.to_string()]);
+}
+
+#[tokio::test]
+async fn test_corrupted_blob_failure() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let layout_path = create_test_image_layout(tempdir.path()).await;
+
+    let layer_digest = Digest::from_bytes(&test_layer_blobs().1);
+    let blob_path = layout_path
+        .join("blobs")
+        .join(layer_digest.algorithm())
+        .join(layer_digest.hex_digest());
+    std::fs::write(blob_path, b"corrupted").unwrap();
+
+    let archive_path = tempdir.path().join("image.tar");
+    let mut builder = tar::Builder::new(std::fs::File::create(&archive_path).unwrap());
+    builder.append_dir_all(".", layout_path).unwrap();
+    builder.finish().unwrap();
+
+    transports::init_transports();
+    let image_name = format!("oci-archive:{}:{}", archive_path.display(), TEST_LAYOUT_TAG);
+    let image_ref = transports::parse_image_name(&image_name).unwrap();
+    let image = image_ref.new_image().unwrap();
+
+    // The Blob is verified as it is read.
+    let mut layer = image.source_ref().get_blob(&layer_digest).await.unwrap();
+    let mut blob = Vec::new();
+    assert!(layer.read_to_end(&mut blob).await.is_err());
+}
+
+#[tokio::test]
+async fn test_missing_archive_failure() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    transports::init_transports();
+    let image_name = format!("oci-archive:{}/missing.tar", tempdir.path().display());
+    let image_ref = transports::parse_image_name(&image_name).unwrap();
+
+    assert!(image_ref.new_image().is_err());
+}
@@ -0,0 +1,43 @@
+//! Implementation of OCI Archive Transport
+
+use std::boxed::Box;
+use std::string::String;
+
+use crate::image::types::{ImageReference, ImageResult, ImageTransport};
+
+use super::reference::OCIArchiveReference;
+
+pub(crate) static OCI_ARCHIVE_TRANSPORT_NAME: &str = "oci-archive";
+
+pub(in crate::image) fn get_oci_archive_transport(
+) -> (String, Box<dyn ImageTransport + Send + Sync>) {
+    (
+        String::from(OCI_ARCHIVE_TRANSPORT_NAME),
+        Box::new(OCIArchiveTransport::new()),
+    )
+}
+
+/// A Structure implementing OCI Archive Transport.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) struct OCIArchiveTransport {}
+
+impl OCIArchiveTransport {
+    pub(crate) fn new() -> Self {
+        OCIArchiveTransport {}
+    }
+}
+
+impl ImageTransport for OCIArchiveTransport {
+    fn name(&self) -> String {
+        String::from(OCI_ARCHIVE_TRANSPORT_NAME)
+    }
+
+    fn parse_reference(&self, reference: &str) -> ImageResult<Box<dyn ImageReference>> {
+        log::debug!("Parsing Reference '{}'", reference);
+        Ok(Box::new(OCIArchiveReference::parse(reference)?))
+    }
+
+    fn cloned(&self) -> Box<dyn ImageTransport + Send + Sync> {
+        Box::new(*self)
+    }
+}
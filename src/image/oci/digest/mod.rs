@@ -19,6 +19,9 @@ use serde::{Deserialize, Serialize};
 use sha2::{digest::DynDigest, Digest as ShaDigest, Sha256};
 use tokio::io::{AsyncRead, AsyncReadExt};
 
+mod reader;
+pub use reader::{DigestReader, DigestVerifier};
+
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub struct Digest {
     algorithm: String,
@@ -50,7 +53,23 @@ impl Digest {
         }
     }
 
-    fn digester(&self) -> Result<Box<dyn DynDigest + Send>, DigestError> {
+    /// Return a Sha256 Digest for all the bytes read from the given reader.
+    ///
+    /// Note: This is a blocking call.
+    pub fn from_reader<R>(reader: &mut R) -> std::io::Result<Self>
+    where
+        R: std::io::Read,
+    {
+        let mut hasher = Sha256::new();
+        std::io::copy(reader, &mut hasher)?;
+
+        Ok(Digest {
+            algorithm: "sha256".to_string(),
+            hex_digest: hex::encode(hasher.finalize()),
+        })
+    }
+
+    fn digester(&self) -> Result<Box<dyn DynDigest + Send + Sync>, DigestError> {
         match &*self.algorithm.to_lowercase() {
             "sha256" => Ok(Box::<sha2::Sha256>::default()),
             _ => Err(DigestError::AlgorithmNotSupported(
@@ -235,6 +254,17 @@ mod tests {
         assert!(res.is_err());
     }
 
+    #[test]
+    fn test_from_reader() {
+        let bytes = b"hello, intermodal".to_vec();
+
+        let d = Digest::from_reader(&mut bytes.as_slice());
+        assert_eq!(d.unwrap(), Digest::from_bytes(&bytes));
+
+        let d = Digest::from_reader(&mut "".as_bytes());
+        assert_eq!(d.unwrap(), Digest::default());
+    }
+
     #[tokio::test]
     async fn test_verify_success() {
         let s = String::from("");
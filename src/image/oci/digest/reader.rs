@@ -0,0 +1,163 @@
+//! Verifying the Digest of the data as it is read (or written)
+//!
+//! Using a `DigestReader` (or a `DigestVerifier`), the data is verified in the same pass in which
+//! it is consumed, instead of reading it once for verification and then again for using it.
+
+use std::io::{self, Write};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use sha2::digest::DynDigest;
+use tokio::io::{AsyncRead, ReadBuf};
+
+use super::{Digest, DigestError};
+
+/// Computes the Digest of the data written to it and verifies it against the expected `Digest`.
+pub struct DigestVerifier {
+    digest: Digest,
+    digester: Box<dyn DynDigest + Send + Sync>,
+}
+
+impl DigestVerifier {
+    /// Returns a `DigestVerifier` for the expected `digest`.
+    pub fn new(digest: &Digest) -> Result<Self, DigestError> {
+        Ok(DigestVerifier {
+            digest: digest.clone(),
+            digester: digest.digester()?,
+        })
+    }
+
+    /// Adds the `data` to the Digest being computed.
+    pub fn update(&mut self, data: &[u8]) {
+        self.digester.update(data);
+    }
+
+    /// Verifies the Digest of all the data added, against the expected `Digest`.
+    pub fn verify(self) -> io::Result<()> {
+        let computed = hex::encode(self.digester.finalize());
+        log::trace!(
+            "Hex Digest: {}, Computed: {}",
+            self.digest.hex_digest(),
+            computed
+        );
+
+        if computed != self.digest.hex_digest() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{} (Expected: {}, Computed: {}:{})",
+                    DigestError::InvalidDigest,
+                    self.digest,
+                    self.digest.algorithm(),
+                    computed
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl Write for DigestVerifier {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// An `AsyncRead` adaptor verifying the Digest of the data read from the wrapped reader.
+///
+/// Once the wrapped reader reaches the end, the Digest is verified and the read fails with an
+/// `InvalidData` error, if it does not match.
+pub struct DigestReader<R> {
+    reader: R,
+    verifier: Option<DigestVerifier>,
+}
+
+impl<R> DigestReader<R>
+where
+    R: AsyncRead + Unpin,
+{
+    /// Returns a `DigestReader` verifying the data read from `reader` against the `digest`.
+    pub fn new(reader: R, digest: &Digest) -> Result<Self, DigestError> {
+        Ok(DigestReader {
+            reader,
+            verifier: Some(DigestVerifier::new(digest)?),
+        })
+    }
+}
+
+impl<R> AsyncRead for DigestReader<R>
+where
+    R: AsyncRead + Unpin,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        let this = &mut *self;
+        match Pin::new(&mut this.reader).poll_read(cx, buf) {
+            Poll::Ready(Ok(())) => {
+                let read = &buf.filled()[before..];
+                if !read.is_empty() {
+                    if let Some(verifier) = this.verifier.as_mut() {
+                        verifier.update(read);
+                    }
+                } else if buf.remaining() > 0 {
+                    // End of the data, verified only once.
+                    if let Some(verifier) = this.verifier.take() {
+                        verifier.verify()?;
+                    }
+                }
+                Poll::Ready(Ok(()))
+            }
+            other => other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn test_digest_reader() {
+        let data = b"hello, intermodal".repeat(1000);
+        let digest = Digest::from_bytes(&data);
+
+        let mut reader = DigestReader::new(data.as_slice(), &digest).unwrap();
+        let mut output = vec![];
+        assert!(reader.read_to_end(&mut output).await.is_ok());
+        assert_eq!(output, data);
+
+        let mut reader = DigestReader::new(&data[1..], &digest).unwrap();
+        let result = reader.read_to_end(&mut vec![]).await;
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_digest_verifier() {
+        let digest = Digest::from_bytes(b"hello, intermodal");
+
+        let mut verifier = DigestVerifier::new(&digest).unwrap();
+        verifier.write_all(b"hello, ").unwrap();
+        verifier.update(b"intermodal");
+        assert!(verifier.verify().is_ok());
+
+        let mut verifier = DigestVerifier::new(&digest).unwrap();
+        verifier.update(b"hello");
+        assert!(verifier.verify().is_err());
+
+        let unsupported: Digest = "md5:deadbeef".parse().unwrap();
+        assert!(DigestVerifier::new(&unsupported).is_err());
+    }
+}
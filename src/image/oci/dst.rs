@@ -0,0 +1,135 @@
+//! Implementation of OCI Image Layout specific ImageDestination
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, BufReader};
+
+use crate::image::{
+    oci::{
+        digest::{Digest, DigestError, DigestReader},
+        layout::OCIImageLayout,
+        spec_v1::{Descriptor, ANNOTATION_REF_NAME},
+    },
+    types::{
+        errors::{ImageError, ImageResult},
+        ImageDestination, ImageManifest, ImageReference,
+    },
+};
+
+use super::reference::OCIReference;
+
+/// OCIDestination structure. This structure implements `ImageDestination` trait for an Image
+/// Layout.
+///
+/// Images are written to an existing layout at the path (if any), the manifest for the `tag` in
+/// the layout is replaced.
+#[derive(Debug)]
+pub(crate) struct OCIDestination {
+    pub(crate) reference: OCIReference,
+    pub(crate) layout: OCIImageLayout,
+}
+
+impl OCIDestination {
+    /// Returns a destination for the reference.
+    ///
+    /// Note: This is a blocking call.
+    pub(crate) fn new(reference: &OCIReference) -> ImageResult<Self> {
+        let layout = OCIImageLayout::open_or_new(&reference.path, reference.tag.as_deref())?;
+
+        Ok(OCIDestination {
+            reference: reference.clone(),
+            layout,
+        })
+    }
+}
+
+#[async_trait]
+impl ImageDestination for OCIDestination {
+    fn reference(&self) -> Box<dyn ImageReference> {
+        Box::new(self.reference.clone())
+    }
+
+    fn supports_digests(&self) -> bool {
+        true
+    }
+
+    async fn has_blob(&self, digest: &Digest) -> ImageResult<bool> {
+        Ok(tokio::fs::metadata(self.layout.blob_path(digest))
+            .await
+            .is_ok())
+    }
+
+    async fn put_blob(
+        &self,
+        blob: Box<dyn AsyncRead + Unpin + Send + Sync>,
+        digest: &Digest,
+        _size: Option<u64>,
+    ) -> ImageResult<()> {
+        log::debug!("Writing Blob: {}", digest);
+        // Verified as it is written, the blob is not moved in place if the digest does not match.
+        let reader = DigestReader::new(blob, digest).map_err(|e| ImageError::new().with(e))?;
+        let mut reader = BufReader::new(reader);
+        if let Err(e) = self.layout.write_blob_file(digest, &mut reader).await {
+            log::error!("Writing Blob {} failed: {}", digest, e);
+            return Err(e.into());
+        }
+
+        Ok(())
+    }
+
+    async fn put_manifest(
+        &mut self,
+        manifest: &ImageManifest,
+        instance_digest: Option<&Digest>,
+    ) -> ImageResult<()> {
+        let digest = Digest::from_bytes(&manifest.manifest);
+        if let Some(instance_digest) = instance_digest {
+            if *instance_digest != digest {
+                log::error!(
+                    "Manifest Digest: {}, does not match the Instance Digest: {}",
+                    digest,
+                    instance_digest
+                );
+                return Err(ImageError::new().with(DigestError::InvalidDigest));
+            }
+        }
+
+        log::debug!("Writing Manifest Blob: {}", digest);
+        let mut reader = BufReader::new(&*manifest.manifest);
+        self.layout.write_blob_file(&digest, &mut reader).await?;
+
+        // Instances of an index are referred to by the index itself.
+        if instance_digest.is_none() {
+            let annotations = self.reference.tag.as_ref().map(|tag| {
+                let mut annotations = HashMap::new();
+                annotations.insert(ANNOTATION_REF_NAME.to_string(), tag.clone());
+                annotations
+            });
+
+            self.layout.add_manifest_descriptor(Descriptor {
+                mediatype: Some(manifest.mime_type.clone()),
+                digest,
+                size: manifest.manifest.len() as i64,
+                urls: None,
+                platform: None,
+                annotations,
+                artifact_type: None,
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn commit(&mut self) -> ImageResult<()> {
+        self.layout.create_fs_path().await?;
+
+        log::debug!("Writing 'index.json'.");
+        self.layout.write_index_json().await?;
+
+        log::debug!("Writing 'oci-layout'.");
+        self.layout.write_image_layout().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+//! Implementation of a [`Image`][`crate::image::types::Image`] trait for OCI images.
+//!
+//! The `OCIImage` works with any `ImageSource` that returns OCI (or the equivalent Docker Schema2)
+//! manifests, so it can be used by all the transports that serve images from the local file
+//! system.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use tokio::io::AsyncReadExt;
+
+use crate::image::{
+    docker::{MEDIA_TYPE_DOCKER_V2_LIST, MEDIA_TYPE_DOCKER_V2_SCHEMA2_MANIFEST},
+    oci::spec_v1::{
+        Image as OCIv1Image, Index, Manifest, MEDIA_TYPE_IMAGE_INDEX, MEDIA_TYPE_IMAGE_MANIFEST,
+    },
+    platform::get_os_platform,
+    types::{
+        errors::{ImageError, ImageResult},
+        Image, ImageInspect, ImageManifest, ImageReference, ImageSource,
+    },
+};
+
+/// An `OCIImage` is an Image served by an `ImageSource` (like `OCISource`), with the resolved
+/// manifest in OCI Image Manifest (or Docker Schema2) format.
+#[derive(Debug)]
+pub struct OCIImage {
+    pub source: Box<dyn ImageSource + Send + Sync>,
+    pub cfgblob: Option<Vec<u8>>,
+}
+
+// `docker_version` is not a part of the OCI Image Config, but images converted from docker images
+// will usually have it.
+#[derive(Debug, Default, Deserialize)]
+struct DockerVersionOnly {
+    #[serde(default)]
+    docker_version: Option<String>,
+}
+
+impl OCIImage {
+    async fn manifest_for_our_os_arch(
+        &mut self,
+        original: &ImageManifest,
+    ) -> ImageResult<ImageManifest> {
+        let mime_type = original.mime_type.as_str();
+
+        log::debug!("Getting the Manifest for Current OS/Architecture");
+        match mime_type {
+            MEDIA_TYPE_IMAGE_MANIFEST | MEDIA_TYPE_DOCKER_V2_SCHEMA2_MANIFEST => {
+                log::trace!("Current Manifest is not an Index, So using it as it is!");
+                Ok(original.clone())
+            }
+            MEDIA_TYPE_IMAGE_INDEX | MEDIA_TYPE_DOCKER_V2_LIST => {
+                log::trace!("Found Image Index, Getting the actual manifest matching, OS/Platform");
+                let index: Index = serde_json::from_slice(&original.manifest)?;
+                let platform = get_os_platform();
+                for m in index.manifests.iter() {
+                    if let Some(p) = &m.platform {
+                        if p.architecture == platform.architecture && p.os == platform.os {
+                            log::trace!("Getting Manifest for Digest: {}", m.digest);
+                            return self.source.get_manifest(Some(&m.digest)).await;
+                        }
+                    }
+                }
+                log::error!("No Manifest found Matching Current OS/Platform!");
+                Err(ImageError::new())
+            }
+            _ => {
+                log::error!(
+                    "Media Type: {} found. Can't Read Manifest for this Media Type.",
+                    mime_type
+                );
+                Err(ImageError::new())
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Image for OCIImage {
+    fn reference(&self) -> Box<dyn ImageReference> {
+        self.source.reference()
+    }
+
+    fn source_ref(&self) -> &(dyn ImageSource + Send + Sync) {
+        self.source.as_ref()
+    }
+
+    fn source_mut(&mut self) -> &mut (dyn ImageSource + Send + Sync) {
+        self.source.as_mut()
+    }
+
+    async fn manifest(&mut self) -> ImageResult<ImageManifest> {
+        self.source.get_manifest(None).await
+    }
+
+    async fn resolved_manifest(&mut self) -> ImageResult<ImageManifest> {
+        let original = self.source.get_manifest(None).await?;
+
+        self.manifest_for_our_os_arch(&original).await
+    }
+
+    async fn config_blob(&mut self) -> ImageResult<Vec<u8>> {
+        if self.cfgblob.is_none() {
+            log::debug!("Config blob is not cached. Reading Config blob.");
+            let manifest = self.resolved_manifest().await?;
+            let manifest: Manifest = serde_json::from_slice(&manifest.manifest)?;
+            let mut cfgblob = self.source.get_blob(&manifest.config.digest).await?;
+
+            let mut blobvec = Vec::new();
+            cfgblob.read_to_end(&mut blobvec).await?;
+
+            self.cfgblob = Some(blobvec);
+        }
+        Ok(self.cfgblob.as_ref().unwrap().clone())
+    }
+
+    async fn oci_config(&mut self) -> ImageResult<OCIv1Image> {
+        Ok(serde_json::from_slice(&self.config_blob().await?)?)
+    }
+
+    async fn inspect(&mut self) -> ImageResult<ImageInspect> {
+        let manifest: Manifest = serde_json::from_slice(&self.resolved_manifest().await?.manifest)?;
+        let layers: Vec<String> = manifest
+            .layers
+            .iter()
+            .map(|l| l.digest.to_string())
+            .collect();
+
+        let cfgblob = self.config_blob().await?;
+        let image: OCIv1Image = serde_json::from_slice(&cfgblob)?;
+        let docker_version: DockerVersionOnly =
+            serde_json::from_slice(&cfgblob).unwrap_or_default();
+
+        let (labels, env) = match image.config {
+            Some(config) => (
+                config.labels.unwrap_or_default(),
+                config.env.unwrap_or_default(),
+            ),
+            None => (Default::default(), Default::default()),
+        };
+
+        Ok(ImageInspect {
+            created: image.created.map(|c| c.to_string()).unwrap_or_default(),
+            architecture: image.architecture,
+            docker_version: docker_version.docker_version.unwrap_or_default(),
+            os: image.os,
+            layers,
+            labels,
+            env,
+        })
+    }
+}
@@ -26,17 +26,19 @@ use tokio::{
     io::{self, AsyncRead, AsyncWriteExt, BufWriter},
 };
 
+use crate::image::types::errors::ImageError;
+
 use super::{
     digest::Digest,
-    spec_v1::{ImageLayout, Index},
+    spec_v1::{Descriptor, ImageLayout, Index, ANNOTATION_REF_NAME},
 };
 
-const OCI_LAYOUT_FILENAME: &str = "oci-layout";
-const INDEX_JSON_FILENAME: &str = "index.json";
-const BLOBS_DIRNAME: &str = "blobs";
+pub(crate) const OCI_LAYOUT_FILENAME: &str = "oci-layout";
+pub(crate) const INDEX_JSON_FILENAME: &str = "index.json";
+pub(crate) const BLOBS_DIRNAME: &str = "blobs";
 
 #[derive(Debug)]
-pub struct OCIImageLayoutError(String);
+pub struct OCIImageLayoutError(pub(crate) String);
 
 impl StdError for OCIImageLayoutError {}
 
@@ -58,6 +60,12 @@ impl From<serde_json::Error> for OCIImageLayoutError {
     }
 }
 
+impl From<OCIImageLayoutError> for ImageError {
+    fn from(e: OCIImageLayoutError) -> Self {
+        ImageError::new().with(e)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct OCIImageLayout {
     _name: String,
@@ -75,10 +83,10 @@ impl OCIImageLayout {
     {
         let mut image_path = PathBuf::from(path.as_ref());
 
-        if tag.is_none() {
-            image_path.push(name);
+        if let Some(tag) = tag {
+            image_path.push(format!("{}/{}", name, tag));
         } else {
-            image_path.push(format!("{}/{}", name, tag.unwrap()));
+            image_path.push(name);
         }
 
         let tag = tag.map(|t| t.to_string());
@@ -92,6 +100,70 @@ impl OCIImageLayout {
         }
     }
 
+    /// Opens an existing `OCIImageLayout` at the given path on the FS.
+    ///
+    /// The `oci-layout` and `index.json` files from the path are read. Unlike `new`, the `path`
+    /// is the path of the layout itself (ie. the directory containing `index.json`). If `tag` is
+    /// specified, it should be one of the `org.opencontainers.image.ref.name` annotations in the
+    /// `index.json`, which is checked when the manifest descriptor is looked up.
+    ///
+    /// Note: This is a blocking call, which is okay, since the files read are small.
+    pub fn open<P>(path: P, tag: Option<&str>) -> Result<Self, OCIImageLayoutError>
+    where
+        P: AsRef<Path>,
+    {
+        let image_path = PathBuf::from(path.as_ref());
+
+        let mut layout_file_path = image_path.clone();
+        layout_file_path.push(OCI_LAYOUT_FILENAME);
+        let layout: ImageLayout = serde_json::from_slice(&std::fs::read(&layout_file_path)?)?;
+
+        let mut index_json_path = image_path.clone();
+        index_json_path.push(INDEX_JSON_FILENAME);
+        let index: Index = serde_json::from_slice(&std::fs::read(&index_json_path)?)?;
+
+        log::debug!(
+            "Opened Image Layout at {:?} with {} manifest(s).",
+            image_path,
+            index.manifests.len()
+        );
+
+        Ok(OCIImageLayout {
+            _name: image_path.to_string_lossy().to_string(),
+            tag: tag.map(|t| t.to_string()),
+            image_path,
+            index,
+            layout,
+        })
+    }
+
+    /// Opens an existing `OCIImageLayout` at the given path or a new (empty) one if there's no
+    /// layout at the path.
+    ///
+    /// This is used when writing images to a layout, so that multiple images (with different
+    /// tags) can be written to the same layout. The new layout is not created on the FS.
+    ///
+    /// Note: This is a blocking call, which is okay, since the files read are small.
+    pub fn open_or_new<P>(path: P, tag: Option<&str>) -> Result<Self, OCIImageLayoutError>
+    where
+        P: AsRef<Path>,
+    {
+        let image_path = PathBuf::from(path.as_ref());
+
+        if image_path.join(INDEX_JSON_FILENAME).exists() {
+            return Self::open(path, tag);
+        }
+
+        log::debug!("No Image Layout at {:?}, using a new one.", image_path);
+        Ok(OCIImageLayout {
+            _name: image_path.to_string_lossy().to_string(),
+            tag: tag.map(|t| t.to_string()),
+            image_path,
+            index: Index::default(),
+            layout: ImageLayout::default(),
+        })
+    }
+
     /// Create the Layout on the FS
     ///
     /// Creates the underlying 'blobs' directory as well (As it is a required one.)
@@ -151,7 +223,9 @@ impl OCIImageLayout {
 
     /// Write a blob file
     ///
-    /// The digest specifies the <algorithm>/<filename> part
+    /// The digest specifies the <algorithm>/<filename> part. The blob is written to a temporary
+    /// file first and moved in place once written completely, so that an interrupted (or a failed)
+    /// write does not leave a partial blob in the layout.
     pub async fn write_blob_file<T>(
         &self,
         digest: &Digest,
@@ -164,18 +238,55 @@ impl OCIImageLayout {
         path.push(BLOBS_DIRNAME);
         path.push(digest.algorithm());
         if !path.exists() {
-            tokio::fs::create_dir(&path).await?;
+            tokio::fs::create_dir_all(&path).await?;
         }
 
+        let (file, tmp_path) = tempfile::Builder::new()
+            .prefix(".tmp-")
+            .permissions(std::os::unix::fs::PermissionsExt::from_mode(0o644))
+            .tempfile_in(&path)?
+            .into_parts();
         path.push(digest.hex_digest());
 
-        let mut file = File::create(&path).await?;
-
+        let mut file = File::from_std(file);
         io::copy(blob, &mut file).await?;
+        file.sync_all().await?;
+
+        tmp_path.persist(&path).map_err(|e| e.error)?;
 
         Ok(())
     }
 
+    /// Returns the path of the blob file for the given digest.
+    ///
+    /// The blob file may not exist, caller should check for existance if required.
+    pub fn blob_path(&self, digest: &Digest) -> PathBuf {
+        let mut path = self.image_path.clone();
+        path.push(BLOBS_DIRNAME);
+        path.push(digest.algorithm());
+        path.push(digest.hex_digest());
+
+        path
+    }
+
+    /// Opens a blob file for reading.
+    pub async fn read_blob_file(&self, digest: &Digest) -> Result<File, std::io::Error> {
+        File::open(self.blob_path(digest)).await
+    }
+
+    /// Returns the Descriptor for the manifest in the `index.json` matching the `tag`.
+    ///
+    /// If the layout was opened without a tag, the `index.json` should contain exactly one
+    /// manifest, which is returned.
+    pub fn manifest_descriptor(&self) -> Result<Descriptor, OCIImageLayoutError> {
+        manifest_descriptor_for_tag(&self.index, self.tag.as_deref(), &self.image_path)
+    }
+
+    /// Returns all the tags (`org.opencontainers.image.ref.name` annotations) in the `index.json`.
+    pub fn tags(&self) -> Vec<String> {
+        tags_in_index(&self.index)
+    }
+
     // Accessors
     #[inline(always)]
     pub fn tag(&self) -> Option<String> {
@@ -192,6 +303,29 @@ impl OCIImageLayout {
         self.index.clone()
     }
 
+    /// Adds the manifest `descriptor` to the index.
+    ///
+    /// Any existing manifest in the index with the same `org.opencontainers.image.ref.name`
+    /// annotation (or the same digest, if the `descriptor` does not have the annotation) is
+    /// replaced.
+    ///
+    /// Note: The updated index is not written to the disk, caller should explicitly write it to
+    /// disk.
+    pub fn add_manifest_descriptor(&mut self, descriptor: Descriptor) {
+        let ref_name = |d: &Descriptor| {
+            d.annotations
+                .as_ref()
+                .and_then(|a| a.get(ANNOTATION_REF_NAME).cloned())
+        };
+
+        let new_ref_name = ref_name(&descriptor);
+        self.index.manifests.retain(|m| match &new_ref_name {
+            Some(_) => ref_name(m) != new_ref_name,
+            None => ref_name(m).is_some() || m.digest != descriptor.digest,
+        });
+        self.index.manifests.push(descriptor);
+    }
+
     /// Updates the index consuming the passed index.
     ///
     /// Note: The updated index is not written to the disk, caller should explicitly write it to
@@ -201,6 +335,59 @@ impl OCIImageLayout {
     }
 }
 
+/// Returns the Descriptor for the manifest in the `index` matching the `tag`.
+///
+/// If `tag` is `None`, the `index` should contain exactly one manifest, which is returned. The
+/// `location` is used only for reporting errors.
+pub(crate) fn manifest_descriptor_for_tag<L>(
+    index: &Index,
+    tag: Option<&str>,
+    location: L,
+) -> Result<Descriptor, OCIImageLayoutError>
+where
+    L: std::fmt::Debug,
+{
+    match tag {
+        Some(tag) => {
+            for m in &index.manifests {
+                if let Some(annotations) = &m.annotations {
+                    if annotations.get(ANNOTATION_REF_NAME).map(|t| t.as_str()) == Some(tag) {
+                        return Ok(m.clone());
+                    }
+                }
+            }
+            Err(OCIImageLayoutError(format!(
+                "No manifest found for tag '{}' in {:?}.",
+                tag, location
+            )))
+        }
+        None => {
+            if index.manifests.len() == 1 {
+                Ok(index.manifests[0].clone())
+            } else {
+                Err(OCIImageLayoutError(format!(
+                    "Layout {:?} contains {} manifests, a tag is required.",
+                    location,
+                    index.manifests.len()
+                )))
+            }
+        }
+    }
+}
+
+/// Returns all the tags (`org.opencontainers.image.ref.name` annotations) in the `index`.
+pub(crate) fn tags_in_index(index: &Index) -> Vec<String> {
+    index
+        .manifests
+        .iter()
+        .filter_map(|m| {
+            m.annotations
+                .as_ref()
+                .and_then(|a| a.get(ANNOTATION_REF_NAME).cloned())
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -1,3 +1,20 @@
+//! OCI Image handling inside intermodal.
+//!
+//! References:
+//! [OCI Image Spec](https://github.com/opencontainers/image-spec)
+//! [OCI Layout Implementation](https://github.com/containers/image/tree/master/oci/layout)
+
+pub mod archive;
 pub mod digest;
+pub(crate) mod dst;
+pub mod image;
 pub(crate) mod layout;
+pub(crate) mod reference;
+pub(crate) mod source;
 pub mod spec_v1;
+pub mod transport;
+
+#[cfg(test)]
+pub(crate) mod testdata;
+#[cfg(test)]
+mod tests;
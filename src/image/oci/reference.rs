@@ -0,0 +1,91 @@
+//! Implementation of OCI Image Reference
+
+use std::path::PathBuf;
+
+use crate::image::types::{
+    Image, ImageDestination, ImageReference, ImageResult, ImageSource, ImageTransport,
+};
+
+use super::{
+    dst::OCIDestination, image::OCIImage, layout::OCIImageLayout, source::OCISource,
+    transport::OCITransport, transport::TransportError,
+};
+
+/// A structure implementing an OCI Image Layout Reference.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct OCIReference {
+    pub(crate) path: PathBuf,
+    pub(crate) tag: Option<String>,
+}
+
+impl OCIReference {
+    /// Parses the reference of the form `<path>[:<tag>]`.
+    ///
+    /// A ':' is treated as the tag separator only if the part following it does not contain a
+    /// '/', so that paths containing ':' can still be referred to.
+    pub(crate) fn parse(reference: &str) -> Result<Self, TransportError> {
+        let (path, tag) = match reference.rsplit_once(':') {
+            Some((path, tag)) if !tag.contains('/') => (path, Some(tag)),
+            _ => (reference, None),
+        };
+
+        if path.is_empty() {
+            return crate::log_err_return!(
+                TransportError,
+                "Path in the reference '{}' is empty.",
+                reference
+            );
+        }
+
+        if tag == Some("") {
+            return crate::log_err_return!(
+                TransportError,
+                "Tag in the reference '{}' is empty.",
+                reference
+            );
+        }
+
+        Ok(OCIReference {
+            path: PathBuf::from(path),
+            tag: tag.map(|t| t.to_string()),
+        })
+    }
+}
+
+impl ImageReference for OCIReference {
+    fn transport(&self) -> Box<dyn ImageTransport + Send + Sync> {
+        Box::new(OCITransport::new())
+    }
+
+    fn string_within_transport(&self) -> String {
+        match &self.tag {
+            Some(tag) => format!("{}:{}", self.path.display(), tag),
+            None => format!("{}", self.path.display()),
+        }
+    }
+
+    /// Returns an object implementing trait 'ImageSource' (in our case 'OCISource').
+    fn new_image_source(&self) -> ImageResult<Box<dyn ImageSource + Send + Sync>> {
+        let layout = OCIImageLayout::open(&self.path, self.tag.as_deref())?;
+
+        Ok(Box::new(OCISource {
+            reference: self.clone(),
+            layout,
+        }))
+    }
+
+    /// Returns an object implementing trait 'Image' in our case 'OCIImage'
+    fn new_image(&self) -> ImageResult<Box<dyn Image + Send + Sync>> {
+        let source = self.new_image_source()?;
+
+        Ok(Box::new(OCIImage {
+            source,
+            cfgblob: None,
+        }))
+    }
+
+    /// Returns an object implementing trait 'ImageDestination' (in our case 'OCIDestination').
+    fn new_image_destination(&self) -> ImageResult<Box<dyn ImageDestination + Send + Sync>> {
+        Ok(Box::new(OCIDestination::new(self)?))
+    }
+}
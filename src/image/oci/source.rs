@@ -0,0 +1,147 @@
+//! Implementation of OCI Image Layout specific ImageSource
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::image::{
+    oci::{
+        digest::{Digest, DigestError},
+        layout::OCIImageLayout,
+        spec_v1::{MEDIA_TYPE_IMAGE_INDEX, MEDIA_TYPE_IMAGE_MANIFEST},
+    },
+    types::{
+        errors::{ImageError, ImageResult},
+        ImageManifest, ImageReference, ImageSource,
+    },
+};
+
+use super::reference::OCIReference;
+
+/// OCISource structure. This structure implements `ImageSource` trait for an Image Layout.
+#[derive(Debug)]
+pub(crate) struct OCISource {
+    pub(crate) reference: OCIReference,
+    pub(crate) layout: OCIImageLayout,
+}
+
+// Only the fields required for determining the media type of a manifest blob.
+#[derive(Debug, Deserialize)]
+struct MediaTypeOnly {
+    #[serde(default, rename = "mediaType")]
+    media_type: Option<String>,
+
+    #[serde(default)]
+    manifests: Option<serde_json::Value>,
+}
+
+/// Determine the Media Type of the manifest blob from the blob itself.
+///
+/// The `mediaType` field is optional in OCI manifests, so if it's not present, a manifest having
+/// `manifests` is treated as an Image Index and an Image Manifest otherwise.
+pub(crate) fn guess_manifest_mime_type(manifest: &[u8]) -> ImageResult<String> {
+    let m: MediaTypeOnly = serde_json::from_slice(manifest)?;
+
+    Ok(match m.media_type {
+        Some(media_type) => media_type,
+        None => {
+            if m.manifests.is_some() {
+                MEDIA_TYPE_IMAGE_INDEX.to_string()
+            } else {
+                MEDIA_TYPE_IMAGE_MANIFEST.to_string()
+            }
+        }
+    })
+}
+
+impl OCISource {
+    async fn read_manifest_blob(&self, digest: &Digest) -> ImageResult<Vec<u8>> {
+        let mut f = self.layout.read_blob_file(digest).await?;
+
+        let mut manifest = Vec::new();
+        f.read_to_end(&mut manifest).await?;
+
+        if Digest::from_bytes(&manifest) != *digest {
+            log::error!("Manifest blob for digest '{}' is corrupted.", digest);
+            return Err(ImageError::new().with(DigestError::InvalidDigest));
+        }
+
+        Ok(manifest)
+    }
+}
+
+#[async_trait]
+impl ImageSource for OCISource {
+    fn reference(&self) -> Box<dyn ImageReference> {
+        Box::new(self.reference.clone())
+    }
+
+    async fn get_manifest(&mut self, digest: Option<&Digest>) -> ImageResult<ImageManifest> {
+        match digest {
+            Some(digest) => {
+                log::trace!("Reading Manifest for Digest: {}", digest);
+                let manifest = self.read_manifest_blob(digest).await?;
+                let mime_type = guess_manifest_mime_type(&manifest)?;
+
+                Ok(ImageManifest {
+                    manifest,
+                    mime_type,
+                })
+            }
+            None => {
+                let descriptor = self.layout.manifest_descriptor()?;
+                log::trace!("Reading Manifest for Descriptor: {:?}", descriptor);
+
+                let manifest = self.read_manifest_blob(&descriptor.digest).await?;
+                let mime_type = match descriptor.mediatype {
+                    Some(mime_type) => mime_type,
+                    None => guess_manifest_mime_type(&manifest)?,
+                };
+
+                Ok(ImageManifest {
+                    manifest,
+                    mime_type,
+                })
+            }
+        }
+    }
+
+    async fn get_blob(
+        &self,
+        digest: &Digest,
+    ) -> ImageResult<Box<dyn AsyncRead + Unpin + Send + Sync>> {
+        log::debug!("Reading Blob: {}", digest);
+        Ok(Box::new(self.layout.read_blob_file(digest).await?))
+    }
+
+    async fn get_repo_tags(&self) -> ImageResult<Vec<String>> {
+        Ok(self.layout.tags())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_guess_manifest_mime_type() {
+        let docker = r#"{"schemaVersion": 2, "mediaType": "application/vnd.docker.distribution.manifest.v2+json"}"#;
+        let index = r#"{"schemaVersion": 2, "manifests": []}"#;
+        let manifest = r#"{"schemaVersion": 2, "config": {}, "layers": []}"#;
+
+        assert_eq!(
+            guess_manifest_mime_type(docker.as_bytes()).unwrap(),
+            "application/vnd.docker.distribution.manifest.v2+json"
+        );
+        assert_eq!(
+            guess_manifest_mime_type(index.as_bytes()).unwrap(),
+            MEDIA_TYPE_IMAGE_INDEX
+        );
+        assert_eq!(
+            guess_manifest_mime_type(manifest.as_bytes()).unwrap(),
+            MEDIA_TYPE_IMAGE_MANIFEST
+        );
+        assert!(guess_manifest_mime_type(b"not json").is_err());
+    }
+}
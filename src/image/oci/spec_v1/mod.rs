@@ -16,7 +16,12 @@ pub struct Empty {}
 /// [godefs]: https://github.com/opencontainers/image-spec/blob/master/specs-go/v1/descriptor.go
 #[derive(PartialEq, Eq, Serialize, Deserialize, Debug, Clone)]
 pub struct Descriptor {
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        rename = "mediaType",
+        alias = "mediatype",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub mediatype: Option<String>,
 
     pub digest: Digest,
@@ -31,6 +36,15 @@ pub struct Descriptor {
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub annotations: Option<HashMap<String, String>>,
+
+    /// Type of the artifact, for the descriptors of the artifact manifests (eg. in the list of
+    /// referrers).
+    #[serde(
+        default,
+        rename = "artifactType",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub artifact_type: Option<String>,
 }
 
 /// Platform Struct used by the 'descriptor' struct above
@@ -118,6 +132,18 @@ pub struct Manifest {
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub annotations: Option<HashMap<String, String>>,
+
+    /// Type of the artifact, if the manifest is for an artifact (eg. an SBOM or a signature).
+    #[serde(
+        default,
+        rename = "artifactType",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub artifact_type: Option<String>,
+
+    /// The manifest this manifest refers to (eg. the image an SBOM is for).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<Descriptor>,
 }
 
 pub const MEDIA_TYPE_DESCRIPTOR: &str = "application/vnd.oci.descriptor.v1+json";
@@ -145,6 +171,9 @@ pub const MEDIA_TYPE_IMAGE_LAYER_NON_DISTRIBUTABLE_ZSTD: &str =
 
 pub const MEDIA_TYPE_IMAGE_CONFIG: &str = "application/vnd.oci.image.config.v1+json";
 
+/// Annotation used in an `index.json` of an Image Layout to record the 'tag' of an Image.
+pub const ANNOTATION_REF_NAME: &str = "org.opencontainers.image.ref.name";
+
 /// Image Config
 ///
 /// [Reference](https://github.com/opencontainers/image-spec/blob/master/config.md)
@@ -265,6 +294,30 @@ mod tests {
         assert!(parsed.is_ok(), "{}", parsed.err().unwrap());
     }
 
+    #[test]
+    fn test_artifact_manifest_ok() {
+        // Reference: https://github.com/opencontainers/image-spec/blob/main/manifest.md#guidelines-for-artifact-usage
+        let input = r##"{ "schemaVersion": 2, "mediaType": "application/vnd.oci.image.manifest.v1+json", "artifactType": "application/vnd.example.sbom.v1", "config": { "mediaType": "application/vnd.oci.empty.v1+json", "size": 2, "digest": "sha256:44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a" }, "layers": [ { "mediaType": "application/vnd.example.sbom.v1+json", "size": 1024, "digest": "sha256:9834876dcfb05cb167a5c24953eba58c4ac89b1adf57f28f2f9d09af107ee8f0" } ], "subject": { "mediaType": "application/vnd.oci.image.manifest.v1+json", "size": 7682, "digest": "sha256:5b0bcabd1ed22e9fb1310cf6c2dec7cdef19f0ad69efa1f392e94a4333501270" } }"##;
+        let parsed = serde_json::from_str::<Manifest>(input).unwrap();
+        assert_eq!(
+            parsed.artifact_type.as_deref(),
+            Some("application/vnd.example.sbom.v1")
+        );
+        assert_eq!(
+            parsed.subject.unwrap().digest.to_string(),
+            "sha256:5b0bcabd1ed22e9fb1310cf6c2dec7cdef19f0ad69efa1f392e94a4333501270"
+        );
+
+        // Not serialized if absent.
+        let manifest = Manifest {
+            artifact_type: None,
+            subject: None,
+            ..serde_json::from_str::<Manifest>(input).unwrap()
+        };
+        let serialized = serde_json::to_string(&manifest).unwrap();
+        assert!(!serialized.contains("artifactType") && !serialized.contains("subject"));
+    }
+
     #[test]
     fn test_image_config_ok() {
         // Reference: https://github.com/opencontainers/image-spec/blob/master/config.md
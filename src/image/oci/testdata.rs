@@ -0,0 +1,109 @@
+//! Test Data used for Testing - A small OCI Image Layout created on the FS.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::image::oci::{
+    digest::Digest,
+    layout::OCIImageLayout,
+    spec_v1::{
+        Descriptor, Index, Manifest, ANNOTATION_REF_NAME, MEDIA_TYPE_IMAGE_CONFIG,
+        MEDIA_TYPE_IMAGE_LAYER_GZIP, MEDIA_TYPE_IMAGE_MANIFEST,
+    },
+};
+
+pub(crate) const TEST_LAYOUT_NAME: &str = "testimage";
+pub(crate) const TEST_LAYOUT_TAG: &str = "latest";
+
+/// Returns a tar archive with a single file in it and it's gzipped version.
+pub(crate) fn test_layer_blobs() -> (Vec<u8>, Vec<u8>) {
+    let contents = b"Hello, intermodal!\n";
+
+    let mut builder = tar::Builder::new(Vec::new());
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, "hello.txt", &contents[..])
+        .unwrap();
+    let tarred = builder.into_inner().unwrap();
+
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&tarred).unwrap();
+    let gzipped = encoder.finish().unwrap();
+
+    (tarred, gzipped)
+}
+
+/// Returns an OCI Image Config with the `diff_id` for the given layer.
+pub(crate) fn test_config_blob(diff_id: &Digest) -> Vec<u8> {
+    serde_json::to_vec(&serde_json::json!({
+        "created": "2021-01-26T00:23:51.73608945Z",
+        "architecture": "amd64",
+        "os": "linux",
+        "config": {
+            "Env": ["PATH=/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin"],
+            "Labels": {"maintainer": "intermodal"}
+        },
+        "rootfs": {"type": "layers", "diff_ids": [diff_id]},
+        "history": [{"created": "2021-01-26T00:23:51.73608945Z"}]
+    }))
+    .unwrap()
+}
+
+fn descriptor(mediatype: &str, blob: &[u8]) -> Descriptor {
+    Descriptor {
+        mediatype: Some(mediatype.to_string()),
+        digest: Digest::from_bytes(blob),
+        size: blob.len() as i64,
+        urls: None,
+        platform: None,
+        annotations: None,
+        artifact_type: None,
+    }
+}
+
+/// Creates an OCI Image Layout with one image tagged as `TEST_LAYOUT_TAG` inside the `path`.
+///
+/// Returns the path of the Image Layout.
+pub(crate) async fn create_test_image_layout(path: &Path) -> PathBuf {
+    let (tarred, gzipped) = test_layer_blobs();
+    let config = test_config_blob(&Digest::from_bytes(&tarred));
+
+    let manifest = Manifest {
+        version: 2,
+        config: descriptor(MEDIA_TYPE_IMAGE_CONFIG, &config),
+        layers: vec![descriptor(MEDIA_TYPE_IMAGE_LAYER_GZIP, &gzipped)],
+        annotations: None,
+        artifact_type: None,
+        subject: None,
+    };
+    let manifest = serde_json::to_vec(&manifest).unwrap();
+
+    let mut layout = OCIImageLayout::new(TEST_LAYOUT_NAME, Some(TEST_LAYOUT_TAG), path);
+    layout.create_fs_path().await.unwrap();
+
+    for blob in [&config, &gzipped, &manifest] {
+        layout
+            .write_blob_file(&Digest::from_bytes(blob), &mut blob.as_slice())
+            .await
+            .unwrap();
+    }
+
+    let mut manifest_descriptor = descriptor(MEDIA_TYPE_IMAGE_MANIFEST, &manifest);
+    let mut annotations = HashMap::new();
+    annotations.insert(ANNOTATION_REF_NAME.to_string(), TEST_LAYOUT_TAG.to_string());
+    manifest_descriptor.annotations = Some(annotations);
+
+    layout.update_index(Index {
+        version: 2,
+        manifests: vec![manifest_descriptor],
+        annotations: None,
+    });
+    layout.write_index_json().await.unwrap();
+    layout.write_image_layout().await.unwrap();
+
+    layout.image_fs_path()
+}
@@ -0,0 +1,131 @@
+use tokio::io::AsyncReadExt;
+
+use super::{
+    digest::Digest,
+    spec_v1::{Manifest, MEDIA_TYPE_IMAGE_MANIFEST},
+    testdata::{create_test_image_layout, test_layer_blobs, TEST_LAYOUT_TAG},
+};
+use crate::image::transports;
+
+#[tokio::test]
+async fn test_get_manifest_success() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let layout_path = create_test_image_layout(tempdir.path()).await;
+
+    transports::init_transports();
+    let image_name = format!("oci:{}:{}", layout_path.display(), TEST_LAYOUT_TAG);
+    let image_ref = transports::parse_image_name(&image_name);
+    assert!(image_ref.is_ok(), "{:?}", image_ref.err());
+
+    let mut image = image_ref.unwrap().new_image().unwrap();
+    let manifest = image.manifest().await;
+    assert!(manifest.is_ok(), "{:?}", manifest.err());
+    assert_eq!(manifest.unwrap().mime_type, MEDIA_TYPE_IMAGE_MANIFEST);
+
+    let tags = image.source_ref().get_repo_tags().await;
+    assert_eq!(tags.unwrap(), vec![TEST_LAYOUT_TAG.to_string()]);
+}
+
+#[tokio::test]
+async fn test_no_tag_single_manifest_success() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let layout_path = create_test_image_layout(tempdir.path()).await;
+
+    transports::init_transports();
+    let image_name = format!("oci:{}", layout_path.display());
+    let mut image = transports::parse_image_name(&image_name)
+        .unwrap()
+        .new_image()
+        .unwrap();
+
+    assert!(image.manifest().await.is_ok());
+}
+
+#[tokio::test]
+async fn test_unknown_tag_failure() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let layout_path = create_test_image_layout(tempdir.path()).await;
+
+    transports::init_transports();
+    let image_name = format!("oci:{}:unknown", layout_path.display());
+    let mut image = transports::parse_image_name(&image_name)
+        .unwrap()
+        .new_image()
+        .unwrap();
+
+    assert!(image.manifest().await.is_err());
+}
+
+#[tokio::test]
+async fn test_missing_layout_failure() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    transports::init_transports();
+    let image_name = format!("oci:{}/missing:latest", tempdir.path().display());
+    let image_ref = transports::parse_image_name(&image_name).unwrap();
+
+    assert!(image_ref.new_image().is_err());
+}
+
+#[tokio::test]
+async fn test_get_blob_and_inspect() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let layout_path = create_test_image_layout(tempdir.path()).await;
+
+    transports::init_transports();
+    let image_name = format!("oci:{}:{}", layout_path.display(), TEST_LAYOUT_TAG);
+    let mut image = transports::parse_image_name(&image_name)
+        .unwrap()
+        .new_image()
+        .unwrap();
+
+    let manifest = image.resolved_manifest().await.unwrap();
+    let manifest: Manifest = serde_json::from_slice(&manifest.manifest).unwrap();
+
+    let mut layer = image
+        .source_ref()
+        .get_blob(&manifest.layers[0].digest)
+        .await
+        .unwrap();
+    let mut blob = Vec::new();
+    layer.read_to_end(&mut blob).await.unwrap();
+    assert_eq!(blob, test_layer_blobs().1);
+
+    let config = image.oci_config().await.unwrap();
+    assert_eq!(
+        config.rootfs.diff_ids,
+        vec![Digest::from_bytes(&test_layer_blobs().0)]
+    );
+
+    let inspect = image.inspect().await.unwrap();
+    assert_eq!(inspect.architecture, "amd64");
+    assert_eq!(inspect.os, "linux");
+    assert_eq!(inspect.layers, vec![manifest.layers[0].digest.to_string()]);
+    assert_eq!(inspect.labels.get("maintainer").unwrap(), "intermodal");
+}
+
+#[tokio::test]
+async fn test_put_blob_digest_mismatch() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    transports::init_transports();
+    let image_name = format!("oci:{}/layout:latest", tempdir.path().display());
+    let dest = transports::parse_image_name(&image_name)
+        .unwrap()
+        .new_image_destination()
+        .unwrap();
+
+    let (tarred, _) = test_layer_blobs();
+    let digest = Digest::from_bytes(&tarred);
+    let blob = Box::new(std::io::Cursor::new(b"corrupted".to_vec()));
+    assert!(dest.put_blob(blob, &digest, None).await.is_err());
+
+    // Nothing is left behind, that could be taken for the blob later.
+    assert!(!dest.has_blob(&digest).await.unwrap());
+    let blobs_dir = tempdir.path().join("layout/blobs").join(digest.algorithm());
+    assert_eq!(std::fs::read_dir(blobs_dir).unwrap().count(), 0);
+
+    let blob = Box::new(std::io::Cursor::new(tarred));
+    assert!(dest.put_blob(blob, &digest, None).await.is_ok());
+    assert!(dest.has_blob(&digest).await.unwrap());
+}
@@ -0,0 +1,134 @@
+//! Implementation of OCI Transport
+//!
+//! The OCI Transport reads images from an [OCI Image Layout][oci_layout] on the local file system.
+//! The reference for the transport looks like `oci:<path>[:<tag>]`, where the `tag` is matched
+//! against the `org.opencontainers.image.ref.name` annotation in the `index.json` of the layout.
+//!
+//! [oci_layout]: https://github.com/opencontainers/image-spec/blob/master/image-layout.md
+
+use std::boxed::Box;
+use std::error::Error as StdError;
+use std::fmt;
+use std::string::String;
+
+use crate::image::types::errors::ImageError;
+use crate::image::types::{ImageReference, ImageResult, ImageTransport};
+
+use super::reference::OCIReference;
+
+pub(crate) static OCI_TRANSPORT_NAME: &str = "oci";
+
+pub(in crate::image) fn get_oci_transport() -> (String, Box<dyn ImageTransport + Send + Sync>) {
+    (
+        String::from(OCI_TRANSPORT_NAME),
+        Box::new(OCITransport::new()),
+    )
+}
+
+/// A Structure implementing OCI Transport.
+///
+/// Like the `DockerTransport`, this structure does not have any fields.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) struct OCITransport {}
+
+impl OCITransport {
+    pub(crate) fn new() -> Self {
+        OCITransport {}
+    }
+}
+
+impl ImageTransport for OCITransport {
+    fn name(&self) -> String {
+        String::from(OCI_TRANSPORT_NAME)
+    }
+
+    fn parse_reference(&self, reference: &str) -> ImageResult<Box<dyn ImageReference>> {
+        log::debug!("Parsing Reference '{}'", reference);
+        Ok(Box::new(OCIReference::parse(reference)?))
+    }
+
+    fn cloned(&self) -> Box<dyn ImageTransport + Send + Sync> {
+        Box::new(*self)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct TransportError(pub(crate) String);
+
+impl fmt::Display for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "OCI Transport Error: {}", self.0)
+    }
+}
+
+impl StdError for TransportError {}
+
+impl From<TransportError> for ImageError {
+    fn from(e: TransportError) -> Self {
+        ImageError::new().with(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_parse_reference() {
+        struct ParseRefTC<'a> {
+            input: &'a str,
+            result: bool,
+            within_transport: &'a str,
+        }
+
+        let transport = OCITransport::new();
+        let test_cases = vec![
+            ParseRefTC {
+                input: "/tmp/fedora:latest",
+                result: true,
+                within_transport: "/tmp/fedora:latest",
+            },
+            ParseRefTC {
+                input: "/tmp/fedora",
+                result: true,
+                within_transport: "/tmp/fedora",
+            },
+            ParseRefTC {
+                input: "images/fedora:f33",
+                result: true,
+                within_transport: "images/fedora:f33",
+            },
+            ParseRefTC {
+                input: "/tmp/v1.0:2/fedora",
+                result: true,
+                within_transport: "/tmp/v1.0:2/fedora",
+            },
+            ParseRefTC {
+                input: "",
+                result: false,
+                within_transport: "",
+            },
+            ParseRefTC {
+                input: ":latest",
+                result: false,
+                within_transport: "",
+            },
+            ParseRefTC {
+                input: "/tmp/fedora:",
+                result: false,
+                within_transport: "",
+            },
+        ];
+
+        for tc in test_cases {
+            let result = transport.parse_reference(tc.input);
+            assert_eq!(result.is_ok(), tc.result, "{}", tc.input);
+
+            if let Ok(r) = result {
+                assert_eq!(r.transport().name(), "oci");
+                assert_eq!(r.string_within_transport(), tc.within_transport);
+            }
+        }
+    }
+}
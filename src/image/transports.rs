@@ -3,7 +3,10 @@ use std::sync::Mutex;
 
 use lazy_static::lazy_static;
 
-use super::docker::transport::get_docker_transport;
+use super::docker::{
+    archive::transport::get_docker_archive_transport, transport::get_docker_transport,
+};
+use super::oci::{archive::transport::get_oci_archive_transport, transport::get_oci_transport};
 use super::types::errors::ImageError;
 use super::types::{ImageReference, ImageResult, ImageTransport};
 
@@ -15,13 +18,18 @@ lazy_static! {
 /// A function that initializes all supported transports
 ///
 pub fn init_transports() {
-    // Right now only docker transport is supported, when we support additional transports, we will
-    // need to revisit the function to make sure that all transports can be properly obtained.
-    let (name, obj) = get_docker_transport();
-
-    {
+    // When we support additional transports, the `get_<name>_transport` function for the transport
+    // should be added below.
+    let transports = vec![
+        get_docker_transport(),
+        get_oci_transport(),
+        get_oci_archive_transport(),
+        get_docker_archive_transport(),
+    ];
+
+    let mut map = ALL_TRANSPORTS_MAP.lock().unwrap();
+    for (name, obj) in transports {
         log::debug!("Registering '{}' Transport.", name);
-        let mut map = ALL_TRANSPORTS_MAP.lock().unwrap();
         map.insert(name, obj);
     }
 }
@@ -46,9 +54,13 @@ pub fn parse_image_name<'a>(image_name: &'a str) -> ImageResult<Box<dyn ImageRef
         );
         let map = ALL_TRANSPORTS_MAP.lock().unwrap();
 
-        let transport = map.get(&transport_name).unwrap();
-
-        transport.parse_reference(reference_part)
+        match map.get(&transport_name) {
+            Some(transport) => transport.parse_reference(reference_part),
+            None => {
+                log::error!("Transport '{}' is not supported.", transport_name);
+                Err(ImageError::new())
+            }
+        }
     }
 }
 
@@ -56,7 +68,7 @@ pub fn parse_image_name<'a>(image_name: &'a str) -> ImageResult<Box<dyn ImageRef
 pub fn transport_from_image_name(
     image_name: &str,
 ) -> Option<Box<dyn ImageTransport + Send + Sync>> {
-    let tokens: Vec<&str> = image_name.split(':').collect();
+    let tokens: Vec<&str> = image_name.splitn(2, ':').collect();
 
     if tokens.len() != 2 {
         log::error!(
@@ -101,6 +113,15 @@ mod tests {
         assert!(result.is_some());
     }
 
+    #[test]
+    fn test_transport_from_image_name_oci_success() {
+        init_transports();
+        let result = transport_from_image_name("oci:/tmp/fedora:latest");
+
+        assert!(result.is_some());
+        assert_eq!(result.unwrap().name(), "oci");
+    }
+
     #[test]
     fn test_transport_from_image_name_failure() {
         init_transports();
@@ -135,6 +156,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_image_name_unknown_transport_failure() {
+        init_transports();
+        let result = parse_image_name("focker://fedora");
+
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_parse_image_name_failure() {
         init_transports();
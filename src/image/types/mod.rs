@@ -11,6 +11,7 @@ use std::boxed::Box;
 use std::collections::HashMap;
 
 use async_trait::async_trait;
+use futures_util::stream::{self, BoxStream, StreamExt, TryStreamExt};
 use serde::Serialize;
 use tokio::io::AsyncRead;
 
@@ -35,7 +36,6 @@ pub trait ImageTransport: std::fmt::Debug {
     // Hashmap, and then we'll have to return clone of the value in the HashMap. The additional
     // `Sync` and `Send` requirements are because the HashMap is protected by a Mutex (being a
     // global variable).
-
     fn cloned(&self) -> Box<dyn ImageTransport + Send + Sync>;
 }
 
@@ -69,12 +69,21 @@ pub trait ImageReference: std::fmt::Debug {
         None
     }
 
+    /// Returns an Image Destination for the Reference or an Error.
+    ///
+    /// Not all transports support writing images, for such transports an Error is returned.
+    fn new_image_destination(&self) -> ImageResult<Box<dyn ImageDestination + Send + Sync>> {
+        log::error!(
+            "Transport '{}' does not support writing images.",
+            self.transport().name()
+        );
+        Err(errors::ImageError::new())
+    }
+
     // FIXME: implement following methods
     // fn policy_configuration_identity(&self) -> String;
 
     // fn policy_configuration_namespaces(&self) -> Vec<String>;
-
-    // fn new_image_destination(&self) -> Result
 }
 
 /// A trait that should be implemented by All Image Sources.
@@ -112,6 +121,79 @@ pub trait ImageSource: std::fmt::Debug {
     /// Get's all tags corresponding to this Image Source. Note: Right now this makes sense only
     /// for the 'docker' Image sources, for other image sources, simply return an Empty List.
     async fn get_repo_tags(&self) -> ImageResult<Vec<String>>;
+
+    /// Get a stream of all tags for this Image source
+    ///
+    /// Useful for the repositories with a large number of tags, where the tags are fetched a page
+    /// at a time (of at most `page_size` tags, if specified) as the stream is consumed. Only the
+    /// tags after the tag `last` (in the lexical order) are streamed, if specified.
+    ///
+    /// The default implementation simply streams the tags returned by `get_repo_tags` (ignoring
+    /// the `page_size`).
+    fn get_repo_tags_stream<'a>(
+        &'a self,
+        _page_size: Option<usize>,
+        last: Option<&'a str>,
+    ) -> BoxStream<'a, ImageResult<String>> {
+        stream::once(self.get_repo_tags())
+            .map_ok(move |tags| {
+                let tags = tags
+                    .into_iter()
+                    .filter(move |tag| last.is_none_or(|last| tag.as_str() > last));
+                stream::iter(tags.map(Ok))
+            })
+            .try_flatten()
+            .boxed()
+    }
+}
+
+/// A trait that should be implemented by All Image Destinations.
+///
+/// An ImageDestination is the counterpart of an `ImageSource` and is used for writing images to
+/// a transport. The blobs (config and layers) of an image are put first followed by the
+/// manifest(s) and finally the destination is `commit`ed.
+#[async_trait]
+pub trait ImageDestination: std::fmt::Debug {
+    /// Returns a Reference corresponding to this particular ImageDestination.
+    fn reference(&self) -> Box<dyn ImageReference>;
+
+    /// Whether the destination supports referring to the manifests by their Digests.
+    ///
+    /// If the destination does not support this, only a single (resolved) manifest can be put to
+    /// the destination.
+    fn supports_digests(&self) -> bool;
+
+    /// Returns whether a blob with the given `Digest` is already present at the destination.
+    ///
+    /// This is used to avoid uploading blobs that are already present at the destination.
+    async fn has_blob(&self, digest: &Digest) -> ImageResult<bool>;
+
+    /// Puts a blob to the destination.
+    ///
+    /// The contents of the blob are read from `blob` and verified against the `digest`. `size`
+    /// is the size of the blob if it is known in advance.
+    async fn put_blob(
+        &self,
+        blob: Box<dyn AsyncRead + Unpin + Send + Sync>,
+        digest: &Digest,
+        size: Option<u64>,
+    ) -> ImageResult<()>;
+
+    /// Puts the manifest to the destination.
+    ///
+    /// If the `instance_digest` is None, the manifest is put for the reference this destination
+    /// points to. Otherwise, the manifest is an instance of a 'list' or 'index' manifest (which
+    /// should be put later) and is referred to by the `instance_digest`.
+    async fn put_manifest(
+        &mut self,
+        manifest: &ImageManifest,
+        instance_digest: Option<&Digest>,
+    ) -> ImageResult<()>;
+
+    /// Commits the image written to the destination.
+    ///
+    /// Until an image is committed, it may not be visible at the destination.
+    async fn commit(&mut self) -> ImageResult<()>;
 }
 
 /// A trait that should be implemented by all Images.
@@ -123,6 +205,10 @@ pub trait Image: std::fmt::Debug {
     /// Underlying 'image source'
     fn source_ref(&self) -> &(dyn ImageSource + Send + Sync);
 
+    /// Underlying 'image source' (mutable, eg. for getting the manifests of the instances in a
+    /// list).
+    fn source_mut(&mut self) -> &mut (dyn ImageSource + Send + Sync);
+
     /// Reference of the 'image source'.
     fn reference(&self) -> Box<dyn ImageReference>;
 
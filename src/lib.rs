@@ -18,6 +18,18 @@
 //!
 //! Initial target is for Linux systems mainly.
 
+// Lints reported by the newer `clippy` in the existing tests, which are left as they are.
+#![cfg_attr(
+    test,
+    allow(
+        clippy::bool_assert_comparison,
+        clippy::needless_borrow,
+        clippy::single_char_add_str,
+        clippy::to_string_in_format_args,
+        clippy::unnecessary_unwrap
+    )
+)]
+
 pub mod cmd;
 pub mod image;
 pub mod storage;
@@ -1,7 +1,7 @@
 //! Functionality related to handling 'overlay' file-system
 
 use std::ffi::CString;
-use std::io::{BufReader, Write};
+use std::io::{BufRead, BufReader, Read, Write};
 use std::path::{Path, PathBuf};
 
 use crate::{image::oci::digest::Digest, utils::storage_root_for_fs};
@@ -12,6 +12,8 @@ const WHITEOUT_OPAQUE: &str = ".wh..wh..opq";
 const XATTR_OVERLAY_FS_OPAQUE_KEY: &str = "trusted.overlay.opaque";
 const XATTR_OVERLAY_FS_OPAQUE_VAL: &[u8; 1] = b"y";
 
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
 /// Returns the Path to the 'layers' directory.
 pub fn layers_base_path() -> std::io::Result<PathBuf> {
     let mut layers_base_path = storage_root_for_fs("overlay")?;
@@ -25,7 +27,7 @@ pub fn layers_base_path() -> std::io::Result<PathBuf> {
 /// 'apply' the given layer to the FS path.
 ///
 /// For the 'overlay' filesystem, this involves, extracting the tar files and handling the
-/// whiteouts.
+/// whiteouts. The layer may be a gzipped or an uncompressed tar file.
 pub fn apply_layer<P: AsRef<Path> + std::fmt::Debug>(
     digest: &Digest,
     layer: P,
@@ -72,9 +74,14 @@ pub fn apply_layer<P: AsRef<Path> + std::fmt::Debug>(
     }
 
     log::trace!("Applying entries in the Layer Tar!");
-    let reader = BufReader::new(std::fs::File::open(layer)?);
-    let gz_reader = flate2::bufread::GzDecoder::new(reader);
-    let mut tar_reader = tar::Archive::new(gz_reader);
+    let mut reader = BufReader::new(std::fs::File::open(layer)?);
+    let reader: Box<dyn Read> = if reader.fill_buf()?.starts_with(&GZIP_MAGIC) {
+        Box::new(flate2::bufread::GzDecoder::new(reader))
+    } else {
+        log::trace!("Layer is not gzipped, treating it as an uncompressed tar.");
+        Box::new(reader)
+    };
+    let mut tar_reader = tar::Archive::new(reader);
 
     let entries = tar_reader.entries()?;
 
@@ -160,6 +167,38 @@ mod tests {
         pull_container_image("docker://busybox:1.32", to_path, false, true).await
     }
 
+    #[test]
+    fn test_apply_uncompressed_layer() {
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let contents = b"uncompressed";
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "etc/hello", &contents[..])
+            .unwrap();
+        let layer = builder.into_inner().unwrap();
+
+        let layer_path = tempdir.path().join("layer.tar");
+        std::fs::write(&layer_path, &layer).unwrap();
+
+        let digest = Digest::from_bytes(&layer);
+        let base_path = tempdir.path().join("layers");
+        let r = apply_layer(&digest, &layer_path, Some(&base_path), "");
+        assert!(r.is_ok(), "{:#?}", r.err());
+
+        let mut applied = base_path.clone();
+        applied.push(format!(
+            "{}/{}/diff/etc/hello",
+            digest.algorithm(),
+            digest.hex_digest()
+        ));
+        assert_eq!(std::fs::read(applied).unwrap(), contents);
+    }
+
     #[tokio::test]
     async fn test_apply_layer() {
         // Pull the image
@@ -39,6 +39,27 @@ pub fn image_blobs_cache_root() -> std::io::Result<PathBuf> {
     Ok(blobs_cache_dir)
 }
 
+/// Get's the path of the file the locations of the blobs pushed to (or pulled from) the Registries
+/// are saved in.
+///
+/// The locations are used as the source repositories for mounting the blobs (instead of uploading
+/// them again) when they are pushed to other repositories in the same Registry.
+pub fn blob_locations_cache_path() -> std::io::Result<PathBuf> {
+    let mut cache_dir = match ProjectDirs::from(QUALIFIER, ORGANIZATION, APPLICATION) {
+        Some(p) => PathBuf::from(p.cache_dir()),
+        None => std::env::temp_dir(),
+    };
+
+    if !cache_dir.exists() {
+        log::debug!("The Parent Cache directory does not exist. Creating.");
+        std::fs::create_dir_all(&cache_dir)?;
+    }
+
+    cache_dir.push("blob-locations.json");
+
+    Ok(cache_dir)
+}
+
 /// Get's the Local Path for OCI Images.
 ///
 /// Local images are stored in a directory on the FS. The images are stored using a Layout
@@ -87,10 +108,7 @@ pub fn storage_root_for_fs(fs: &str) -> std::io::Result<PathBuf> {
     storage_root_dir.push(fs);
 
     if !storage_root_dir.exists() {
-        log::debug!(
-            "{}",
-            format!("Creating Storage Root directory for : {}", fs)
-        );
+        log::debug!("Creating Storage Root directory for : {}", fs);
         std::fs::create_dir_all(&storage_root_dir)?;
     }
 